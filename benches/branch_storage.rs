@@ -0,0 +1,99 @@
+//! Lookup time and memory of the sparse `BTreeMap` a `Branch` keeps its
+//! occupied slots in, against a dense 256 slot array, across occupancy
+//! levels, next to the time a whole `Trie` lookup takes. Run with `cargo
+//! bench --bench branch_storage`.
+
+use mmpt::node::{Leaf, Node};
+use mmpt::trie::Trie;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// The system allocator, counting the bytes allocated.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Returns what `build` returns, along with the bytes it holds on to.
+fn allocated<T>(build: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let built = build();
+    (built, ALLOCATED.load(Ordering::Relaxed) - before)
+}
+
+const LOOKUPS: usize = 1_000_000;
+
+type Slot = Node<Vec<u8>>;
+
+/// Returns the time taken per call of `lookup` on each of `nibbles`, cycled
+/// through `LOOKUPS` times.
+fn per_lookup(nibbles: &[u8], mut lookup: impl FnMut(u8) -> bool) -> Duration {
+    let start = Instant::now();
+    let mut found = 0;
+    for nibble in nibbles.iter().cycle().take(LOOKUPS) {
+        found += usize::from(lookup(black_box(*nibble)));
+    }
+    black_box(found);
+    start.elapsed() / LOOKUPS as u32
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(431);
+    let mut probes: Vec<u8> = (0..=255).collect();
+    probes.shuffle(&mut rng);
+
+    println!("slot size: {} bytes", size_of::<Slot>());
+    println!("{:>9} {:>12} {:>12} {:>14} {:>14}", "occupied", "sparse", "dense", "sparse memory", "dense memory");
+    for occupied in [2usize, 4, 16, 64, 128, 256] {
+        let mut nibbles: Vec<u8> = (0..=255).collect();
+        nibbles.shuffle(&mut rng);
+        nibbles.truncate(occupied);
+
+        let (sparse, sparse_memory) = allocated(|| nibbles.iter().map(|nibble| (*nibble, Node::None)).collect::<BTreeMap<u8, Slot>>());
+        let (dense, dense_memory) = allocated(|| {
+            let mut dense: Box<[Option<Slot>; 256]> = Box::new(std::array::from_fn(|_| None));
+            for nibble in &nibbles {
+                dense[*nibble as usize] = Some(Node::None);
+            }
+            dense
+        });
+
+        let sparse_time = per_lookup(&probes, |nibble| sparse.contains_key(&nibble));
+        let dense_time = per_lookup(&probes, |nibble| dense[nibble as usize].is_some());
+        println!("{:>9} {:>12?} {:>12?} {:>14} {:>14}", occupied, sparse_time, dense_time, sparse_memory, dense_memory);
+    }
+
+    for leaves in [100_000, 1_000_000] {
+        let addresses: Vec<[u8; 32]> = (0..leaves).map(|_| rng.gen()).collect();
+        let mut trie: Trie<Vec<u8>> = Trie::default();
+        trie.add_many(addresses.iter().map(|address| Leaf::new(*address, address.to_vec()))).unwrap();
+        let start = Instant::now();
+        for address in addresses.iter().cycle().take(LOOKUPS) {
+            black_box(trie.contains(black_box(address)));
+        }
+        let lookup = start.elapsed() / LOOKUPS as u32;
+        // The forks are the branches below the `Root` one.
+        let branches = 1 + trie.iter_forks().count();
+        let dense = 1 + trie.iter_forks().filter(|(_, _, occupied)| *occupied > 128).count();
+        println!("{} leaves: Trie::contains {:?}, {} of {} branches hold more than 128 slots", leaves, lookup, dense, branches);
+    }
+}
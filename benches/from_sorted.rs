@@ -0,0 +1,44 @@
+//! Serial `Trie::from_sorted` vs parallel `Trie::from_sorted_par` on a
+//! million leaves. Run with `cargo bench --features rayon --bench
+//! from_sorted`.
+
+use mmpt::node::Leaf;
+use mmpt::trie::Trie;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+const LEAVES: usize = 1_000_000;
+const RUNS: u32 = 3;
+
+type Leaves = Vec<Leaf<Vec<u8>>>;
+
+/// Returns the fastest of `RUNS` builds of a `Trie` from `leaves` by `build`,
+/// along with its `Root` hash.
+fn fastest(leaves: &Leaves, build: fn(Leaves) -> Trie<Vec<u8>>) -> (Duration, [u8; 32]) {
+    let mut best = Duration::MAX;
+    let mut root = [0u8; 32];
+    for _ in 0..RUNS {
+        let leaves = leaves.clone();
+        let start = Instant::now();
+        let mut trie = build(leaves);
+        best = best.min(start.elapsed());
+        root = trie.root_hash();
+    }
+    (best, root)
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(412);
+    let mut addresses: Vec<[u8; 32]> = (0..LEAVES).map(|_| rng.gen()).collect();
+    addresses.sort_unstable();
+    addresses.dedup();
+    let leaves: Leaves = addresses.iter().map(|address| Leaf::new(*address, address.to_vec())).collect();
+
+    let (serial, serial_root) = fastest(&leaves, |leaves| Trie::from_sorted(leaves).unwrap());
+    let (parallel, parallel_root) = fastest(&leaves, |leaves| Trie::from_sorted_par(leaves).unwrap());
+    assert_eq!(serial_root, parallel_root);
+
+    println!("{} leaves, fastest of {} runs, {} threads", leaves.len(), RUNS, rayon::current_num_threads());
+    println!("from_sorted:      {:?}", serial);
+    println!("from_sorted_par:  {:?}", parallel);
+}
@@ -0,0 +1,65 @@
+//! Cold vs warm lookup latency of a `Trie` committed to a `NodeStore`,
+//! without and with `CachedStore::prefetch`. Run with `cargo bench --bench
+//! prefetch`.
+
+use mmpt::hash::Sha256Algorithm;
+use mmpt::node::Leaf;
+use mmpt::store::{CachedStore, HashMapStore, NodeStore};
+use mmpt::trie::Trie;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+const LEAVES: usize = 100_000;
+const LOOKUPS: usize = 1_000;
+/// The latency of a single read from `SlowStore`, standing in for a disk.
+const READ_LATENCY: Duration = Duration::from_micros(100);
+
+/// A `HashMapStore` taking `READ_LATENCY` to answer every read.
+struct SlowStore(HashMapStore);
+
+impl NodeStore for SlowStore {
+    fn get(&self, hash: &[u8; 32]) -> Option<Vec<u8>> {
+        let start = Instant::now();
+        while start.elapsed() < READ_LATENCY {
+            std::hint::spin_loop();
+        }
+        NodeStore::<Sha256Algorithm>::get(&self.0, hash)
+    }
+
+    fn put(&mut self, hash: [u8; 32], bytes: Vec<u8>) {
+        NodeStore::<Sha256Algorithm>::put(&mut self.0, hash, bytes);
+    }
+}
+
+/// Returns the time taken per lookup of every address of `addresses`.
+fn per_lookup(root: [u8; 32], store: &CachedStore<SlowStore>, addresses: &[[u8; 32]]) -> Duration {
+    let start = Instant::now();
+    for address in addresses {
+        assert!(Trie::<Vec<u8>>::try_get(root, store, address).unwrap().is_some());
+    }
+    start.elapsed() / addresses.len() as u32
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(433);
+    let addresses: Vec<[u8; 32]> = (0..LEAVES).map(|_| rng.gen()).collect();
+    let mut trie: Trie<Vec<u8>> = Trie::default();
+    trie.add_many(addresses.iter().map(|address| Leaf::new(*address, address.to_vec()))).unwrap();
+    let mut store = SlowStore(HashMapStore::with_capacity(3 * LEAVES));
+    let root = trie.commit(&mut store);
+    let access_list = &addresses[..LOOKUPS];
+
+    let cold: CachedStore<_> = CachedStore::new(store);
+    let cold_lookup = per_lookup(root, &cold, access_list);
+
+    let warm: CachedStore<_> = CachedStore::new(cold.into_inner());
+    let start = Instant::now();
+    warm.prefetch(&root, access_list).unwrap();
+    let prefetch = start.elapsed();
+    let warm_lookup = per_lookup(root, &warm, access_list);
+
+    println!("{} leaves, {} lookups, {:?} per store read", LEAVES, LOOKUPS, READ_LATENCY);
+    println!("cold lookup:  {:?}", cold_lookup);
+    println!("prefetch:     {:?} ({} records)", prefetch, warm.cached());
+    println!("warm lookup:  {:?}", warm_lookup);
+}
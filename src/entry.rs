@@ -0,0 +1,190 @@
+use crate::hash::{Hasher, Sha256Algorithm};
+use crate::node::{Address, Leaf};
+use crate::trie::Trie;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::ops::{Deref, DerefMut};
+
+/// A view into a single address of a `Trie`, which may or may not hold a
+/// `Leaf`, as returned by `Trie::entry`.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+///
+/// let mut trie: Trie<Vec<u8>> = Trie::default();
+/// trie.entry([0u8; 32]).or_insert(vec![1]);
+/// trie.entry([0u8; 32]).and_modify(|payload| payload.push(2)).or_insert(vec![]);
+///
+/// assert_eq!(*trie.entry([0u8; 32]).or_insert(vec![]), vec![1, 2]);
+/// ```
+#[derive(Debug)]
+pub enum Entry<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    Occupied(OccupiedEntry<'a, P, H, N>),
+    Vacant(VacantEntry<'a, P, H, N>),
+}
+
+/// An `Entry` at an address holding a `Leaf`.
+#[derive(Debug)]
+pub struct OccupiedEntry<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    trie: &'a mut Trie<P, H, N>,
+    address: Address<N>,
+}
+
+/// An `Entry` at an address holding no `Leaf`.
+#[derive(Debug)]
+pub struct VacantEntry<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    trie: &'a mut Trie<P, H, N>,
+    address: Address<N>,
+}
+
+/// A mutable borrow of the payload of a `Leaf`, as returned by the `Entry`
+/// methods. Since the hashes on the path of the `Leaf` commit to its payload,
+/// the payload is written back, and every hash on the path recomputed, when
+/// the `PayloadMut` is dropped.
+#[derive(Debug)]
+pub struct PayloadMut<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    trie: &'a mut Trie<P, H, N>,
+    address: Address<N>,
+    payload: Option<P>,
+}
+
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Entry<'a, P, H, N> {
+    pub(crate) fn new(trie: &'a mut Trie<P, H, N>, address: Address<N>) -> Entry<'a, P, H, N> {
+        if trie.contains(&address) {
+            Entry::Occupied(OccupiedEntry { trie, address })
+        } else {
+            Entry::Vacant(VacantEntry { trie, address })
+        }
+    }
+
+    /// Returns the address of this `Entry`.
+    pub fn address(&self) -> &Address<N> {
+        match self {
+            Entry::Occupied(entry) => &entry.address,
+            Entry::Vacant(entry) => &entry.address,
+        }
+    }
+
+    /// Adds a `Leaf` carrying `payload` if the address is vacant, and returns
+    /// the payload of the `Leaf` at the address.
+    pub fn or_insert(self, payload: P) -> PayloadMut<'a, P, H, N> {
+        self.or_insert_with(|| payload)
+    }
+
+    /// Adds a `Leaf` carrying the result of `f` if the address is vacant, and
+    /// returns the payload of the `Leaf` at the address.
+    pub fn or_insert_with<F: FnOnce() -> P>(self, f: F) -> PayloadMut<'a, P, H, N> {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Applies `f` to the payload of the `Leaf` at the address, if there is
+    /// one, rehashing its path, and returns the `Entry`.
+    pub fn and_modify<F: FnOnce(&mut P)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(&mut entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> OccupiedEntry<'a, P, H, N> {
+    /// Returns a reference to the payload of the `Leaf` at the address.
+    pub fn get(&self) -> &P {
+        self.trie
+            .find_leaf(&self.address)
+            .expect("an occupied entry has a leaf at its address")
+            .payload_ref()
+    }
+
+    /// Returns the payload of the `Leaf` at the address, borrowed from the
+    /// `OccupiedEntry`.
+    pub fn get_mut(&mut self) -> PayloadMut<'_, P, H, N> {
+        PayloadMut::new(self.trie, self.address)
+    }
+
+    /// Returns the payload of the `Leaf` at the address, borrowed for as long
+    /// as the `Trie`.
+    pub fn into_mut(self) -> PayloadMut<'a, P, H, N> {
+        PayloadMut::new(self.trie, self.address)
+    }
+
+    /// Replaces the payload of the `Leaf` at the address, rehashing its
+    /// path, and returns the old payload.
+    pub fn insert(&mut self, payload: P) -> P {
+        self.trie
+            .replace(&self.address, payload)
+            .expect("an occupied entry has a leaf at its address")
+    }
+}
+
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> VacantEntry<'a, P, H, N> {
+    /// Adds a `Leaf` carrying `payload` at the address, and returns its payload.
+    pub fn insert(self, payload: P) -> PayloadMut<'a, P, H, N> {
+        self.trie
+            .add(Leaf::with_hasher(self.address, payload))
+            .expect("a vacant entry has no leaf at its address");
+        PayloadMut::new(self.trie, self.address)
+    }
+}
+
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PayloadMut<'a, P, H, N> {
+    fn new(trie: &'a mut Trie<P, H, N>, address: Address<N>) -> PayloadMut<'a, P, H, N> {
+        let payload = trie
+            .find_leaf(&address)
+            .expect("a payload is only borrowed from an address holding a leaf")
+            .get_payload();
+        PayloadMut {
+            trie,
+            address,
+            payload: Some(payload),
+        }
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Deref for PayloadMut<'_, P, H, N> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        self.payload.as_ref().expect("the payload is only taken when dropped")
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> DerefMut for PayloadMut<'_, P, H, N> {
+    fn deref_mut(&mut self) -> &mut P {
+        self.payload.as_mut().expect("the payload is only taken when dropped")
+    }
+}
+
+/// Writes the payload back into its `Leaf`, recomputing every hash on the
+/// path of the `Leaf` up to the `Root`.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Drop for PayloadMut<'_, P, H, N> {
+    fn drop(&mut self) {
+        if let Some(payload) = self.payload.take() {
+            let _ = self.trie.replace(&self.address, payload);
+        }
+    }
+}
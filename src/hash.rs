@@ -1,12 +1,27 @@
 use core::convert::TryFrom;
-use core::mem;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use alloc::vec::Vec;
 use sha2::{Sha256, Digest, digest::FixedOutput};
+use sha3::Keccak256;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
 
-pub trait Hasher: Clone {
-    type Hash: Copy + PartialEq + Into<Vec<u8>> + TryFrom<Vec<u8>>;
+pub trait Hasher: Clone + Debug {
+    type Hash: Copy + PartialEq + Debug + AsRef<[u8]> + Into<Vec<u8>> + TryFrom<Vec<u8>>;
 
     fn hash(data: &[u8]) -> Self::Hash;
 
+    /// The canonical empty hash, all zeros, which is the hash of an empty
+    /// `Branch`, see `merkle_root`, and the placeholder of an unoccupied slot
+    /// under `NoneHashPolicy::ZeroPlaceholder`.
+    fn zero() -> Self::Hash;
+
+    /// A unique identifier for the hash algorithm, to be written alongside
+    /// serialized hashes so that a reader can reject data produced by a
+    /// different algorithm rather than verifying against the wrong one.
+    fn algorithm_id() -> u8;
+
     fn concat_and_hash(
         left: &Self::Hash,
         right: Option<&Self::Hash>
@@ -23,12 +38,125 @@ pub trait Hasher: Clone {
         }
     }
 
+    /// The length in bytes of a digest, which is how many bytes a hash takes
+    /// when serialized. Defaults to the length of `zero`, which suits any
+    /// `Hash` whose bytes are all digest, unlike e.g. `mem::size_of`, which
+    /// counts the pointer and length of a heap backed one instead.
+    fn hash_size() -> usize {
+        Self::zero().as_ref().len()
+    }
+
+    /// Hashes everything read from `reader` until it is exhausted, to the
+    /// same hash `hash` would give the bytes read. Reads them all into memory
+    /// first by default, hashers able to, e.g. `Blake3Algorithm`, stream them
+    /// instead so that huge payloads are never held whole.
+    #[cfg(feature = "std")]
+    fn hash_reader(mut reader: impl Read) -> io::Result<Self::Hash> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(Self::hash(&data))
+    }
+
+    /// How the unoccupied slots of a `Branch` take part in its hash, and so
+    /// in the `Root` hash and in proofs. Defaults to `NoneHashPolicy::Skip`,
+    /// see `ZeroPadded` for the other.
+    fn none_hash_policy() -> NoneHashPolicy {
+        NoneHashPolicy::Skip
+    }
+
+    /// Reduces `hashes` to a single hash by folding them pairwise, in order,
+    /// through `concat_and_hash` until one remains. An odd hash out at any
+    /// level is carried up unchanged, and no hashes at all reduce to `zero`.
+    fn merkle_root(hashes: &[Self::Hash]) -> Self::Hash {
+        if hashes.is_empty() {
+            return Self::zero();
+        }
+        let mut level = hashes.to_vec();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| Self::concat_and_hash(&pair[0], pair.get(1)))
+                .collect();
+        }
+        level[0]
+    }
+}
+
+/// How the unoccupied slots of a `Branch` take part in its hash, see
+/// `Hasher::none_hash_policy`. Either way the hash of a `Branch` is the
+/// `Hasher::merkle_root` of the `nibble || hash` hashes of its slots, in
+/// `Nibble` order, and an empty `Branch` hashes to `Hasher::zero`; the two
+/// give different `Root` hashes for the same leaves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoneHashPolicy {
+    /// Unoccupied slots are left out, so the Merkle tree of a `Branch` has
+    /// as many leaves as it has occupied slots, and proofs against sparse
+    /// branches are short.
+    Skip,
+    /// Unoccupied slots contribute `Hasher::zero`, so the Merkle tree of a
+    /// non-empty `Branch` always has 256 leaves, the slot at `Nibble` `i`
+    /// being the `i`th, and every `ProofStep` has 8 `Sibling`s. Hashing a
+    /// `Branch` takes 255 hashes however few slots it holds, in exchange for
+    /// a slot's position in the tree not depending on its neighbours.
+    ZeroPlaceholder,
+}
+
+/// `H` with `NoneHashPolicy::ZeroPlaceholder`: hashes exactly like `H`, but
+/// `Branch` hashes, and so `Root` hashes and proofs, count every unoccupied
+/// slot as `Hasher::zero`. Its `algorithm_id` is that of `H` with the high
+/// bit set, so data serialized under one policy is rejected under the other.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::hash::{Sha256Algorithm, ZeroPadded};
+/// use mmpt::node::Leaf;
+/// use mmpt::trie::Trie;
+///
+/// let mut skipping = Trie::<String>::default();
+/// skipping.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+/// let mut padded = Trie::<String, ZeroPadded<Sha256Algorithm>>::default();
+/// padded.add(Leaf::with_hasher([0u8; 32], "Some Data".to_string())).unwrap();
+///
+/// assert_ne!(skipping.root_hash(), padded.root_hash());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ZeroPadded<H: Hasher>(PhantomData<H>);
+
+impl<H: Hasher> Hasher for ZeroPadded<H> {
+    type Hash = H::Hash;
+
+    fn hash(data: &[u8]) -> Self::Hash {
+        H::hash(data)
+    }
+
+    fn zero() -> Self::Hash {
+        H::zero()
+    }
+
+    fn algorithm_id() -> u8 {
+        H::algorithm_id() | 0x80
+    }
+
+    fn concat_and_hash(left: &Self::Hash, right: Option<&Self::Hash>) -> Self::Hash {
+        H::concat_and_hash(left, right)
+    }
+
     fn hash_size() -> usize {
-        mem::size_of::<Self::Hash>()
+        H::hash_size()
+    }
+
+    #[cfg(feature = "std")]
+    fn hash_reader(reader: impl Read) -> io::Result<Self::Hash> {
+        H::hash_reader(reader)
+    }
+
+    fn none_hash_policy() -> NoneHashPolicy {
+        NoneHashPolicy::ZeroPlaceholder
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Sha256Algorithm;
 
 impl Hasher for Sha256Algorithm {
@@ -41,6 +169,14 @@ impl Hasher for Sha256Algorithm {
         <[u8; 32]>::from(hasher.finalize_fixed())
     }
 
+    fn zero() -> Self::Hash {
+        [0u8; 32]
+    }
+
+    fn algorithm_id() -> u8 {
+        1
+    }
+
     fn concat_and_hash(
         left: &Self::Hash,
         right: Option<&Self::Hash>
@@ -58,6 +194,85 @@ impl Hasher for Sha256Algorithm {
     }
 
     fn hash_size() -> usize {
-        mem::size_of::<Self::Hash>()
+        32
+    }
+}
+
+/// The original Keccak-256 (as used by Ethereum), for state commitments
+/// that need to interoperate with Keccak based systems.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::hash::Keccak256Algorithm;
+/// use mmpt::node::Leaf;
+/// use mmpt::trie::Trie;
+///
+/// let mut trie = Trie::<String, Keccak256Algorithm>::default();
+/// trie.add(Leaf::with_hasher([0u8; 32], "Some Data".to_string())).unwrap();
+/// assert!(trie.contains(&[0u8; 32]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Keccak256Algorithm;
+
+impl Hasher for Keccak256Algorithm {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> Self::Hash {
+        let mut hasher = Keccak256::new();
+
+        hasher.update(data);
+        <[u8; 32]>::from(hasher.finalize_fixed())
+    }
+
+    fn zero() -> Self::Hash {
+        [0u8; 32]
     }
-}
\ No newline at end of file
+
+    fn algorithm_id() -> u8 {
+        2
+    }
+}
+
+/// BLAKE3, substantially faster than SHA-256 on large payloads, and able to
+/// hash them as they are read, see `hash_reader`. Available with the
+/// `blake3` feature.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::hash::Blake3Algorithm;
+/// use mmpt::node::Leaf;
+/// use mmpt::trie::Trie;
+///
+/// let mut trie = Trie::<String, Blake3Algorithm>::default();
+/// trie.add(Leaf::with_hasher([0u8; 32], "Some Data".to_string())).unwrap();
+/// assert!(trie.contains(&[0u8; 32]));
+/// ```
+#[cfg(feature = "blake3")]
+#[derive(Clone, Debug)]
+pub struct Blake3Algorithm;
+
+#[cfg(feature = "blake3")]
+impl Hasher for Blake3Algorithm {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> Self::Hash {
+        blake3::hash(data).into()
+    }
+
+    fn zero() -> Self::Hash {
+        [0u8; 32]
+    }
+
+    fn algorithm_id() -> u8 {
+        3
+    }
+
+    #[cfg(feature = "std")]
+    fn hash_reader(reader: impl Read) -> io::Result<Self::Hash> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_reader(reader)?;
+        Ok(hasher.finalize().into())
+    }
+}
@@ -1,4 +1,28 @@
+#[cfg(feature = "std")]
+use std::error::Error;
+use core::fmt::Display;
+
+/// Returned when converting a number into a `Layer` it has no variant for,
+/// i.e. one past the last byte of a 32 byte address. Holds the number.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LayerOutOfRange(pub usize);
+
+impl Display for LayerOutOfRange {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "There is no Layer {}, the last one is 31", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for LayerOutOfRange {
+    fn description(&self) -> &str {
+        "There is no Layer past the last byte of an address"
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "u8", try_from = "u8"))]
 pub enum Layer {
     Zero,
     One,
@@ -32,8 +56,6 @@ pub enum Layer {
     TwentyNine,
     Thirty,
     ThirtyOne,
-    OutOfRange,
-
 }
 
 impl From<Layer> for u8 {
@@ -71,54 +93,23 @@ impl From<Layer> for u8 {
             Layer::TwentyNine => 29,
             Layer::Thirty => 30,
             Layer::ThirtyOne => 31,
-            Layer::OutOfRange => 255,
         }
     }
 }
 
-impl From<u8> for Layer {
-    fn from(i: u8) -> Layer {
-        match i {
-            0 => Layer::Zero,
-            1 => Layer::One,
-            2 => Layer::Two,
-            3 => Layer::Three,
-            4 => Layer::Four,
-            5 => Layer::Five,
-            6 => Layer::Six,
-            7 => Layer::Seven,
-            8 => Layer::Eight,
-            9 => Layer::Nine,
-            10 => Layer::Ten,
-            11 => Layer::Eleven,
-            12 => Layer::Twelve,
-            13 => Layer::Thirteen,
-            14 => Layer::Fourteen,
-            15 => Layer::Fifteen,
-            16 => Layer::Sixteen,
-            17 => Layer::Seventeen,
-            18 => Layer::Eighteen,
-            19 => Layer::Nineteen,
-            20 => Layer::Twenty,
-            21 => Layer::TwentyOne,
-            22 => Layer::TwentyTwo,
-            23 => Layer::TwentyThree,
-            24 => Layer::TwentyFour,
-            25 => Layer::TwentyFive,
-            26 => Layer::TwentySix,
-            27 => Layer::TwentySeven,
-            28 => Layer::TwentyEight,
-            29 => Layer::TwentyNine,
-            30 => Layer::Thirty,
-            31 => Layer::ThirtyOne,
-            _ => Layer::OutOfRange,
-        }
+impl TryFrom<u8> for Layer {
+    type Error = LayerOutOfRange;
+
+    fn try_from(i: u8) -> Result<Layer, LayerOutOfRange> {
+        Layer::try_from(i as usize)
     }
 }
 
-impl From<usize> for Layer {
-    fn from(i: usize) -> Layer {
-        match i {
+impl TryFrom<usize> for Layer {
+    type Error = LayerOutOfRange;
+
+    fn try_from(i: usize) -> Result<Layer, LayerOutOfRange> {
+        let layer = match i {
             0 => Layer::Zero,
             1 => Layer::One,
             2 => Layer::Two,
@@ -151,7 +142,8 @@ impl From<usize> for Layer {
             29 => Layer::TwentyNine,
             30 => Layer::Thirty,
             31 => Layer::ThirtyOne,
-            _ => Layer::OutOfRange,
-        }
+            _ => return Err(LayerOutOfRange(i)),
+        };
+        Ok(layer)
     }
 }
\ No newline at end of file
@@ -1,12 +1,69 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 pub mod node;
 pub mod trie;
 pub mod layer;
 pub mod hash;
+pub mod set;
+pub mod proof;
+pub mod partial;
+pub mod store;
+pub mod entry;
+pub mod smt;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "rlp")]
+mod rlp;
+#[cfg(any(all(test, feature = "std"), feature = "testutil"))]
+pub mod testutil;
 
-#[cfg(test)]
-mod tests {
+/// Tests built without the `std` feature, i.e. against `core` and `alloc`
+/// only, see `cargo test --no-default-features`.
+#[cfg(all(test, not(feature = "std")))]
+mod no_std_tests {
+    use crate::node::Leaf;
     use crate::trie::Trie;
-    use crate::node::{Branch, Leaf, Root, Node};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn trie_adds_proves_and_round_trips_without_std() {
+        let mut trie: Trie<Vec<u8>> = Trie::default();
+        for byte in 0..16u8 {
+            trie.add(Leaf::new([byte; 32], vec![byte])).unwrap();
+        }
+        let mut address = [3u8; 32];
+        address[31] = 4;
+        trie.add(Leaf::new(address, vec![34])).unwrap();
+
+        assert_eq!(trie.len(), 17);
+        assert!(trie.prove(&[3u8; 32]).unwrap().verify(&trie.root_hash(), &[3u8; 32], &vec![3]));
+        assert_eq!(trie.remove(&[3u8; 32]).map(|leaf| leaf.get_payload()), Some(vec![3]));
+
+        let decoded: Trie<Vec<u8>> = Trie::from_bytes(&trie.to_bytes()).unwrap();
+        assert_eq!(decoded, trie);
+        assert!(decoded.verify_integrity());
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::trie::{InvalidInsertError, LookupOutcome, LookupStep, NodeVariant, Trie, TrieError};
+    use crate::node::{path_of, slot_hash, DecodeError, Branch, Fork, Leaf, Root, Node, NodeRef};
+    use crate::layer::{Layer, LayerOutOfRange};
+    use crate::hash::{Hasher, Keccak256Algorithm, NoneHashPolicy, Sha256Algorithm, ZeroPadded};
+    use crate::testutil;
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::set::TrieSet;
+    use crate::store::{CachedStore, HashMapStore, NodeStore};
+    use std::cell::Cell;
+    use crate::proof::{dedup_proofs, Divergence, MultiProof, Proof, ProofError, ProofStep, RangeNode, Sibling};
+    use crate::partial::PartialTrie;
+    use crate::entry::Entry;
+    use crate::shared::SharedTrie;
+    use crate::smt::SparseMerkleTree;
+    use std::thread;
 
     #[test]
     fn create_new_empyt_trie() {
@@ -33,7 +90,7 @@ mod tests {
         let mut trie: Trie<String> = Trie::default();
         let payload = "Some Data".to_string();
         let new_leaf: Leaf<String> = Leaf::new([0u8; 32], payload);
-        trie.add(new_leaf.clone());
+        trie.add(new_leaf.clone()).unwrap();
         let leaf = trie.get(&0);
         assert!(leaf.is_data());
         match leaf {
@@ -45,7 +102,7 @@ mod tests {
 
     #[test]
     fn adding_shared_nibble_node_creates_new_branch() {
-        let mut branch: Branch<String> = Branch::new(0u8.into());
+        let mut branch: Branch<String> = Branch::new(Layer::Zero);
         let payload = "Some Data".to_string();
         let leaf_1: Leaf<String> = Leaf::new([0u8; 32], payload);
         let mut leaf_2_address = [0u8; 32];
@@ -77,6 +134,3518 @@ mod tests {
 
     }
 
+    #[test]
+    fn adding_node_under_existing_fork_uses_next_layer_nibble() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [1u8; 32];
+        address[1] = 2;
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[1] = 3;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+        address[1] = 4;
+        let leaf_3: Leaf<String> = Leaf::new(address, "Even More Data".to_string());
+        trie.add(leaf_3.clone()).unwrap();
+
+        match trie.get(&1) {
+            Node::Fork { fork, .. } => {
+                assert!(fork.get(&1).is_none());
+                match fork.get(&4) {
+                    Node::Data { data, .. } => { assert_eq!(data, leaf_3) }
+                    _ => { panic!("Wrong node type") }
+                }
+            }
+            _ => { panic!("Wrong node type") }
+        }
+    }
+
+    #[test]
+    fn prefix_compression_ratio_of_trie_without_forks_is_zero() {
+        let mut trie: Trie<String> = Trie::default();
+        assert_eq!(trie.prefix_compression_ratio(), 0.0);
+        trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+        trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+        assert_eq!(trie.prefix_compression_ratio(), 0.0);
+    }
+
+    #[test]
+    fn prefix_compression_ratio_counts_single_child_forks() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[3] = 1;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+        address[1] = 1;
+        trie.add(Leaf::new(address, "Even More Data".to_string())).unwrap();
+
+        // The fork at layer 0 wraps nibbles {0, 1}, the fork at layer 1 wraps
+        // only nibble 0 and the fork at layer 2 wraps nibbles {0, 1}.
+        assert!((trie.prefix_compression_ratio() - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn node_by_hash_finds_branches_forks_and_leaves() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        let leaf_1: Leaf<String> = Leaf::new(address, "Some Data".to_string());
+        address[1] = 1;
+        let leaf_2: Leaf<String> = Leaf::new(address, "Some More Data".to_string());
+        trie.add(leaf_1).unwrap();
+        trie.add(leaf_2.clone()).unwrap();
+        trie.add(Leaf::new([3u8; 32], "Even More Data".to_string())).unwrap();
+
+        let root_branch = trie.root.get_next();
+        match trie.node_by_hash(&root_branch.get_hash()) {
+            Some(NodeRef::Branch(branch)) => { assert_eq!(branch, &root_branch) }
+            _ => { panic!("Branch not found by its hash") }
+        }
+
+        let fork = match trie.get(&0) {
+            Node::Fork { fork, .. } => fork,
+            _ => { panic!("Wrong node type") }
+        };
+        match trie.node_by_hash(&fork.get_hash()) {
+            Some(NodeRef::Fork(found)) => { assert_eq!(found, &fork) }
+            _ => { panic!("Fork not found by its hash") }
+        }
+        match trie.node_by_hash(&fork.get_next().get_hash()) {
+            Some(NodeRef::Branch(found)) => { assert_eq!(found, &fork.get_next()) }
+            _ => { panic!("Fork branch not found by its hash") }
+        }
+        match trie.node_by_hash(&leaf_2.get_hash()) {
+            Some(NodeRef::Leaf(found)) => { assert_eq!(found, &leaf_2) }
+            _ => { panic!("Leaf not found by its hash") }
+        }
+
+        assert!(trie.node_by_hash(&[7u8; 32]).is_none());
+    }
+
+    #[test]
+    fn node_by_hash_finds_forks_inserted_without_rehashing() {
+        let mut address = [0u8; 32];
+        let mut leaves: Vec<Leaf<String>> = vec![Leaf::new(address, "Some Data".to_string())];
+        address[1] = 1;
+        leaves.push(Leaf::new(address, "Some More Data".to_string()));
+        address[2] = 1;
+        leaves.push(Leaf::new(address, "Even More Data".to_string()));
+
+        let mut trie: Trie<String> = Trie::default();
+        for leaf in &leaves {
+            trie.root.get_next_mut().insert(leaf.clone());
+        }
+        assert!(trie.root.get_next_ref().is_dirty());
+        let expected: Trie<String> = leaves.iter().cloned().collect();
+
+        let fork = match expected.get(&0) {
+            Node::Fork { fork, .. } => fork,
+            _ => { panic!("Wrong node type") }
+        };
+        let nested = match fork.get(&1) {
+            Node::Fork { fork, .. } => fork,
+            _ => { panic!("Wrong node type") }
+        };
+        for fork in [&fork, &nested] {
+            match trie.node_by_hash(&fork.get_hash()) {
+                Some(NodeRef::Fork(found)) => { assert_eq!(found.get_hash(), fork.get_hash()) }
+                _ => { panic!("Fork not found by its hash") }
+            }
+            assert!(matches!(trie.node_by_hash(&fork.get_next().get_hash()), Some(NodeRef::Branch(_))));
+        }
+        match trie.node_by_hash(&leaves[1].get_hash()) {
+            Some(NodeRef::Leaf(found)) => { assert_eq!(found.get_address(), leaves[1].get_address()) }
+            _ => { panic!("Leaf not found by its hash") }
+        }
+        assert_eq!(trie.to_string(), expected.to_string());
+        assert_eq!(trie.to_dot(), expected.to_dot());
+    }
+
+    #[test]
+    fn max_depth_counts_branches_to_deepest_leaf() {
+        let mut trie: Trie<String> = Trie::default();
+        assert_eq!(trie.max_depth(), 0);
+        assert!(trie.is_depth_within(0));
+
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+        assert_eq!(trie.max_depth(), 1);
+
+        address[3] = 1;
+        trie.add(Leaf::new(address, "Even More Data".to_string())).unwrap();
+        assert_eq!(trie.max_depth(), 4);
+        assert!(trie.is_depth_within(4));
+        assert!(!trie.is_depth_within(3));
+    }
+
+    #[test]
+    fn address_exceeding_depth_surfaces_the_deep_leaf() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new([1u8; 32], "Some Data".to_string())).unwrap();
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+        address[2] = 1;
+        trie.add(Leaf::new(address, "Even More Data".to_string())).unwrap();
+
+        assert_eq!(trie.address_exceeding_depth(3), None);
+        let deep = trie.address_exceeding_depth(2).unwrap();
+        assert!(deep == [0u8; 32] || deep == address);
+    }
+
+    #[test]
+    fn map_preserves_addresses_and_shape() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[1] = 1;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+        trie.add(Leaf::new([2u8; 32], "Even More Data".to_string())).unwrap();
+
+        let mapped: Trie<Vec<u8>> = trie.map(|payload| vec![payload.len() as u8]);
+        assert_eq!(mapped.max_depth(), trie.max_depth());
+        assert_eq!(mapped.prefix_compression_ratio(), trie.prefix_compression_ratio());
+
+        match mapped.get(&2) {
+            Node::Data { data, hash } => {
+                assert_eq!(data.get_address(), [2u8; 32]);
+                assert_eq!(data.get_payload(), vec![14u8]);
+                assert_eq!(hash, Leaf::new([2u8; 32], vec![14u8]).get_hash());
+            }
+            _ => { panic!("Wrong node type") }
+        }
+        match mapped.get(&0) {
+            Node::Fork { fork, .. } => {
+                match fork.get(&1) {
+                    Node::Data { data, .. } => {
+                        assert_eq!(data.get_address(), address);
+                        assert_eq!(data.get_payload(), vec![14u8]);
+                    }
+                    _ => { panic!("Wrong node type") }
+                }
+            }
+            _ => { panic!("Wrong node type") }
+        }
+    }
+
+    #[test]
+    fn iter_yields_addresses_and_payloads_in_sorted_order() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut addresses = vec![[9u8; 32], [0u8; 32], [255u8; 32]];
+        let mut address = [0u8; 32];
+        address[2] = 7;
+        addresses.push(address);
+        address[1] = 3;
+        addresses.push(address);
+        for (i, address) in addresses.iter().enumerate() {
+            trie.add(Leaf::new(*address, format!("Data {}", i))).unwrap();
+        }
+
+        let yielded: Vec<([u8; 32], &String)> = trie.iter().collect();
+        addresses.sort();
+        assert_eq!(
+            yielded.iter().map(|(address, _)| *address).collect::<Vec<_>>(),
+            addresses
+        );
+        assert_eq!(yielded[0].1, "Data 1");
+        assert_eq!(yielded[4].1, "Data 2");
+        assert_eq!(trie.iter().count(), 5);
+    }
+
+    #[test]
+    fn iter_on_empty_trie_yields_nothing() {
+        let trie: Trie<String> = Trie::default();
+        assert!(trie.iter().next().is_none());
+    }
+
+    #[test]
+    fn replace_updates_nested_leaf_and_rehashes_path() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[2] = 1;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+        let branch_hash = trie.root.get_next().get_hash();
+
+        let old = trie.replace(&address, "Some New Data".to_string());
+        assert_eq!(old, Ok("Some More Data".to_string()));
+        assert_ne!(trie.root.get_next().get_hash(), branch_hash);
+
+        let mut expected: Trie<String> = Trie::default();
+        expected.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+        expected.add(Leaf::new(address, "Some New Data".to_string())).unwrap();
+        assert_eq!(trie.root.get_next().get_hash(), expected.root.get_next().get_hash());
+    }
+
+    #[test]
+    fn replace_on_absent_address_returns_not_found() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        let branch_hash = trie.root.get_next().get_hash();
+
+        address[5] = 1;
+        assert_eq!(trie.replace(&address, "Some Data".to_string()), Err(TrieError::NotFound));
+        assert_eq!(trie.replace(&[1u8; 32], "Some Data".to_string()), Err(TrieError::NotFound));
+        assert_eq!(trie.root.get_next().get_hash(), branch_hash);
+        assert_eq!(trie.iter().count(), 1);
+    }
+
+    #[test]
+    fn trie_set_insert_and_contains() {
+        let mut set = TrieSet::new();
+        let mut address = [0u8; 32];
+        assert!(set.insert(address));
+        assert!(!set.insert(address));
+        address[4] = 1;
+        assert!(!set.contains(&address));
+        assert!(set.insert(address));
+        assert!(set.contains(&address));
+        assert!(set.contains(&[0u8; 32]));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![[0u8; 32], address]);
+    }
+
+    #[test]
+    fn trie_set_commitment_depends_on_the_addresses() {
+        let mut set_1 = TrieSet::new();
+        let mut set_2 = TrieSet::new();
+        set_1.insert([1u8; 32]);
+        set_2.insert([2u8; 32]);
+        assert_ne!(
+            set_1.as_trie().root.get_next().get_hash(),
+            set_2.as_trie().root.get_next().get_hash()
+        );
+    }
+
+    #[test]
+    fn trie_set_union_intersection_and_difference() {
+        let mut set_1 = TrieSet::new();
+        let mut set_2 = TrieSet::new();
+        set_1.insert([0u8; 32]);
+        set_1.insert([1u8; 32]);
+        set_2.insert([1u8; 32]);
+        set_2.insert([2u8; 32]);
+
+        let union = set_1.union(&set_2);
+        assert_eq!(union.iter().collect::<Vec<_>>(), vec![[0u8; 32], [1u8; 32], [2u8; 32]]);
+
+        let intersection = set_1.intersection(&set_2);
+        assert_eq!(intersection.iter().collect::<Vec<_>>(), vec![[1u8; 32]]);
+
+        let difference = set_1.difference(&set_2);
+        assert_eq!(difference.iter().collect::<Vec<_>>(), vec![[0u8; 32]]);
+    }
+
+    fn set_operation_addresses() -> Vec<[u8; 32]> {
+        let mut addresses = vec![[0u8; 32], [3u8; 32], [200u8; 32]];
+        let mut address = [0u8; 32];
+        address[1] = 1;
+        addresses.push(address);
+        address[4] = 9;
+        addresses.push(address);
+        address = [3u8; 32];
+        address[2] = 0;
+        addresses.push(address);
+        addresses
+    }
+
+    fn trie_from(addresses: &[[u8; 32]], payload: &str) -> Trie<String> {
+        let mut trie: Trie<String> = Trie::default();
+        for address in addresses {
+            trie.add(Leaf::new(*address, payload.to_string())).unwrap();
+        }
+        trie
+    }
+
+    #[test]
+    fn union_matches_inserting_every_leaf_and_prefers_self() {
+        let addresses = set_operation_addresses();
+        let trie_1 = trie_from(&addresses[..4], "Ours");
+        let trie_2 = trie_from(&addresses[2..], "Theirs");
+
+        let union = trie_1.union(&trie_2);
+        let mut expected = trie_from(&addresses[..4], "Ours");
+        for address in &addresses[4..] {
+            expected.add(Leaf::new(*address, "Theirs".to_string())).unwrap();
+        }
+        assert_eq!(union.root.get_next().get_hash(), expected.root.get_next().get_hash());
+        assert_eq!(
+            trie_2.union(&trie_1).iter().filter(|(_, payload)| *payload == "Theirs").count(),
+            4
+        );
+        assert_eq!(
+            trie_1.union(&trie_1).root.get_next().get_hash(),
+            trie_1.root.get_next().get_hash()
+        );
+    }
+
+    #[test]
+    fn intersection_keeps_shared_addresses_with_own_payloads() {
+        let addresses = set_operation_addresses();
+        let trie_1 = trie_from(&addresses[..4], "Ours");
+        let trie_2 = trie_from(&addresses[2..], "Theirs");
+
+        let intersection = trie_1.intersection(&trie_2);
+        let expected = trie_from(&addresses[2..4], "Ours");
+        assert_eq!(
+            intersection.root.get_next().get_hash(),
+            expected.root.get_next().get_hash()
+        );
+        assert_eq!(trie_1.intersection(&trie_1).iter().count(), 4);
+        assert_eq!(trie_1.intersection(&Trie::default()).iter().count(), 0);
+    }
+
+    #[test]
+    fn difference_drops_addresses_present_in_other() {
+        let addresses = set_operation_addresses();
+        let trie_1 = trie_from(&addresses[..4], "Ours");
+        let trie_2 = trie_from(&addresses[2..], "Theirs");
+
+        let difference = trie_1.difference(&trie_2);
+        let expected = trie_from(&addresses[..2], "Ours");
+        assert_eq!(
+            difference.root.get_next().get_hash(),
+            expected.root.get_next().get_hash()
+        );
+        assert_eq!(trie_1.difference(&trie_1).iter().count(), 0);
+        assert_eq!(trie_1.difference(&Trie::default()).iter().count(), 4);
+    }
+
+    #[test]
+    fn intersection_and_difference_reuse_equal_subtrees() {
+        let mut rng = StdRng::seed_from_u64(425);
+        let mut addresses = testutil::random_addresses(400, &mut rng);
+        addresses.extend(testutil::clustered_addresses(200, 2, &mut rng));
+        let mut ours: Trie<String, CountingHasher> = Trie::with_hasher();
+        for address in &addresses {
+            ours.add(Leaf::with_hasher(*address, "Ours".to_string())).unwrap();
+        }
+        ours.root_hash();
+
+        let mut theirs = ours.clone();
+        for address in addresses.iter().filter(|address| address[0] < 16) {
+            theirs.remove(address);
+        }
+        for address in addresses.iter().filter(|address| (16..32).contains(&address[0])) {
+            theirs.replace(address, "Theirs".to_string()).unwrap();
+        }
+        for address in testutil::random_addresses(50, &mut rng).into_iter().filter(|address| address[0] < 32) {
+            theirs.add(Leaf::with_hasher(address, "Theirs".to_string())).unwrap();
+        }
+        theirs.root_hash();
+
+        let rebuild = |addresses: Vec<&[u8; 32]>| {
+            let mut trie: Trie<String, CountingHasher> = Trie::with_hasher();
+            for address in addresses {
+                trie.add(Leaf::with_hasher(*address, "Ours".to_string())).unwrap();
+            }
+            trie.root_hash();
+            trie
+        };
+        let before = hash_calls();
+        let mut expected = rebuild(addresses.iter().filter(|address| address[0] >= 16).collect());
+        let rebuild_hashes = hash_calls() - before;
+
+        let before = hash_calls();
+        let mut intersection = ours.intersection(&theirs);
+        assert!(hash_calls() - before < rebuild_hashes / 4);
+        assert_eq!(intersection.root_hash(), expected.root_hash());
+        intersection.assert_canonical();
+        for nibble in 32..=u8::MAX {
+            if let (Node::Fork { fork, .. }, Node::Fork { fork: shared, .. }) = (ours.root.get_ref(&nibble), intersection.root.get_ref(&nibble)) {
+                assert!(core::ptr::eq(fork.get_next_ref(), shared.get_next_ref()));
+            }
+        }
+
+        let before = hash_calls();
+        let mut expected = rebuild(addresses.iter().filter(|address| address[0] < 16).collect());
+        let rebuild_hashes = hash_calls() - before;
+        let before = hash_calls();
+        let mut difference = ours.difference(&theirs);
+        assert!(hash_calls() - before < rebuild_hashes);
+        assert_eq!(difference.root_hash(), expected.root_hash());
+        difference.assert_canonical();
+    }
+
+    #[test]
+    fn debug_output_only_includes_occupied_nibbles() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[1] = 1;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+
+        let debug = format!("{:?}", trie);
+        assert!(!debug.contains("None"));
+        assert!(debug.contains("Some More Data"));
+        assert!(debug.len() < 2500);
+        assert!(!format!("{:#?}", trie).contains("None"));
+        assert!(!format!("{:?}", Branch::<String>::new(Layer::Zero)).contains("None"));
+    }
+
+    #[test]
+    fn inserted_tries_have_no_dangling_forks() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        assert!(!trie.has_dangling_forks());
+        assert!(!trie.union(&trie_from(&[[7u8; 32]], "Some Data")).has_dangling_forks());
+        assert!(!trie.difference(&trie_from(&addresses[..3], "Some Data")).has_dangling_forks());
+    }
+
+    #[test]
+    fn has_dangling_forks_detects_corrupted_tries() {
+        let mut trie = trie_from(&set_operation_addresses(), "Some Data");
+        let mut fork: Fork<String> = Fork::new(9, Layer::One);
+        let hash = fork.get_hash();
+        trie.root.get_next_mut().set(9, Node::Fork { fork: fork.clone(), hash });
+        assert!(trie.has_dangling_forks());
+
+        let mut address = [9u8; 32];
+        address[1] = 4;
+        fork.insert(Leaf::new(address, "Some Data".to_string()));
+        let hash = fork.get_hash();
+        trie.root.get_next_mut().set(9, Node::Fork { fork: fork.clone(), hash });
+        assert!(trie.has_dangling_forks());
+
+        address[1] = 5;
+        fork.insert(Leaf::new(address, "Some More Data".to_string()));
+        let hash = fork.get_hash();
+        trie.root.get_next_mut().set(9, Node::Fork { fork, hash });
+        assert!(!trie.has_dangling_forks());
+    }
+
+    #[test]
+    fn build_trie_holds_the_requested_number_of_leaves() {
+        let trie = testutil::build_trie(500);
+        assert_eq!(trie.iter().count(), 500);
+        assert!(!trie.has_dangling_forks());
+        assert_eq!(
+            trie.root.get_next().get_hash(),
+            testutil::build_trie(500).root.get_next().get_hash()
+        );
+    }
+
+    #[test]
+    fn clustered_addresses_share_the_prefix() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let addresses = testutil::clustered_addresses(50, 3, &mut rng);
+        assert_eq!(addresses.len(), 50);
+        assert!(addresses.iter().all(|address| address[..3] == addresses[0][..3]));
+
+        let trie = trie_from(&addresses, "Some Data");
+        assert_eq!(trie.iter().count(), 50);
+        assert!(trie.max_depth() >= 4);
+    }
+
+    #[test]
+    fn sha256_algorithm_id_is_pinned() {
+        assert_eq!(Sha256Algorithm::algorithm_id(), 1);
+    }
+
+    #[test]
+    fn address_nibbles_follow_the_path_through_forks() {
+        let mut address = [0u8; 32];
+        address[2] = 5;
+        let leaf: Leaf<String> = Leaf::new(address, "Some Data".to_string());
+        assert_eq!(leaf.address_nibbles().len(), 32);
+
+        let mut trie: Trie<String> = Trie::default();
+        trie.add(Leaf::new([0u8; 32], "Some More Data".to_string())).unwrap();
+        trie.add(leaf.clone()).unwrap();
+        let nibbles = leaf.address_nibbles();
+        let mut node = trie.get(&nibbles[0]);
+        for nibble in &nibbles[1..] {
+            node = match node {
+                Node::Fork { fork, .. } => fork.get(nibble),
+                _ => break,
+            };
+        }
+        match node {
+            Node::Data { data, .. } => { assert_eq!(data, leaf) }
+            _ => { panic!("Wrong node type") }
+        }
+    }
+
+    #[test]
+    fn min_and_max_address_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut addresses = testutil::clustered_addresses(200, 1, &mut rng);
+        addresses.extend(testutil::random_addresses(200, &mut rng));
+        let trie = trie_from(&addresses, "Some Data");
+
+        assert_eq!(trie.min_address(), addresses.iter().min().copied());
+        assert_eq!(trie.max_address(), addresses.iter().max().copied());
+        assert_eq!(Trie::<String>::default().max_address(), None);
+    }
+
+    fn leaf_layer(trie: &Trie<String>, address: &[u8; 32]) -> usize {
+        let mut node = trie.get(&address[0]);
+        let mut layer = 0;
+        while let Node::Fork { fork, .. } = node {
+            layer += 1;
+            node = fork.get(&address[layer]);
+        }
+        layer
+    }
+
+    #[test]
+    fn add_at_layer_matches_unhinted_add() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut addresses = testutil::clustered_addresses(50, 2, &mut rng);
+        addresses.extend(testutil::random_addresses(300, &mut rng));
+        let expected = trie_from(&addresses, "Some Data");
+
+        let mut trie: Trie<String> = Trie::default();
+        for address in addresses.iter().rev() {
+            let layer = leaf_layer(&expected, address);
+            trie.add_at_layer(Leaf::new(*address, "Some Data".to_string()), layer).unwrap();
+        }
+        assert_eq!(trie.root.get_next().get_hash(), expected.root.get_next().get_hash());
+        assert!(!trie.has_dangling_forks());
+    }
+
+    #[test]
+    fn add_at_layer_rejects_contradicting_hints() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+
+        address[1] = 1;
+        let leaf: Leaf<String> = Leaf::new(address, "Some More Data".to_string());
+        let invalid = Err(InvalidInsertError::InvalidAddress);
+        assert_eq!(trie.add_at_layer(leaf.clone(), 32), invalid);
+        assert_eq!(trie.add_at_layer(leaf.clone(), 0), invalid);
+        assert_eq!(trie.add_at_layer(leaf.clone(), 1), invalid);
+        assert_eq!(trie.iter().count(), 1);
+    }
+
+    #[test]
+    fn iter_forks_yields_paths_nibbles_and_child_counts() {
+        let trie = trie_from(&set_operation_addresses(), "Some Data");
+        let forks: Vec<(Vec<u8>, u8, usize)> = trie.iter_forks().collect();
+        assert_eq!(
+            forks,
+            vec![
+                (vec![], 0, 2),
+                (vec![0], 1, 1),
+                (vec![0, 1], 0, 1),
+                (vec![0, 1, 0], 0, 2),
+                (vec![], 3, 1),
+                (vec![3], 3, 2),
+            ]
+        );
+        assert_eq!(Trie::<String>::default().iter_forks().count(), 0);
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn trie_and_nodes_are_send_and_sync() {
+        assert_send_sync::<Trie<String>>();
+        assert_send_sync::<Root<String>>();
+        assert_send_sync::<Branch<String>>();
+        assert_send_sync::<Fork<String>>();
+        assert_send_sync::<Leaf<String>>();
+        assert_send_sync::<Node<String>>();
+        assert_send_sync::<TrieSet>();
+    }
+
+    #[test]
+    fn checksum_tracks_contents_not_insertion_order() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let mut reversed = addresses.clone();
+        reversed.reverse();
+        assert_eq!(trie.checksum(), trie_from(&reversed, "Some Data").checksum());
+        assert_ne!(trie.checksum(), trie_from(&addresses, "Other Data").checksum());
+        assert_ne!(trie.checksum(), trie_from(&addresses[1..], "Some Data").checksum());
+
+        let mut replaced = trie.clone();
+        replaced.replace(&addresses[0], "Other Data".to_string()).unwrap();
+        assert_ne!(trie.checksum(), replaced.checksum());
+    }
+
+    #[test]
+    fn path_of_maps_address_bytes_to_layers_most_significant_first() {
+        let mut address = [0u8; 32];
+        for (i, byte) in address.iter_mut().enumerate() {
+            *byte = (i as u8) * 8 + 1;
+        }
+        let path = path_of(&address);
+        assert_eq!(path, address.to_vec());
+        assert_eq!(Leaf::new(address, "Some Data".to_string()).address_nibbles(), path);
+
+        // Two leaves first diverging at byte 3 sit side by side in the
+        // Branch at Layer 3, at the Nibble given by their fourth byte.
+        let mut other = address;
+        other[3] = 0;
+        let trie = trie_from(&[address, other], "Some Data");
+        assert_eq!(leaf_layer(&trie, &address), 3);
+        assert_eq!(trie.iter_forks().last(), Some((path[..2].to_vec(), path[2], 2)));
+    }
+
+    #[test]
+    fn explain_traces_each_layer_of_the_lookup() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+
+        let trace = trie.explain(&addresses[4]);
+        assert_eq!(trace.outcome, LookupOutcome::Found);
+        assert_eq!(
+            trace.steps.iter().map(|step| step.node).collect::<Vec<_>>(),
+            vec![
+                NodeVariant::Fork,
+                NodeVariant::Fork,
+                NodeVariant::Fork,
+                NodeVariant::Fork,
+                NodeVariant::Data,
+            ]
+        );
+        assert_eq!(trace.steps[4], LookupStep { layer: 4, nibble: 9, node: NodeVariant::Data });
+
+        let trace = trie.explain(&[1u8; 32]);
+        assert_eq!(trace.outcome, LookupOutcome::NotFound);
+        assert_eq!(trace.steps, vec![LookupStep { layer: 0, nibble: 1, node: NodeVariant::None }]);
+
+        let mut address = addresses[3];
+        address[20] = 1;
+        let trace = trie.explain(&address);
+        assert_eq!(trace.outcome, LookupOutcome::DivergedAtLeaf(addresses[3]));
+        assert_eq!(trace.steps.len(), 5);
+    }
+
+    #[test]
+    fn remove_lone_leaf_leaves_an_empty_trie() {
+        let mut trie: Trie<String> = Trie::default();
+        let leaf: Leaf<String> = Leaf::new([5u8; 32], "Some Data".to_string());
+        trie.add(leaf.clone()).unwrap();
+
+        assert_eq!(trie.remove(&[5u8; 32]), Some(leaf));
+        assert!(trie.get(&5).is_none());
+        assert_eq!(trie.iter().count(), 0);
+        let mut empty = Branch::<String>::new(Layer::Zero);
+        empty.hash_nibbles();
+        assert_eq!(trie.root.get_next().get_hash(), empty.get_hash());
+    }
+
+    #[test]
+    fn remove_collapses_forks_back_into_a_leaf() {
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses, "Some Data");
+
+        // addresses[3] and addresses[4] share four bytes beneath a chain of forks.
+        let removed = trie.remove(&addresses[4]).unwrap();
+        assert_eq!(removed.get_address(), addresses[4]);
+        assert_eq!(removed.get_payload(), "Some Data".to_string());
+        assert!(!trie.has_dangling_forks());
+
+        let mut survivors = addresses.clone();
+        survivors.remove(4);
+        let expected = trie_from(&survivors, "Some Data");
+        assert_eq!(trie.root.get_next().get_hash(), expected.root.get_next().get_hash());
+        assert_eq!(trie.max_depth(), 3);
+
+        trie.remove(&addresses[3]).unwrap();
+        match trie.get(&0) {
+            Node::Data { data, .. } => { assert_eq!(data.get_address(), addresses[0]) }
+            _ => { panic!("Wrong node type") }
+        }
+        trie.remove(&addresses[0]).unwrap();
+        assert!(trie.get(&0).is_none());
+        assert!(!trie.has_dangling_forks());
+    }
+
+    #[test]
+    fn remove_nonexistent_address_returns_none() {
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses, "Some Data");
+        let hash = trie.root.get_next().get_hash();
+
+        let mut address = addresses[4];
+        address[31] = 1;
+        assert_eq!(trie.remove(&address), None);
+        assert_eq!(trie.remove(&[1u8; 32]), None);
+        assert_eq!(trie.root.get_next().get_hash(), hash);
+        assert_eq!(trie.iter().count(), addresses.len());
+    }
+
+    #[test]
+    fn removing_half_of_a_large_trie_matches_rebuilding_from_survivors() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut addresses = testutil::clustered_addresses(100, 2, &mut rng);
+        addresses.extend(testutil::random_addresses(300, &mut rng));
+        let mut trie = trie_from(&addresses, "Some Data");
+
+        let (removed, survivors): (Vec<_>, Vec<_>) =
+            addresses.iter().enumerate().partition(|(i, _)| i % 2 == 0);
+        for (_, address) in removed {
+            assert!(trie.remove(address).is_some());
+        }
+        let survivors: Vec<[u8; 32]> = survivors.into_iter().map(|(_, address)| *address).collect();
+        let expected = trie_from(&survivors, "Some Data");
+        assert_eq!(trie.root.get_next().get_hash(), expected.root.get_next().get_hash());
+        assert!(!trie.has_dangling_forks());
+    }
+
+    #[test]
+    fn update_deeply_nested_leaf_changes_hashes_on_the_path() {
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses, "Some Data");
+        let branch_hash = trie.root.get_next().get_hash();
+        let fork_hash = trie.get(&0).get_hash();
+        assert_eq!(trie.explain(&addresses[4]).steps.len(), 5);
+
+        assert!(trie.update(&addresses[4], "Some New Data".to_string()).is_ok());
+        assert_ne!(trie.root.get_next().get_hash(), branch_hash);
+        assert_ne!(trie.get(&0).get_hash(), fork_hash);
+        assert_eq!(
+            trie.iter().find(|(address, _)| *address == addresses[4]).map(|(_, payload)| payload),
+            Some(&"Some New Data".to_string())
+        );
+
+        let mut expected = trie_from(&addresses[..4], "Some Data");
+        expected.add(Leaf::new(addresses[4], "Some New Data".to_string())).unwrap();
+        expected.add(Leaf::new(addresses[5], "Some Data".to_string())).unwrap();
+        assert_eq!(trie.root.get_next().get_hash(), expected.root.get_next().get_hash());
+    }
+
+    #[test]
+    fn update_on_absent_address_does_not_insert() {
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses, "Some Data");
+        let mut address = addresses[4];
+        address[5] = 1;
+        assert_eq!(trie.update(&address, "Some New Data".to_string()), Err(InvalidInsertError::NotFound));
+        assert_eq!(trie.iter().count(), addresses.len());
+    }
+
+    #[test]
+    fn contains_finds_present_addresses_at_any_depth() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        assert!(addresses.iter().all(|address| trie.contains(address)));
+    }
+
+    #[test]
+    fn contains_rejects_absent_and_prefix_sharing_addresses() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+
+        assert!(!trie.contains(&[1u8; 32]));
+        assert!(!Trie::<String>::default().contains(&[0u8; 32]));
+
+        let mut address = [200u8; 32];
+        address[31] = 0;
+        assert!(!trie.contains(&address));
+        let mut address = addresses[4];
+        address[4] = 5;
+        assert!(!trie.contains(&address));
+        address[4] = 9;
+        address[30] = 1;
+        assert!(!trie.contains(&address));
+    }
+
+    #[test]
+    fn get_by_address_retrieves_leaves_several_forks_deep() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        for address in &addresses {
+            match trie.get_by_address(address) {
+                Node::Data { data, hash } => {
+                    assert_eq!(data.get_address(), *address);
+                    assert_eq!(hash, data.get_hash());
+                }
+                _ => { panic!("Leaf not found") }
+            }
+        }
+        assert_eq!(trie.explain(&addresses[4]).steps.len(), 5);
+    }
+
+    #[test]
+    fn get_by_address_ignores_prefix_collisions() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let mut address = addresses[4];
+        address[31] = 1;
+        assert!(trie.get_by_address(&address).is_none());
+        assert!(trie.get_by_address(&[1u8; 32]).is_none());
+        assert!(trie.get(&0).is_fork());
+    }
+
+    #[test]
+    fn borrowed_branch_iterator_yields_every_nibble_in_order() {
+        let mut branch: Branch<String> = Branch::new(Layer::Zero);
+        branch.insert(Leaf::new([0u8; 32], "Some Data".to_string()));
+        branch.insert(Leaf::new([7u8; 32], "Some More Data".to_string()));
+        branch.insert(Leaf::new([255u8; 32], "Even More Data".to_string()));
+
+        let nodes: Vec<Node<String>> = (&branch).into_iter().collect();
+        assert_eq!(nodes.len(), 256);
+        let occupied: Vec<usize> = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_data())
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(occupied, vec![0, 7, 255]);
+    }
+
+    #[test]
+    fn owned_branch_iterator_terminates_after_the_last_nibble() {
+        let mut branch: Branch<String> = Branch::new(Layer::Zero);
+        branch.insert(Leaf::new([255u8; 32], "Some Data".to_string()));
+
+        let mut iter = branch.into_iter();
+        assert!(iter.next().unwrap().is_none());
+        assert_eq!(iter.by_ref().count(), 255);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn fork_iterators_walk_the_children_of_the_fork() {
+        let mut trie: Trie<String> = Trie::new();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[1] = 1;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+
+        let fork = match trie.get(&0) {
+            Node::Fork { fork, .. } => fork,
+            _ => { panic!("Wrong node type") }
+        };
+
+        let borrowed: Vec<Node<String>> = (&fork).into_iter().collect();
+        assert_eq!(borrowed.len(), 256);
+        let occupied: Vec<usize> = borrowed
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_data())
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(occupied, vec![0, 1]);
+
+        let owned: Vec<Node<String>> = fork.into_iter().collect();
+        assert_eq!(owned.len(), 256);
+        assert!(owned[0].is_data() && owned[1].is_data());
+        assert!(owned[2..].iter().all(|node| node.is_none()));
+    }
+
+    #[test]
+    fn root_hash_is_refreshed_after_every_mutation() {
+        let empty: Root<String> = Root::default();
+        let mut root: Root<String> = Root::default();
+        root.get_next_mut().insert(Leaf::new([0u8; 32], "Some Data".to_string()));
+        root.rehash();
+        assert_ne!(root.get_hash(), empty.get_hash());
+
+        let mut trie: Trie<String> = Trie::new();
+        trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+        assert_ne!(trie.root.get_hash(), empty.get_hash());
+        assert_eq!(trie.root.get_hash(), root.get_hash());
+
+        let added = trie.root.get_hash();
+        trie.update(&[0u8; 32], "Some New Data".to_string()).unwrap();
+        assert_ne!(trie.root.get_hash(), added);
+
+        trie.remove(&[0u8; 32]);
+        assert_eq!(trie.root.get_hash(), empty.get_hash());
+    }
+
+    thread_local! {
+        static HASH_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    /// A `Hasher` that counts its invocations on the current thread and
+    /// tags its input so its hashes never collide with `Sha256Algorithm`'s.
+    #[derive(Clone, Debug)]
+    struct CountingHasher;
+
+    impl Hasher for CountingHasher {
+        type Hash = [u8; 32];
+
+        fn hash(data: &[u8]) -> Self::Hash {
+            HASH_CALLS.with(|calls| calls.set(calls.get() + 1));
+            let mut tagged = vec![0xff];
+            tagged.extend_from_slice(data);
+            Sha256Algorithm::hash(&tagged)
+        }
+
+        fn zero() -> Self::Hash {
+            [0u8; 32]
+        }
+
+        fn algorithm_id() -> u8 {
+            255
+        }
+    }
+
+    fn hash_calls() -> usize {
+        HASH_CALLS.with(|calls| calls.get())
+    }
+
+    #[test]
+    fn trie_can_be_built_with_a_custom_hasher() {
+        let addresses = set_operation_addresses();
+        let mut custom: Trie<String, CountingHasher> = Trie::with_hasher();
+        for address in &addresses {
+            custom.add(Leaf::with_hasher(*address, "Some Data".to_string())).unwrap();
+        }
+        let default = trie_from(&addresses, "Some Data");
+
+        assert!(hash_calls() > 0);
+        assert!(addresses.iter().all(|address| custom.contains(address)));
+        assert_eq!(custom.max_depth(), default.max_depth());
+        assert_ne!(custom.root.get_hash(), default.root.get_hash());
+
+        let mut expected: Trie<String, CountingHasher> = Trie::with_hasher();
+        for address in addresses.iter().rev() {
+            expected.add(Leaf::with_hasher(*address, "Some Data".to_string())).unwrap();
+        }
+        assert_eq!(custom.root.get_hash(), expected.root.get_hash());
+    }
+
+    #[test]
+    fn cloned_trie_reuses_cached_hashes() {
+        let mut trie: Trie<String, CountingHasher> = Trie::with_hasher();
+        for address in set_operation_addresses() {
+            trie.add(Leaf::with_hasher(address, "Some Data".to_string())).unwrap();
+        }
+        trie.root_hash();
+
+        let before = hash_calls();
+        let clone = trie.clone();
+        assert_eq!(clone.root.get_hash(), trie.root.get_hash());
+        assert_eq!(clone.root.get_next().get_hash(), trie.root.get_next().get_hash());
+        assert_eq!(hash_calls(), before);
+    }
+
+    #[test]
+    fn add_many_matches_adding_leaves_one_at_a_time_with_fewer_hashes() {
+        let mut rng = StdRng::seed_from_u64(525);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(60, 2, &mut rng));
+        let leaves: Vec<Leaf<String, CountingHasher>> = addresses
+            .iter()
+            .map(|address| Leaf::with_hasher(*address, "Some Data".to_string()))
+            .collect();
+
+        let mut individually: Trie<String, CountingHasher> = Trie::with_hasher();
+        let before = hash_calls();
+        for leaf in leaves.clone() {
+            individually.add(leaf).unwrap();
+            individually.root_hash();
+        }
+        let individual_hashes = hash_calls() - before;
+
+        let mut batched: Trie<String, CountingHasher> = Trie::with_hasher();
+        let before = hash_calls();
+        batched.add_many(leaves.clone()).unwrap();
+        let batched_hashes = hash_calls() - before;
+
+        assert_eq!(batched.root.get_hash(), individually.root.get_hash());
+        assert_eq!(batched, individually);
+        assert!(batched_hashes < individual_hashes);
+
+        let mut extended: Trie<String, CountingHasher> = Trie::with_hasher();
+        extended.add(leaves[3].clone()).unwrap();
+        extended.extend(leaves.clone().into_iter().chain(leaves[..5].iter().cloned()));
+        assert_eq!(extended, individually);
+
+        let mut split: Trie<String, CountingHasher> = Trie::with_hasher();
+        split.add_many(leaves[..30].to_vec()).unwrap();
+        split.add_many(leaves[30..].to_vec()).unwrap();
+        assert_eq!(split, individually);
+        assert_eq!(split.add_many(leaves[..1].to_vec()), Err(InvalidInsertError::DuplicateAddress));
+        assert_eq!(
+            Trie::<String, CountingHasher>::with_hasher().add_many(vec![leaves[0].clone(), leaves[0].clone()]),
+            Err(InvalidInsertError::DuplicateAddress)
+        );
+    }
+
+    #[test]
+    fn collecting_leaves_matches_adding_them_one_at_a_time() {
+        let mut rng = StdRng::seed_from_u64(526);
+        let addresses = testutil::clustered_addresses(30, 1, &mut rng);
+        let leaves: Vec<Leaf<String>> = addresses
+            .iter()
+            .enumerate()
+            .map(|(i, address)| Leaf::new(*address, format!("Leaf {}", i)))
+            .collect();
+
+        let collected: Trie<String> = leaves.clone().into_iter().collect();
+        let mut expected: Trie<String> = Trie::default();
+        for leaf in leaves {
+            expected.add(leaf).unwrap();
+        }
+        assert_eq!(collected.root.get_hash(), expected.root.get_hash());
+        assert_eq!(collected.len(), addresses.len());
+
+        let empty: Trie<String> = std::iter::empty().collect();
+        assert_eq!(empty.root.get_hash(), Trie::<String>::default().root.get_hash());
+    }
+
+    #[test]
+    fn iter_addressed_rebuilds_every_address() {
+        let mut rng = StdRng::seed_from_u64(527);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(40, 3, &mut rng));
+        let mut address = [9u8; 32];
+        address[31] = 10;
+        addresses.push(address);
+        addresses.push([9u8; 32]);
+        let trie = trie_from(&addresses, "Some Data");
+
+        let rebuilt: Vec<[u8; 32]> = trie.iter_addressed().map(|(address, _)| address).collect();
+        let mut expected = addresses.clone();
+        expected.sort();
+        assert_eq!(rebuilt, expected);
+
+        let decoded = Trie::<String>::from_bytes(&trie.to_bytes()).unwrap();
+        assert!(decoded.iter_addressed().eq(trie.iter()));
+    }
+
+    #[test]
+    fn iter_prefix_yields_the_leaves_under_a_prefix() {
+        let mut rng = StdRng::seed_from_u64(529);
+        let cluster = testutil::clustered_addresses(25, 4, &mut rng);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(cluster.iter().copied());
+        let trie = trie_from(&addresses, "Some Data");
+
+        let prefixed = |prefix: &[u8]| -> Vec<[u8; 32]> {
+            trie.iter_prefix(prefix).map(|(address, _)| address).collect()
+        };
+        let mut expected = cluster.clone();
+        expected.sort();
+        assert_eq!(prefixed(&cluster[0][..4]), expected);
+        assert_eq!(prefixed(&cluster[0][..2]), expected);
+        assert_eq!(prefixed(&cluster[0]), vec![cluster[0]]);
+        assert_eq!(prefixed(&[]), trie.iter().map(|(address, _)| address).collect::<Vec<_>>());
+
+        // The prefix runs into the leaf at [200; 32] in the root branch.
+        assert_eq!(prefixed(&[200, 200, 200]), vec![[200u8; 32]]);
+        assert!(prefixed(&[200, 200, 1]).is_empty());
+        assert!(prefixed(&[0, 2]).is_empty());
+        assert!(prefixed(&[201]).is_empty());
+    }
+
+    #[test]
+    fn structural_eq_agrees_with_hash_equality() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let mut reversed = trie_from(&[], "Some Data");
+        for address in addresses.iter().rev() {
+            reversed.add(Leaf::new(*address, "Some Data".to_string())).unwrap();
+        }
+        assert!(trie == reversed);
+        assert!(trie.structural_eq(&reversed));
+
+        let mut changed = reversed.clone();
+        changed.update(&addresses[4], "Other Data".to_string()).unwrap();
+        assert!(trie != changed);
+        assert!(!trie.structural_eq(&changed));
+        changed.update(&addresses[4], "Some Data".to_string()).unwrap();
+        assert!(trie == changed);
+        assert!(trie.structural_eq(&changed));
+
+        let mut smaller = trie.clone();
+        smaller.remove(&addresses[3]);
+        assert!(!trie.structural_eq(&smaller));
+        assert!(!smaller.structural_eq(&trie));
+    }
+
+    #[test]
+    fn root_hash_tracks_every_mutation_and_is_stable_across_reads() {
+        let mut trie: Trie<String, CountingHasher> = Trie::with_hasher();
+        let empty = trie.root_hash();
+
+        trie.add(Leaf::with_hasher([0u8; 32], "Some Data".to_string())).unwrap();
+        let added = trie.root_hash();
+        assert_ne!(added, empty);
+
+        let before = hash_calls();
+        assert_eq!(trie.root_hash(), added);
+        assert_eq!(trie.root_hash(), added);
+        assert_eq!(hash_calls(), before);
+
+        trie.update(&[0u8; 32], "Other Data".to_string()).unwrap();
+        let updated = trie.root_hash();
+        assert_ne!(updated, added);
+
+        trie.add(Leaf::with_hasher([1u8; 32], "Some Data".to_string())).unwrap();
+        assert_ne!(trie.root_hash(), updated);
+
+        trie.remove(&[1u8; 32]);
+        assert_eq!(trie.root_hash(), updated);
+        trie.remove(&[0u8; 32]);
+        assert_eq!(trie.root_hash(), empty);
+    }
+
+    #[test]
+    fn removing_a_missing_address_hashes_nothing() {
+        let mut trie: Trie<String, CountingHasher> = Trie::with_hasher();
+        for address in set_operation_addresses() {
+            trie.add(Leaf::with_hasher(address, "Some Data".to_string())).unwrap();
+        }
+        let root = trie.root_hash();
+        let snapshot = trie.snapshot();
+
+        let mut missing = [0u8; 32];
+        missing[31] = 1;
+        let before = hash_calls();
+        assert_eq!(trie.remove(&missing), None);
+        assert_eq!(trie.remove(&[7u8; 32]), None);
+        assert_eq!(hash_calls(), before);
+        assert!(core::ptr::eq(trie.root.get_next_ref(), snapshot.root.get_next_ref()));
+        assert_eq!(trie.root_hash(), root);
+    }
+
+    #[test]
+    fn replacing_at_a_missing_address_hashes_nothing() {
+        let mut trie: Trie<String, CountingHasher> = Trie::with_hasher();
+        for address in set_operation_addresses() {
+            trie.add(Leaf::with_hasher(address, "Some Data".to_string())).unwrap();
+        }
+        let root = trie.root_hash();
+        let snapshot = trie.snapshot();
+
+        let mut missing = [0u8; 32];
+        missing[31] = 1;
+        let before = hash_calls();
+        assert_eq!(trie.replace(&missing, "Other Data".to_string()), Err(TrieError::NotFound));
+        assert_eq!(trie.replace(&[7u8; 32], "Other Data".to_string()), Err(TrieError::NotFound));
+        assert_eq!(hash_calls(), before);
+        assert!(core::ptr::eq(trie.root.get_next_ref(), snapshot.root.get_next_ref()));
+        assert_eq!(trie.root_hash(), root);
+    }
+
+    #[test]
+    fn verify_integrity_detects_and_recompute_hashes_repairs_corruption() {
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses, "Some Data");
+        assert!(trie.verify_integrity());
+
+        let leaf = Leaf::new(addresses[2], "Some Data".to_string());
+        let mut corrupted = trie.clone();
+        corrupted.root.get_next_mut().set(200, Node::Data { data: leaf, hash: [9u8; 32] });
+        corrupted.root.rehash();
+        assert!(!corrupted.verify_integrity());
+        corrupted.recompute_hashes();
+        assert!(corrupted.verify_integrity());
+        assert_eq!(corrupted.root_hash(), trie.root_hash());
+
+        let fork = match trie.get(&0) {
+            Node::Fork { fork, .. } => fork,
+            _ => panic!("Wrong node type"),
+        };
+        let mut corrupted = trie.clone();
+        corrupted.root.get_next_mut().set(0, Node::Fork { fork, hash: [9u8; 32] });
+        corrupted.root.rehash();
+        assert!(!corrupted.verify_integrity());
+        corrupted.recompute_hashes();
+        assert_eq!(corrupted.root_hash(), trie.root_hash());
+
+        let mut stale = trie.clone();
+        stale.add(Leaf::new([7u8; 32], "Some Data".to_string())).unwrap();
+        assert!(stale.root.get_next_ref().is_dirty());
+        assert!(stale.verify_integrity());
+        let leaf = Leaf::new(addresses[2], "Some Data".to_string());
+        stale.root.get_next_mut()[200] = Node::Data { data: leaf, hash: [9u8; 32] };
+        assert!(!stale.verify_integrity());
+        stale.recompute_hashes();
+        assert!(stale.verify_integrity());
+    }
+
+    #[test]
+    fn leaf_exposes_its_nibble_and_remainder() {
+        let mut address = [5u8; 32];
+        address[1] = 6;
+        let leaf: Leaf<String> = Leaf::new(address, "Some Data".to_string());
+        assert_eq!(leaf.get_nibble(), 5);
+        assert_eq!(leaf.get_remainder(), &address[1..]);
+
+        let mut trie: Trie<String> = Trie::default();
+        trie.add(leaf).unwrap();
+        address[1] = 7;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+        match trie.get_by_address(&address) {
+            Node::Data { data, .. } => {
+                assert_eq!(data.get_nibble(), 7);
+                assert_eq!(data.get_remainder(), &address[2..]);
+            }
+            _ => panic!("Wrong node type"),
+        }
+    }
+
+    #[test]
+    fn node_accessors_extract_only_their_own_variant() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        let leaf: Leaf<String> = Leaf::new(address, "Some Data".to_string());
+        trie.add(leaf.clone()).unwrap();
+        address[1] = 1;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+        trie.add(Leaf::new([3u8; 32], "Even More Data".to_string())).unwrap();
+
+        let data = trie.get(&3);
+        assert!(data.as_data().is_some());
+        assert!(data.as_fork().is_none());
+        assert!(data.clone().into_fork().is_none());
+        assert_eq!(data.into_data().map(|leaf| leaf.get_address()), Some([3u8; 32]));
+
+        let fork = trie.get(&0);
+        assert!(fork.as_data().is_none());
+        assert_eq!(fork.as_fork().and_then(|fork| fork.get(&0).into_data()), Some(leaf));
+        assert!(fork.clone().into_data().is_none());
+        assert_eq!(fork.into_fork().map(|fork| fork.get_next().occupied()), Some(2));
+
+        let none = trie.get(&1);
+        assert!(none.as_data().is_none());
+        assert!(none.as_fork().is_none());
+        assert!(none.clone().into_data().is_none());
+        assert!(none.into_fork().is_none());
+    }
+
+    #[test]
+    fn entry_inserts_missing_leaves_and_modifies_existing_ones() {
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses[..3], "Some Data");
+
+        let missing = addresses[4];
+        assert!(matches!(trie.entry(missing), Entry::Vacant(_)));
+        assert_eq!(*trie.entry(missing).or_insert("New Data".to_string()), "New Data");
+        assert!(trie.contains(&missing));
+        assert!(trie.verify_integrity());
+
+        let mut expected = trie_from(&addresses[..3], "Some Data");
+        expected.add(Leaf::new(missing, "New Data".to_string())).unwrap();
+        assert_eq!(trie.root_hash(), expected.root_hash());
+
+        let existing = addresses[0];
+        trie.entry(existing)
+            .and_modify(|payload| payload.push_str(" Modified"))
+            .or_insert_with(|| panic!("The address is occupied"));
+        expected.update(&existing, "Some Data Modified".to_string()).unwrap();
+        assert_eq!(trie.root_hash(), expected.root_hash());
+
+        trie.entry(addresses[5]).and_modify(|payload| payload.push('!'));
+        assert!(!trie.contains(&addresses[5]));
+
+        match trie.entry(existing) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.get(), "Some Data Modified");
+                entry.get_mut().push('!');
+                assert_eq!(entry.insert("Replaced".to_string()), "Some Data Modified!");
+            }
+            Entry::Vacant(_) => panic!("The address is occupied"),
+        }
+        expected.update(&existing, "Replaced".to_string()).unwrap();
+        assert_eq!(trie.root_hash(), expected.root_hash());
+        assert!(trie.verify_integrity());
+    }
+
+    #[test]
+    fn get_or_insert_with_inserts_once_and_rehashes_on_drop() {
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses[..3], "Some Data");
+
+        let missing = addresses[4];
+        trie.get_or_insert_with(missing, || "New Data".to_string()).push('!');
+        let mut expected = trie_from(&addresses[..3], "Some Data");
+        expected.add(Leaf::new(missing, "New Data!".to_string())).unwrap();
+        assert_eq!(trie.root_hash(), expected.root_hash());
+
+        let existing = trie.get_or_insert_with(addresses[0], || panic!("The address is occupied"));
+        assert_eq!(*existing, "Some Data");
+        drop(existing);
+        trie.get_or_insert_with(addresses[0], String::new).push_str(" Modified");
+        expected.update(&addresses[0], "Some Data Modified".to_string()).unwrap();
+        assert_eq!(trie.root_hash(), expected.root_hash());
+        assert!(trie.verify_integrity());
+    }
+
+    #[test]
+    fn keys_are_stored_at_their_hashed_addresses_independently() {
+        let mut trie: Trie<String> = Trie::default();
+        trie.insert_key("alice", "Some Data".to_string()).unwrap();
+        trie.insert_key(b"bob", "Some More Data".to_string()).unwrap();
+        assert_ne!(Trie::<String>::key_address("alice"), Trie::<String>::key_address("bob"));
+        assert_eq!(trie.len(), 2);
+
+        assert_eq!(trie.get_key("alice"), Some(&"Some Data".to_string()));
+        assert_eq!(trie.get_key("bob"), Some(&"Some More Data".to_string()));
+        assert_eq!(trie.get_key("carol"), None);
+        assert!(trie.contains(&Sha256Algorithm::hash(b"alice")));
+        assert_eq!(
+            trie.insert_key("alice", "Other Data".to_string()),
+            Err(InvalidInsertError::DuplicateAddress)
+        );
+
+        let removed = trie.remove_key("alice").unwrap();
+        assert_eq!(removed.get_address(), Trie::<String>::key_address("alice"));
+        assert!(!trie.contains_key("alice"));
+        assert!(trie.contains_key("bob"));
+        assert!(trie.remove_key("alice").is_none());
+
+        let keccak = Trie::<String, Keccak256Algorithm>::key_address("alice");
+        assert_eq!(keccak, Keccak256Algorithm::hash(b"alice"));
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_leaves() {
+        let mut rng = StdRng::seed_from_u64(537);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(30, 2, &mut rng));
+        let trie = trie_from(&addresses, "Some Data");
+        assert!(trie.diff(&trie.clone()).is_empty());
+
+        let mut changed = trie.clone();
+        changed.update(&addresses[10], "Other Data".to_string()).unwrap();
+        let diff = trie.diff(&changed);
+        assert_eq!(diff.changed, vec![addresses[10]]);
+        assert!(diff.only_in_self.is_empty() && diff.only_in_other.is_empty());
+
+        // a2 sits in a fork with a1 in `trie`, and a1 becomes a leaf once
+        // a2 is removed, so the diff compares a fork against a leaf.
+        let mut other = changed.clone();
+        other.remove(&addresses[4]);
+        other.add(Leaf::new([201u8; 32], "Some Data".to_string())).unwrap();
+        let diff = trie.diff(&other);
+        assert_eq!(diff.only_in_self, vec![addresses[4]]);
+        assert_eq!(diff.only_in_other, vec![[201u8; 32]]);
+        assert_eq!(diff.changed, vec![addresses[10]]);
+
+        let reversed = other.diff(&trie);
+        assert_eq!(reversed.only_in_self, diff.only_in_other);
+        assert_eq!(reversed.only_in_other, diff.only_in_self);
+        assert_eq!(reversed.changed, diff.changed);
+    }
+
+    #[test]
+    fn merge_adds_disjoint_leaves_and_resolves_conflicts() {
+        let mut rng = StdRng::seed_from_u64(538);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(20, 2, &mut rng));
+        let (left, right) = addresses.split_at(12);
+
+        let mut merged = trie_from(left, "Some Data");
+        merged.merge(trie_from(right, "Some Data"), |_, _| panic!("The tries are disjoint"));
+        assert_eq!(merged.root_hash(), trie_from(&addresses, "Some Data").root_hash());
+
+        let mut ours = trie_from(&addresses[..16], "Ours");
+        let theirs = trie_from(&addresses[8..], "Theirs");
+        ours.merge(theirs, |ours, theirs| {
+            assert_eq!((ours.as_str(), theirs.as_str()), ("Ours", "Theirs"));
+            "Resolved".to_string()
+        });
+
+        let mut expected = trie_from(&addresses[..8], "Ours");
+        for address in &addresses[8..16] {
+            expected.add(Leaf::new(*address, "Resolved".to_string())).unwrap();
+        }
+        for address in &addresses[16..] {
+            expected.add(Leaf::new(*address, "Theirs".to_string())).unwrap();
+        }
+        assert_eq!(ours.root_hash(), expected.root_hash());
+        assert!(ours.structural_eq(&expected));
+    }
+
+    #[test]
+    fn display_renders_occupied_nibbles_and_forks() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [0u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[1] = 1;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+
+        let rendered = trie.to_string();
+        assert_eq!(rendered.lines().count(), 4);
+        assert!(rendered.contains(&hex(&[0u8; 32])));
+        assert!(rendered.contains(&hex(&address)));
+        assert_eq!(rendered.matches(" fork ").count(), 1);
+        assert!(rendered.contains(&hex(&trie.root_hash())));
+
+        assert_eq!(Trie::<String>::default().to_string().lines().count(), 1);
+    }
+
+    #[test]
+    fn to_dot_has_a_node_per_occupied_slot() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let dot = trie.to_dot();
+        assert!(dot.starts_with("digraph trie {\n"));
+        assert!(dot.ends_with("}\n"));
+
+        let forks = trie.iter_forks().count();
+        let slots = dot.lines().filter(|line| line.contains(" leaf ") || line.contains(" fork\\n")).count();
+        assert_eq!(slots, addresses.len() + forks);
+        assert_eq!(dot.matches("shape=ellipse").count(), addresses.len());
+        assert_eq!(dot.matches("shape=diamond").count(), forks);
+        assert_eq!(dot.matches("label=\"branch ").count(), forks + 1);
+        let edges = dot.lines().filter(|line| line.contains(" -> ")).count();
+        assert_eq!(edges, addresses.len() + 2 * forks + 1);
+    }
+
+    fn hex(hash: &[u8]) -> String {
+        hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn keccak256_matches_published_vectors() {
+        assert_eq!(
+            hex(&Keccak256Algorithm::hash(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex(&Keccak256Algorithm::hash(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        assert_ne!(Keccak256Algorithm::algorithm_id(), Sha256Algorithm::algorithm_id());
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn blake3_matches_published_vectors() {
+        use crate::hash::Blake3Algorithm;
+        assert_eq!(
+            hex(&Blake3Algorithm::hash(b"")),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+        assert_eq!(
+            hex(&Blake3Algorithm::hash(b"abc")),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(Blake3Algorithm::hash_size(), 32);
+        assert_ne!(Blake3Algorithm::algorithm_id(), Sha256Algorithm::algorithm_id());
+        assert_ne!(Blake3Algorithm::algorithm_id(), Keccak256Algorithm::algorithm_id());
+    }
+
+    /// Reads the bytes of a hash of `A` as a hash of `B`.
+    fn hash_as<A: Hasher, B: Hasher>(hash: &A::Hash) -> B::Hash {
+        B::Hash::try_from(hash.as_ref().to_vec()).ok().expect("both hashers have 32 byte hashes")
+    }
+
+    /// Hands the steps of a `Proof` built under `A` to a verifier using `B`.
+    fn proof_as<A: Hasher, B: Hasher>(proof: &Proof<A>) -> Proof<B> {
+        let steps = proof
+            .steps
+            .iter()
+            .map(|step| ProofStep {
+                nibble: step.nibble,
+                siblings: step
+                    .siblings
+                    .iter()
+                    .map(|sibling| match sibling {
+                        Sibling::Left(hash) => Sibling::Left(hash_as::<A, B>(hash)),
+                        Sibling::Right(hash) => Sibling::Right(hash_as::<A, B>(hash)),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Proof { steps }
+    }
+
+    /// Checks that the proofs of a `Trie` hashed with `A` verify against its
+    /// root under `A` only, and neither against the root of the same leaves
+    /// hashed with `B` nor when verified under `B`, and the other way round.
+    fn assert_proofs_are_hasher_specific<A: Hasher, B: Hasher>() {
+        let addresses = set_operation_addresses();
+        let payload = "Some Data".to_string();
+        let mut a: Trie<String, A> = Trie::with_hasher();
+        let mut b: Trie<String, B> = Trie::with_hasher();
+        for address in &addresses {
+            a.add(Leaf::with_hasher(*address, payload.clone())).unwrap();
+            b.add(Leaf::with_hasher(*address, payload.clone())).unwrap();
+        }
+        let (root_a, root_b) = (a.root_hash(), b.root_hash());
+        assert_ne!(root_a.as_ref(), root_b.as_ref());
+
+        for address in &addresses {
+            let (proof_a, proof_b) = (a.prove(address).unwrap(), b.prove(address).unwrap());
+            assert!(proof_a.verify(&root_a, address, &payload));
+            assert!(proof_b.verify(&root_b, address, &payload));
+
+            assert!(!proof_a.verify(&hash_as::<B, A>(&root_b), address, &payload));
+            assert!(!proof_b.verify(&hash_as::<A, B>(&root_a), address, &payload));
+            assert!(!proof_as::<A, B>(&proof_a).verify(&root_b, address, &payload));
+            assert!(!proof_as::<B, A>(&proof_b).verify(&root_a, address, &payload));
+            assert!(!proof_as::<A, B>(&proof_a).verify(&hash_as::<A, B>(&root_a), address, &payload));
+        }
+
+        let absent = [7u8; 32];
+        let exclusion_a = a.prove_absence(&absent).unwrap();
+        assert!(exclusion_a.verify(&root_a, &absent));
+        assert!(!exclusion_a.verify(&hash_as::<B, A>(&root_b), &absent));
+    }
+
+    #[test]
+    fn proofs_only_verify_under_the_hasher_they_were_built_with() {
+        assert_proofs_are_hasher_specific::<Sha256Algorithm, Keccak256Algorithm>();
+        assert_proofs_are_hasher_specific::<Keccak256Algorithm, Sha256Algorithm>();
+        assert_proofs_are_hasher_specific::<Sha256Algorithm, CountingHasher>();
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn sha256_and_blake3_proofs_reject_each_others_roots() {
+        use crate::hash::Blake3Algorithm;
+        assert_proofs_are_hasher_specific::<Sha256Algorithm, Blake3Algorithm>();
+        assert_proofs_are_hasher_specific::<Blake3Algorithm, Sha256Algorithm>();
+        assert_proofs_are_hasher_specific::<Blake3Algorithm, Keccak256Algorithm>();
+    }
+
+    #[test]
+    fn partial_trie_from_proof_has_the_root_of_the_source_trie() {
+        let mut rng = StdRng::seed_from_u64(446);
+        let mut addresses = testutil::random_addresses(200, &mut rng);
+        addresses.extend(testutil::clustered_addresses(50, 3, &mut rng));
+        let mut trie: Trie<String> = Trie::default();
+        for (i, address) in addresses.iter().enumerate() {
+            trie.add(Leaf::new(*address, i.to_string())).unwrap();
+        }
+        let root = trie.root_hash();
+
+        for (i, address) in addresses.iter().enumerate() {
+            let payload = i.to_string();
+            let proof = trie.prove(address).unwrap();
+            let partial = PartialTrie::from_proof(&proof, address, &payload).unwrap();
+            assert_eq!(partial.root_hash(), root);
+            assert_eq!(partial.get(address), Some(&payload));
+            assert!(addresses.iter().filter(|other| *other != address).all(|other| partial.get(other).is_none()));
+
+            let other = "Other Data".to_string();
+            assert_ne!(PartialTrie::from_proof(&proof, address, &other).unwrap().root_hash(), root);
+        }
+
+        let proof = trie.prove(&addresses[0]).unwrap();
+        let payload = "0".to_string();
+        assert_eq!(PartialTrie::from_proof(&proof, &addresses[1], &payload).unwrap_err(), ProofError::InvalidPath);
+        assert_eq!(PartialTrie::from_proof(&Proof::<Sha256Algorithm> { steps: vec![] }, &addresses[0], &payload).unwrap_err(), ProofError::InvalidPath);
+    }
+
+    #[test]
+    fn merging_partial_tries_of_the_same_root_keeps_the_root() {
+        let mut rng = StdRng::seed_from_u64(447);
+        let mut addresses = testutil::random_addresses(100, &mut rng);
+        addresses.extend(testutil::clustered_addresses(40, 3, &mut rng));
+        let payload = "Some Data".to_string();
+        let mut trie = trie_from(&addresses, &payload);
+        let root = trie.root_hash();
+        let partial = |address: &[u8; 32]| PartialTrie::from_proof(&trie.prove(address).unwrap(), address, &payload).unwrap();
+
+        let mut merged = partial(&addresses[0]);
+        for (i, address) in addresses.iter().enumerate().skip(1) {
+            merged.merge_partial(partial(address)).unwrap();
+            assert_eq!(merged.root_hash(), root);
+            assert!(addresses[..=i].iter().all(|address| merged.get(address) == Some(&payload)));
+            assert!(addresses[i + 1..].iter().all(|address| merged.get(address).is_none()));
+        }
+        merged.merge_partial(partial(&addresses[7])).unwrap();
+        assert_eq!(merged.root_hash(), root);
+
+        let mut other = trie.clone();
+        other.replace(&addresses[3], "Other Data".to_string()).unwrap();
+        let conflicting = PartialTrie::from_proof(&other.prove(&addresses[3]).unwrap(), &addresses[3], &"Other Data".to_string()).unwrap();
+        let mut half = partial(&addresses[0]);
+        let before = half.root_hash();
+        assert_eq!(half.merge_partial(conflicting.clone()), Err(ProofError::Conflict));
+        assert_eq!(half.root_hash(), before);
+        assert_eq!(merged.merge_partial(conflicting), Err(ProofError::Conflict));
+        assert_eq!(merged.root_hash(), root);
+
+        let mut proof = trie.prove(&addresses[5]).unwrap();
+        let sibling = &mut proof.steps[0].siblings[0];
+        *sibling = match sibling {
+            Sibling::Left(hash) => Sibling::Left(Sha256Algorithm::hash(hash)),
+            Sibling::Right(hash) => Sibling::Right(Sha256Algorithm::hash(hash)),
+        };
+        let forged = PartialTrie::from_proof(&proof, &addresses[5], &payload).unwrap();
+        assert_eq!(partial(&addresses[5]).merge_partial(forged), Err(ProofError::Conflict));
+    }
+
+    #[test]
+    fn deduplicated_proofs_expand_to_proofs_that_still_verify() {
+        let mut rng = StdRng::seed_from_u64(421);
+        let mut addresses = testutil::random_addresses(300, &mut rng);
+        addresses.extend(testutil::clustered_addresses(60, 3, &mut rng));
+        let payload = "Some Data".to_string();
+        let mut trie = trie_from(&addresses, &payload);
+        let root = trie.root_hash();
+
+        let mut proofs: Vec<Proof> = addresses.iter().map(|address| trie.prove(address).unwrap()).collect();
+        proofs.push(proofs[0].clone());
+        let multi = dedup_proofs(&proofs).unwrap();
+        assert_eq!(multi.len(), addresses.len());
+        let siblings: usize = proofs.iter().flat_map(|proof| &proof.steps).map(|step| step.siblings.len()).sum();
+        assert!(multi.hash_count() < siblings / 2);
+
+        let expanded = multi.expand();
+        assert_eq!(expanded.len(), addresses.len());
+        for (address, proof) in addresses.iter().zip(&proofs) {
+            let on_path = |expanded: &&Proof| expanded.steps.iter().enumerate().all(|(layer, step)| step.nibble == address[layer]);
+            let expanded = expanded.iter().find(on_path).unwrap();
+            assert_eq!(expanded, proof);
+            assert!(expanded.verify(&root, address, &payload));
+        }
+        assert_eq!(dedup_proofs(&multi.expand()).unwrap(), multi);
+
+        let mut other = trie.clone();
+        other.replace(&addresses[3], "Other Data".to_string()).unwrap();
+        let conflicting = [trie.prove(&addresses[0]).unwrap(), other.prove(&addresses[0]).unwrap()];
+        assert_eq!(dedup_proofs(&conflicting).unwrap_err(), ProofError::Conflict);
+        assert_eq!(dedup_proofs(&[Proof::<Sha256Algorithm> { steps: vec![] }]).unwrap_err(), ProofError::InvalidPath);
+    }
+
+    /// Returns the `MultiProof` the wire format test vectors are built from:
+    /// the proofs of `[0; 32]`, forked from `[0, 1, 0, ..]`, and of `[3; 32]`
+    /// in a `Trie` of five leaves.
+    fn multi_proof_vector_source() -> (Trie<String>, MultiProof) {
+        let mut forked = [0u8; 32];
+        forked[1] = 1;
+        let mut trie: Trie<String> = Trie::default();
+        for address in [[0u8; 32], forked, [2u8; 32], [3u8; 32], [4u8; 32]] {
+            trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        }
+        let multi = dedup_proofs(&[trie.prove(&[3u8; 32]).unwrap(), trie.prove(&[0u8; 32]).unwrap()]).unwrap();
+        (trie, multi)
+    }
+
+    #[test]
+    fn multi_proof_bytes_match_test_vectors() {
+        let (mut trie, multi) = multi_proof_vector_source();
+        let expected = concat!(
+            // Sha256Algorithm, two paths.
+            "01", "00000002",
+            // [0; 32]: left twice in the Root Branch, then left in its Fork.
+            "0002", "000200", "000100",
+            // [3; 32]: right then left in the Root Branch.
+            "0001", "030280",
+            // The sibling hashes in the Root Branch, at left, left-right,
+            // right and right-right, then in the Fork at nibble 0, at right.
+            "225f5d45ed3bd9ee041fe3847d62a547e0795222d685b463fe56ea617b834656",
+            "8b6cd2ebcd68871c6ebec37342a714a3c9f740fb701a7b5938b263bda74c6044",
+            "fbe6b7c466891d767e088f5c6f8fc47005d18b9c1cda9c703f11b96bb4ac61a5",
+            "92deee2cacc93342edfcfb5fc078490eb9a470e4a71f6e199f6cd697b5d37c90",
+            "5c3febf6db5a2e7971e88db20a6d844da52df3564577e91c066ec9539a993b31",
+        );
+        assert_eq!(hex(&multi.to_bytes()), expected);
+        assert_eq!(MultiProof::from_bytes(&multi.to_bytes()).unwrap(), multi);
+        for (address, proof) in [[0u8; 32], [3u8; 32]].iter().zip(multi.expand()) {
+            assert!(proof.verify(&trie.root_hash(), address, &"Some Data".to_string()));
+        }
+
+        let mut bytes = vec![1, 0, 0, 0, 1, 0, 1, 5, 2, 0x40];
+        bytes.extend([0x11u8; 32]);
+        bytes.extend([0x22u8; 32]);
+        let decoded: MultiProof = MultiProof::from_bytes(&bytes).unwrap();
+        let step = ProofStep { nibble: 5, siblings: vec![Sibling::Left([0x11u8; 32]), Sibling::Right([0x22u8; 32])] };
+        assert_eq!(decoded.expand(), vec![Proof { steps: vec![step] }]);
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn multi_proof_bytes_round_trip_and_reject_malformed_input() {
+        let mut rng = StdRng::seed_from_u64(432);
+        let mut addresses = testutil::random_addresses(200, &mut rng);
+        addresses.extend(testutil::clustered_addresses(40, 3, &mut rng));
+        let trie = trie_from(&addresses, "Some Data");
+        let proofs: Vec<Proof> = addresses.iter().step_by(3).map(|address| trie.prove(address).unwrap()).collect();
+        let multi = dedup_proofs(&proofs).unwrap();
+        let bytes = multi.to_bytes();
+        assert_eq!(MultiProof::from_bytes(&bytes).unwrap(), multi);
+        assert_eq!(bytes.len(), 5 + multi.expand().iter().map(|proof| 2 + 3 * proof.path_len()).sum::<usize>() + 32 * multi.hash_count());
+
+        let decode = |bytes: &[u8]| MultiProof::<Sha256Algorithm>::from_bytes(bytes).unwrap_err();
+        assert_eq!(decode(&bytes[..bytes.len() - 1]), DecodeError::UnexpectedEnd);
+        assert_eq!(decode(&[bytes.as_slice(), &[0]].concat()), DecodeError::TrailingBytes);
+        assert_eq!(MultiProof::<Keccak256Algorithm>::from_bytes(&bytes).unwrap_err(), DecodeError::AlgorithmMismatch(1));
+
+        let (_, vector) = multi_proof_vector_source();
+        let bytes = vector.to_bytes();
+        let patched = |offset: usize, byte: u8| {
+            let mut bytes = bytes.clone();
+            bytes[offset] = byte;
+            bytes
+        };
+        // Paths out of order, an empty path, a bit set past the depth, and
+        // deeper than a Merkle tree of 256 slots.
+        assert_eq!(decode(&patched(7, 5)), DecodeError::InvalidPath);
+        assert_eq!(decode(&patched(6, 0)), DecodeError::InvalidPath);
+        assert_eq!(decode(&patched(9, 0x20)), DecodeError::InvalidPath);
+        assert_eq!(decode(&patched(8, 9)), DecodeError::InvalidPath);
+    }
+
+    #[test]
+    fn hash_reader_matches_hashing_the_bytes_read() {
+        let data: Vec<u8> = (0..1 << 20).map(|i| (i % 251) as u8).collect();
+        assert_eq!(Sha256Algorithm::hash_reader(&data[..]).unwrap(), Sha256Algorithm::hash(&data));
+        assert_eq!(Keccak256Algorithm::hash_reader(&b""[..]).unwrap(), Keccak256Algorithm::hash(b""));
+        #[cfg(feature = "blake3")]
+        {
+            use crate::hash::Blake3Algorithm;
+            assert_eq!(Blake3Algorithm::hash_reader(&data[..]).unwrap(), Blake3Algorithm::hash(&data));
+            assert_eq!(Blake3Algorithm::hash_reader(&b"abc"[..]).unwrap(), Blake3Algorithm::hash(b"abc"));
+        }
+    }
+
+    #[test]
+    fn keccak_trie_commits_with_keccak_hashes() {
+        let mut trie = Trie::<String, Keccak256Algorithm>::default();
+        let leaf: Leaf<String, Keccak256Algorithm> = Leaf::with_hasher([0u8; 32], "Some Data".to_string());
+        trie.add(leaf.clone()).unwrap();
+
+        let mut to_hash = [0u8; 32].to_vec();
+        to_hash.extend(b"Some Data");
+        assert_eq!(leaf.get_hash(), Keccak256Algorithm::hash(&to_hash));
+        let slot = slot_hash::<Keccak256Algorithm>(0, &leaf.get_hash());
+        assert_eq!(trie.root.get_next().get_hash(), slot);
+        assert_eq!(trie.root.get_hash(), Keccak256Algorithm::hash(&slot));
+        assert_ne!(trie.root.get_hash(), trie_from(&[[0u8; 32]], "Some Data").root.get_hash());
+    }
+
+    #[test]
+    fn branch_with_a_single_child_hashes_to_the_slot_hash() {
+        let mut branch: Branch<String> = Branch::new(Layer::Zero);
+        let leaf: Leaf<String> = Leaf::new([9u8; 32], "Some Data".to_string());
+        branch.insert(leaf.clone());
+        let slot = slot_hash::<Sha256Algorithm>(9, &leaf.get_hash());
+        assert_eq!(branch.get_hash(), Sha256Algorithm::concat_and_hash(&slot, None));
+
+        let other: Leaf<String> = Leaf::new([7u8; 32], "Some More Data".to_string());
+        let third: Leaf<String> = Leaf::new([200u8; 32], "Even More Data".to_string());
+        branch.insert(other.clone());
+        branch.insert(third.clone());
+        let pair = Sha256Algorithm::concat_and_hash(
+            &slot_hash::<Sha256Algorithm>(7, &other.get_hash()),
+            Some(&slot),
+        );
+        let expected = Sha256Algorithm::concat_and_hash(
+            &pair,
+            Some(&slot_hash::<Sha256Algorithm>(200, &third.get_hash())),
+        );
+        assert_eq!(branch.get_hash(), expected);
+    }
+
+    #[test]
+    fn branch_hash_binds_children_to_their_nibble() {
+        let leaf: Leaf<String> = Leaf::new([1u8; 32], "Some Data".to_string());
+        let node = Node::Data { data: leaf.clone(), hash: leaf.get_hash() };
+
+        let mut at_one: Branch<String> = Branch::new(Layer::Zero);
+        at_one.set(1, node.clone());
+        let mut at_two: Branch<String> = Branch::new(Layer::Zero);
+        at_two.set(2, node);
+
+        assert_ne!(at_one.get_hash(), at_two.get_hash());
+    }
+
+    #[test]
+    fn proof_path_matches_leaf_depth_and_rebuilds_the_root() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+
+        for address in &addresses {
+            let proof = trie.prove(address).unwrap();
+            assert_eq!(proof.steps.len(), leaf_layer(&trie, address) + 1);
+            let nibbles: Vec<u8> = proof.steps.iter().map(|step| step.nibble).collect();
+            assert_eq!(nibbles, path_of(address)[..nibbles.len()]);
+            assert_eq!(proof.compute_root(address, &"Some Data".to_string()), trie.root.get_hash());
+        }
+
+        let mut missing = addresses[3];
+        missing[31] = 1;
+        assert!(trie.prove(&missing).is_none());
+        assert!(trie.prove(&[1u8; 32]).is_none());
+    }
+
+    #[test]
+    fn proof_siblings_follow_the_merkle_reduction_of_a_wide_branch() {
+        let addresses: Vec<[u8; 32]> = (0..5u8).map(|nibble| [nibble; 32]).collect();
+        let trie = trie_from(&addresses, "Some Data");
+
+        let proof = trie.prove(&[4u8; 32]).unwrap();
+        assert_eq!(proof.steps.len(), 1);
+        assert_eq!(proof.steps[0].siblings.len(), 1);
+        assert!(matches!(proof.steps[0].siblings[0], Sibling::Left(_)));
+
+        let proof = trie.prove(&[1u8; 32]).unwrap();
+        assert_eq!(proof.steps[0].siblings.len(), 3);
+        assert!(matches!(proof.steps[0].siblings[0], Sibling::Left(_)));
+        assert!(matches!(proof.steps[0].siblings[1], Sibling::Right(_)));
+        assert!(matches!(proof.steps[0].siblings[2], Sibling::Right(_)));
+        assert_eq!(proof.compute_root(&[1u8; 32], &"Some Data".to_string()), trie.root.get_hash());
+    }
+
+    #[test]
+    fn proving_against_a_clean_trie_hashes_nothing() {
+        let mut rng = StdRng::seed_from_u64(411);
+        let mut addresses = testutil::random_addresses(300, &mut rng);
+        addresses.extend(testutil::clustered_addresses(40, 2, &mut rng));
+        let absent = testutil::random_addresses(20, &mut rng);
+        let mut trie: Trie<String, CountingHasher> = Trie::with_hasher();
+        trie.add_many(addresses.iter().map(|address| Leaf::with_hasher(*address, "Some Data".to_string()))).unwrap();
+
+        let before = hash_calls();
+        let proofs: Vec<_> = addresses.iter().map(|address| trie.prove(address).unwrap()).collect();
+        let exclusions: Vec<_> = absent.iter().map(|address| trie.prove_absence(address).unwrap()).collect();
+        assert_eq!(hash_calls(), before);
+
+        let root = trie.root_hash();
+        let payload = "Some Data".to_string();
+        for (address, proof) in addresses.iter().zip(&proofs) {
+            assert!(proof.verify(&root, address, &payload));
+        }
+        for (address, proof) in absent.iter().zip(&exclusions) {
+            assert!(proof.verify(&root, address));
+        }
+    }
+
+    #[test]
+    fn root_hash_of_a_clone_hashes_nothing() {
+        let mut rng = StdRng::seed_from_u64(449);
+        let mut addresses = testutil::random_addresses(200, &mut rng);
+        addresses.extend(testutil::clustered_addresses(40, 2, &mut rng));
+        let mut trie: Trie<String, CountingHasher> = Trie::with_hasher();
+        for address in &addresses {
+            trie.add(Leaf::with_hasher(*address, "Some Data".to_string())).unwrap();
+        }
+        let root = trie.root_hash();
+
+        let before = hash_calls();
+        let mut clone = trie.clone();
+        assert_eq!(clone.root_hash(), root);
+        assert_eq!(clone.snapshot().root_hash(), root);
+        assert_eq!(clone.root.get_next_ref().get_hash(), trie.root.get_next_ref().get_hash());
+        let proof = clone.prove(&addresses[0]).unwrap();
+        assert_eq!(hash_calls(), before);
+        assert!(proof.verify(&root, &addresses[0], &"Some Data".to_string()));
+    }
+
+    #[test]
+    fn proof_verifies_against_the_root_hash() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let root = trie.root.get_hash();
+        let payload = "Some Data".to_string();
+
+        for address in &addresses {
+            assert!(trie.prove(address).unwrap().verify(&root, address, &payload));
+        }
+    }
+
+    #[test]
+    fn remove_with_proof_proves_the_leaf_against_the_prior_root() {
+        let mut rng = StdRng::seed_from_u64(418);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(30, 2, &mut rng));
+        let mut trie = trie_from(&addresses, "Some Data");
+
+        for address in &addresses {
+            let before = trie.root_hash();
+            let (leaf, proof) = trie.remove_with_proof(address).unwrap();
+            assert_eq!(leaf.get_address(), *address);
+            assert!(proof.verify(&before, address, &leaf.get_payload()));
+            assert!(!proof.verify(&trie.root_hash(), address, &leaf.get_payload()));
+            assert!(!trie.contains(address));
+        }
+        assert!(trie.is_empty());
+
+        let before = trie.root_hash();
+        assert!(trie.remove_with_proof(&addresses[0]).is_none());
+        assert_eq!(trie.root_hash(), before);
+    }
+
+    #[test]
+    fn proof_size_accessors_stay_within_the_documented_limits() {
+        let mut rng = StdRng::seed_from_u64(429);
+        let mut addresses = testutil::random_addresses(300, &mut rng);
+        addresses.extend(testutil::clustered_addresses(30, 29, &mut rng));
+        let trie = trie_from(&addresses, "Some Data");
+
+        for address in &addresses {
+            let proof = trie.prove(address).unwrap();
+            assert_eq!(proof.path_len(), leaf_layer(&trie, address) + 1);
+            assert!(proof.path_len() <= 32);
+            let siblings: usize = proof.steps.iter().map(|step| step.siblings.len()).sum();
+            assert!(proof.steps.iter().all(|step| step.siblings.len() <= 8));
+            assert_eq!(proof.encoded_size(), 2 * proof.path_len() + siblings * 33);
+            assert!(proof.encoded_size() <= 32 * 266);
+        }
+    }
+
+    #[test]
+    fn proof_rejects_a_tampered_payload() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let proof = trie.prove(&addresses[4]).unwrap();
+
+        assert!(!proof.verify(&trie.root.get_hash(), &addresses[4], &"Some Other Data".to_string()));
+    }
+
+    #[test]
+    fn proof_rejects_a_wrong_root() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let proof = trie.prove(&addresses[4]).unwrap();
+        let other = trie_from(&addresses[..4], "Some Data");
+
+        assert!(!proof.verify(&other.root.get_hash(), &addresses[4], &"Some Data".to_string()));
+        assert!(!proof.verify(&[0u8; 32], &addresses[4], &"Some Data".to_string()));
+    }
+
+    #[test]
+    fn proof_rejects_an_address_off_its_path() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let root = trie.root.get_hash();
+        let payload = "Some Data".to_string();
+
+        let proof = trie.prove(&addresses[3]).unwrap();
+        assert!(!proof.verify(&root, &addresses[4], &payload));
+
+        let mut tampered = proof.clone();
+        tampered.steps[0].nibble = 1;
+        assert!(!tampered.verify(&root, &addresses[3], &payload));
+    }
+
+    #[test]
+    fn exclusion_proof_for_an_empty_slot_verifies() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let root = trie.root.get_hash();
+
+        let mut absent = [0u8; 32];
+        absent[1] = 2;
+        let proof = trie.prove_absence(&absent).unwrap();
+        assert_eq!(proof.steps.len(), 1);
+        assert!(matches!(proof.divergence, Divergence::Empty { .. }));
+        assert!(proof.verify(&root, &absent));
+
+        let proof = trie.prove_absence(&[7u8; 32]).unwrap();
+        assert!(proof.steps.is_empty());
+        assert!(proof.verify(&root, &[7u8; 32]));
+        assert!(!proof.verify(&root, &[3u8; 32]));
+
+        let empty: Trie<String> = Trie::default();
+        assert!(empty.prove_absence(&[7u8; 32]).unwrap().verify(&empty.root.get_hash(), &[7u8; 32]));
+    }
+
+    #[test]
+    fn exclusion_proof_for_a_prefix_collision_verifies() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let root = trie.root.get_hash();
+
+        let mut absent = [3u8; 32];
+        absent[31] = 4;
+        let proof = trie.prove_absence(&absent).unwrap();
+        assert_eq!(proof.steps.len(), 2);
+        match &proof.divergence {
+            Divergence::Leaf { address, .. } => assert_eq!(address, &[3u8; 32]),
+            _ => panic!("Expected the path to end at a colliding leaf"),
+        }
+        assert!(proof.verify(&root, &absent));
+        assert!(!proof.verify(&root, &[3u8; 32]));
+
+        let mut absent = addresses[4];
+        absent[10] = 1;
+        let proof = trie.prove_absence(&absent).unwrap();
+        assert_eq!(proof.steps.len(), 4);
+        assert!(proof.verify(&root, &absent));
+    }
+
+    #[test]
+    fn exclusion_proof_rejects_present_addresses_and_tampering() {
+        let addresses = set_operation_addresses();
+        let trie = trie_from(&addresses, "Some Data");
+        let root = trie.root.get_hash();
+        assert!(addresses.iter().all(|address| trie.prove_absence(address).is_none()));
+
+        let proof = trie.prove_absence(&[7u8; 32]).unwrap();
+        assert!(!proof.verify(&trie_from(&addresses[..5], "Some Data").root.get_hash(), &[7u8; 32]));
+
+        let mut hidden = proof.clone();
+        if let Divergence::Empty { occupied } = &mut hidden.divergence {
+            occupied.retain(|(nibble, _)| *nibble != 3);
+        }
+        assert!(!hidden.verify(&root, &[3u8; 32]));
+
+        let mut absent = [3u8; 32];
+        absent[31] = 4;
+        let mut forged = trie.prove_absence(&absent).unwrap();
+        if let Divergence::Leaf { payload, .. } = &mut forged.divergence {
+            payload.push(0);
+        }
+        assert!(!forged.verify(&root, &absent));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_preserves_the_trie() {
+        let mut trie = testutil::build_trie(100);
+        let json = serde_json::to_string(&trie).unwrap();
+        let restored: Trie<String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, trie);
+        assert_eq!(restored.root.get_next().get_hash(), trie.root.get_next().get_hash());
+        let leaves: Vec<([u8; 32], &String)> = trie.iter().collect();
+        assert_eq!(restored.iter().collect::<Vec<_>>(), leaves);
+
+        // `add` left the branches dirty, they stay so once deserialized.
+        assert!(restored.root.get_next_ref().is_dirty());
+        let root = trie.root_hash();
+        let json = serde_json::to_string(&trie).unwrap();
+        let mut restored: Trie<String> = serde_json::from_str(&json).unwrap();
+        assert!(!restored.root.get_next_ref().is_dirty());
+        assert_eq!(restored.root_hash(), root);
+        assert!(restored.verify_integrity());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_skips_unoccupied_nibbles() {
+        let empty: Branch<String> = Branch::new(Layer::Zero);
+        let value = serde_json::to_value(&empty).unwrap();
+        assert_eq!(value["nibbles"], serde_json::json!([]));
+        assert_eq!(value["layer"], serde_json::json!(0));
+
+        let trie = trie_from(&[[9u8; 32]], "Some Data");
+        let value = serde_json::to_value(trie.root.get_next_ref()).unwrap();
+        assert_eq!(value["nibbles"].as_array().unwrap().len(), 1);
+        assert_eq!(value["nibbles"][0][0], serde_json::json!(9));
+    }
+
+    fn byte_trie(addresses: &[[u8; 32]]) -> Trie<Vec<u8>> {
+        let mut trie: Trie<Vec<u8>> = Trie::default();
+        for (i, address) in addresses.iter().enumerate() {
+            trie.add(Leaf::new(*address, vec![i as u8; i])).unwrap();
+        }
+        trie
+    }
+
+    #[test]
+    fn bytes_round_trip_preserves_the_trie() {
+        let mut rng = StdRng::seed_from_u64(516);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(50, 3, &mut rng));
+        let trie = byte_trie(&addresses);
+
+        let restored: Trie<Vec<u8>> = Trie::from_bytes(&trie.to_bytes()).unwrap();
+        assert_eq!(restored.root.get_next().get_hash(), trie.root.get_next().get_hash());
+        assert_eq!(restored.root.get_hash(), trie.root.get_hash());
+        assert_eq!(restored.iter().collect::<Vec<_>>(), trie.iter().collect::<Vec<_>>());
+
+        let empty: Trie<Vec<u8>> = Trie::default();
+        assert_eq!(empty.to_bytes(), vec![Sha256Algorithm::algorithm_id(), 0, 0]);
+        assert_eq!(Trie::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+
+    #[test]
+    fn serialized_size_hint_matches_the_encoded_length() {
+        let mut rng = StdRng::seed_from_u64(417);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(50, 3, &mut rng));
+        let trie = byte_trie(&addresses);
+        assert_eq!(trie.serialized_size_hint(), trie.to_bytes().len());
+
+        let empty: Trie<Vec<u8>> = Trie::default();
+        assert_eq!(empty.serialized_size_hint(), empty.to_bytes().len());
+
+        let mut narrow: Trie<Vec<u8>, Sha256Algorithm, 4> = Trie::new();
+        narrow.insert_key(b"alice", b"Some Data".to_vec()).unwrap();
+        narrow.insert_key(b"bob", b"Some More Data".to_vec()).unwrap();
+        assert_eq!(narrow.serialized_size_hint(), narrow.to_bytes().len());
+    }
+
+    #[test]
+    fn malformed_bytes_fail_to_decode() {
+        let bytes = byte_trie(&set_operation_addresses()).to_bytes();
+        let decode = |bytes: &[u8]| Trie::<Vec<u8>>::from_bytes(bytes).err();
+
+        assert_eq!(decode(&[]), Some(DecodeError::UnexpectedEnd));
+        assert_eq!(decode(&bytes[..bytes.len() - 1]), Some(DecodeError::UnexpectedEnd));
+        assert_eq!(
+            Trie::<Vec<u8>, Keccak256Algorithm>::from_bytes(&bytes).err(),
+            Some(DecodeError::AlgorithmMismatch(Sha256Algorithm::algorithm_id()))
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(decode(&trailing), Some(DecodeError::TrailingBytes));
+
+        // Header, count, then the first occupied nibble and its tag.
+        let mut tag = bytes.clone();
+        tag[4] = 7;
+        assert_eq!(decode(&tag), Some(DecodeError::InvalidTag(7)));
+
+        let mut single = byte_trie(&[[5u8; 32]]).to_bytes();
+        single[3] = 6;
+        assert_eq!(decode(&single), Some(DecodeError::InvalidPath));
+
+        let mut unordered = byte_trie(&[[5u8; 32], [6u8; 32]]).to_bytes();
+        unordered[3] = 7;
+        unordered[5] = 7;
+        assert_eq!(decode(&unordered), Some(DecodeError::InvalidBranch));
+    }
+
+    #[test]
+    fn committed_trie_reloads_by_root_hash() {
+        let mut rng = StdRng::seed_from_u64(517);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(50, 2, &mut rng));
+        let mut store = HashMapStore::new();
+
+        let (root, leaves) = {
+            let trie = byte_trie(&addresses);
+            let leaves: Vec<([u8; 32], Vec<u8>)> = trie.iter().map(|(address, payload)| (address, payload.clone())).collect();
+            (trie.commit(&mut store), leaves)
+        };
+
+        let loaded: Trie<Vec<u8>> = Trie::load(root, &store).unwrap();
+        assert_eq!(loaded.root.get_hash(), root);
+        let reloaded: Vec<([u8; 32], Vec<u8>)> = loaded.iter().map(|(address, payload)| (address, payload.clone())).collect();
+        assert_eq!(reloaded, leaves);
+
+        let committed = store.len();
+        let mut grown = loaded.clone();
+        grown.add(Leaf::new([250u8; 32], b"Some Data".to_vec())).unwrap();
+        let grown_root = grown.commit(&mut store);
+        assert_eq!(store.len(), committed + 3);
+        assert_eq!(Trie::<Vec<u8>>::load(grown_root, &store).unwrap(), grown);
+        assert_eq!(Trie::<Vec<u8>>::load(root, &store).unwrap(), loaded);
+
+        let mut sized = HashMapStore::with_capacity(3 * addresses.len());
+        assert!(sized.is_empty());
+        loaded.commit(&mut sized);
+        assert!(sized.len() <= 3 * addresses.len());
+    }
+
+    #[test]
+    fn loading_a_missing_or_tampered_trie_fails() {
+        let addresses = set_operation_addresses();
+        let trie = byte_trie(&addresses);
+        let mut store = HashMapStore::new();
+        let root = trie.commit(&mut store);
+
+        let load = |root, store: &HashMapStore| Trie::<Vec<u8>>::load(root, store).err();
+        assert_eq!(load([9u8; 32], &store), Some(DecodeError::MissingNode));
+        assert_eq!(load(root, &HashMapStore::new()), Some(DecodeError::MissingNode));
+
+        let leaf = trie.get_by_address(&addresses[2]).get_hash().unwrap();
+        let mut tampered = store.clone();
+        let mut record = addresses[2].to_vec();
+        record.extend(b"Other Data");
+        NodeStore::<Sha256Algorithm>::put(&mut tampered, leaf, record);
+        assert_eq!(load(root, &tampered), Some(DecodeError::HashMismatch));
+
+        let mut moved = store.clone();
+        NodeStore::<Sha256Algorithm>::put(&mut moved, leaf, addresses[1].to_vec());
+        assert_eq!(load(root, &moved), Some(DecodeError::InvalidPath));
+    }
+
+    #[test]
+    fn try_get_tells_absent_addresses_from_unreadable_nodes() {
+        let mut rng = StdRng::seed_from_u64(435);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(30, 2, &mut rng));
+        let trie = byte_trie(&addresses);
+        let mut store = HashMapStore::new();
+        let root = trie.commit(&mut store);
+
+        let try_get = |store: &HashMapStore, address: &[u8; 32]| Trie::<Vec<u8>>::try_get(root, store, address);
+        for (address, payload) in trie.iter() {
+            assert_eq!(try_get(&store, &address), Ok(Some(payload.clone())));
+            assert_eq!(Trie::<Vec<u8>>::get_stored(root, &store, &address), Some(payload.clone()));
+        }
+        let mut diverging = addresses[3];
+        diverging[31] ^= 1;
+        assert_eq!(try_get(&store, &diverging), Ok(None));
+        for address in testutil::random_addresses(10, &mut rng) {
+            assert_eq!(try_get(&store, &address), Ok(None));
+        }
+
+        assert_eq!(try_get(&HashMapStore::new(), &addresses[0]), Err(DecodeError::MissingNode));
+        assert_eq!(Trie::<Vec<u8>>::try_get([9u8; 32], &store, &addresses[0]), Err(DecodeError::MissingNode));
+
+        let leaf = trie.get_by_address(&addresses[2]).get_hash().unwrap();
+        let mut tampered = store.clone();
+        let mut record = addresses[2].to_vec();
+        record.extend(b"Other Data");
+        NodeStore::<Sha256Algorithm>::put(&mut tampered, leaf, record);
+        assert_eq!(try_get(&tampered, &addresses[2]), Err(DecodeError::HashMismatch));
+        assert_eq!(try_get(&tampered, &addresses[0]), try_get(&store, &addresses[0]));
+    }
+
+    /// A `NodeStore` counting how many records are read from it.
+    struct CountingStore {
+        store: HashMapStore,
+        reads: Cell<usize>,
+    }
+
+    impl NodeStore for CountingStore {
+        fn get(&self, hash: &[u8; 32]) -> Option<Vec<u8>> {
+            self.reads.set(self.reads.get() + 1);
+            NodeStore::<Sha256Algorithm>::get(&self.store, hash)
+        }
+
+        fn put(&mut self, hash: [u8; 32], bytes: Vec<u8>) {
+            NodeStore::<Sha256Algorithm>::put(&mut self.store, hash, bytes);
+        }
+    }
+
+    #[test]
+    fn prefetched_paths_are_read_from_memory() {
+        let mut rng = StdRng::seed_from_u64(433);
+        let mut addresses = testutil::random_addresses(300, &mut rng);
+        addresses.extend(testutil::clustered_addresses(60, 3, &mut rng));
+        let trie = byte_trie(&addresses);
+        let mut store = CountingStore { store: HashMapStore::new(), reads: Cell::new(0) };
+        let root = trie.commit(&mut store);
+
+        let cached: CachedStore<_> = CachedStore::new(store);
+        let (warm, cold) = addresses.split_at(100);
+        cached.prefetch(&root, warm).unwrap();
+        let read = cached.store().reads.get();
+        assert!(read > warm.len() && read == cached.cached());
+
+        for address in warm {
+            let payload = Trie::<Vec<u8>>::try_get(root, &cached, address).unwrap();
+            assert_eq!(payload.as_ref(), trie.iter().find(|(stored, _)| stored == address).map(|(_, payload)| payload));
+        }
+        assert_eq!(Trie::<Vec<u8>>::prove_at(root, &cached, &warm[0]).unwrap(), trie.prove(&warm[0]));
+        assert_eq!(cached.store().reads.get(), read);
+
+        cached.prefetch(&root, cold).unwrap();
+        assert!(cached.store().reads.get() > read);
+        assert_eq!(cached.prefetch(&[9u8; 32], warm), Err(DecodeError::MissingNode));
+    }
+
+    #[test]
+    fn prove_at_proves_against_a_past_root() {
+        let mut rng = StdRng::seed_from_u64(443);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(30, 2, &mut rng));
+        let mut trie = byte_trie(&addresses);
+        let mut store = HashMapStore::new();
+        let before = trie.commit(&mut store);
+        let old = trie.clone();
+
+        trie.remove(&addresses[2]);
+        trie.update(&addresses[5], vec![0xff; 3]).unwrap();
+        let after = trie.commit(&mut store);
+        assert_ne!(before, after);
+
+        let prove_at = |root, store: &HashMapStore, address: &[u8; 32]| Trie::<Vec<u8>>::prove_at(root, store, address);
+        for (address, payload) in old.iter() {
+            let proof = prove_at(before, &store, &address).unwrap().unwrap();
+            assert_eq!(proof.path_len(), old.prove(&address).unwrap().path_len());
+            assert!(proof.verify(&before, &address, payload));
+        }
+        let proof = prove_at(before, &store, &addresses[5]).unwrap().unwrap();
+        assert!(proof.verify(&before, &addresses[5], &vec![5u8; 5]));
+        assert!(!proof.verify(&after, &addresses[5], &vec![5u8; 5]));
+        assert!(prove_at(after, &store, &addresses[5]).unwrap().unwrap().verify(&after, &addresses[5], &vec![0xffu8; 3]));
+        assert!(matches!(prove_at(after, &store, &addresses[2]), Ok(None)));
+
+        let mut pruned = HashMapStore::new();
+        trie.commit(&mut pruned);
+        assert!(matches!(prove_at(before, &pruned, &addresses[2]), Err(DecodeError::MissingNode)));
+    }
+
+    #[test]
+    fn remove_many_matches_rebuilding_from_the_survivors() {
+        let mut rng = StdRng::seed_from_u64(444);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(40, 2, &mut rng));
+        addresses.extend(testutil::random_addresses(40, &mut rng));
+        let mut trie = byte_trie(&addresses);
+
+        let mut evicted: Vec<[u8; 32]> = addresses.iter().copied().step_by(3).collect();
+        evicted.extend(testutil::random_addresses(5, &mut rng));
+        evicted.sort();
+        let survivors: Vec<[u8; 32]> = addresses.iter().copied().filter(|address| !evicted.contains(address)).collect();
+
+        assert_eq!(trie.remove_many(&evicted), addresses.len() - survivors.len());
+        let mut expected: Trie<Vec<u8>> = Trie::default();
+        for (i, address) in addresses.iter().enumerate() {
+            if survivors.contains(address) {
+                expected.add(Leaf::new(*address, vec![i as u8; i])).unwrap();
+            }
+        }
+        assert_eq!(trie.root_hash(), expected.root_hash());
+        assert!(trie.structural_eq(&expected));
+        assert!(trie.verify_integrity());
+
+        let counting = || {
+            let mut trie: Trie<Vec<u8>, CountingHasher> = Trie::with_hasher();
+            for (i, address) in addresses.iter().enumerate() {
+                trie.add(Leaf::with_hasher(*address, vec![i as u8; i])).unwrap();
+            }
+            trie
+        };
+        let (mut bulk, mut one_by_one) = (counting(), counting());
+        let calls = hash_calls();
+        bulk.remove_many(&evicted);
+        let bulk_calls = hash_calls() - calls;
+        let calls = hash_calls();
+        for address in &evicted {
+            one_by_one.remove(address);
+        }
+        assert!(bulk_calls < hash_calls() - calls);
+        assert_eq!(bulk.root_hash(), one_by_one.root_hash());
+        assert_eq!(trie.remove_many(&evicted), 0);
+    }
+
+    #[test]
+    fn range_proofs_hold_exactly_the_leaves_in_the_range() {
+        let mut rng = StdRng::seed_from_u64(452);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(40, 2, &mut rng));
+        addresses.extend(testutil::random_addresses(40, &mut rng));
+        let mut trie = byte_trie(&addresses);
+        let root = trie.root_hash();
+
+        let mut sorted = addresses.clone();
+        sorted.sort();
+        let mut between = sorted[20];
+        between[31] = between[31].wrapping_add(1);
+        let ranges = [
+            (sorted[10], sorted[50]),
+            (between, sorted[60]),
+            ([0u8; 32], [0xffu8; 32]),
+            (sorted[30], sorted[30]),
+            (sorted[70], sorted[5]),
+        ];
+        for (start, end) in ranges {
+            let proof = trie.prove_range(&start, &end);
+            assert!(proof.verify_range(&root, &start, &end));
+            let expected: Vec<([u8; 32], Vec<u8>)> = trie
+                .iter()
+                .filter(|(address, _)| start <= *address && *address < end)
+                .map(|(address, payload)| (address, payload.clone()))
+                .collect();
+            let leaves: Vec<([u8; 32], Vec<u8>)> =
+                proof.leaves(&start, &end).into_iter().map(|(address, payload)| (address, payload.to_vec())).collect();
+            assert_eq!(leaves, expected);
+        }
+
+        let (start, end) = (sorted[10], sorted[50]);
+        let proof = trie.prove_range(&start, &end);
+        assert!(!proof.verify_range(&trie_from(&addresses, "Some Data").root_hash(), &start, &end));
+        assert!(!proof.verify_range(&root, &start, &sorted[51]));
+        assert!(!proof.verify_range(&root, &sorted[9], &end));
+
+        let omit = |slots: &mut Vec<(u8, RangeNode)>| {
+            let hidden = slots.iter_mut().find(|(_, node)| matches!(node, RangeNode::Leaf { address, .. } if *address >= start && *address < end));
+            let (_, node) = hidden.expect("the range opens a leaf of the root branch");
+            if let RangeNode::Leaf { address, payload } = node {
+                let leaf: Leaf<Vec<u8>> = Leaf::new(*address, payload.clone());
+                *node = RangeNode::Hash(leaf.get_hash());
+            }
+        };
+        let mut omitted = proof.clone();
+        omit(&mut omitted.slots);
+        assert!(!omitted.verify_range(&root, &start, &end));
+
+        let mut tampered = proof.clone();
+        for (_, node) in tampered.slots.iter_mut() {
+            if let RangeNode::Leaf { payload, .. } = node {
+                payload.push(0);
+                break;
+            }
+        }
+        assert!(!tampered.verify_range(&root, &start, &end));
+
+        let mut unordered = proof.clone();
+        unordered.slots.reverse();
+        assert!(!unordered.verify_range(&root, &start, &end));
+    }
+
+    /// Returns the path of every `RangeNode::Leaf` in `slots` lying in
+    /// `[start, end)`, as the indices of the slots leading to it.
+    fn in_range_leaves(slots: &[(u8, RangeNode)], start: &[u8; 32], end: &[u8; 32], path: &mut Vec<usize>, found: &mut Vec<Vec<usize>>) {
+        for (index, (_, node)) in slots.iter().enumerate() {
+            path.push(index);
+            match node {
+                RangeNode::Leaf { address, .. } if start <= address && address < end => found.push(path.clone()),
+                RangeNode::Fork(slots) => in_range_leaves(slots, start, end, path, found),
+                _ => {}
+            }
+            path.pop();
+        }
+    }
+
+    #[test]
+    fn range_proofs_reject_in_range_leaves_given_by_hash() {
+        let mut rng = StdRng::seed_from_u64(452);
+        let mut addresses = testutil::clustered_addresses(60, 2, &mut rng);
+        addresses.extend(testutil::random_addresses(60, &mut rng));
+        let mut trie = byte_trie(&addresses);
+        let root = trie.root_hash();
+        let mut sorted = addresses.clone();
+        sorted.sort();
+        let (start, end) = (sorted[20], sorted[80]);
+        let proof = trie.prove_range(&start, &end);
+        assert!(proof.verify_range(&root, &start, &end));
+
+        let mut found = vec![];
+        in_range_leaves(&proof.slots, &start, &end, &mut vec![], &mut found);
+        assert_eq!(found.len(), 60);
+        assert!(found.iter().any(|path| path.len() > 1));
+        for path in found {
+            // The hash stands for the same `Leaf`, so the `Root` hash is
+            // unchanged and only the range check can reject the proof.
+            let mut hidden = proof.clone();
+            let mut slots = &mut hidden.slots;
+            for index in &path[..path.len() - 1] {
+                slots = match &mut slots[*index].1 {
+                    RangeNode::Fork(next) => next,
+                    _ => unreachable!("the path leads through forks"),
+                };
+            }
+            let node = &mut slots[path[path.len() - 1]].1;
+            if let RangeNode::Leaf { address, payload } = node {
+                let leaf: Leaf<Vec<u8>> = Leaf::new(*address, payload.clone());
+                *node = RangeNode::Hash(leaf.get_hash());
+            }
+            assert!(!hidden.verify_range(&root, &start, &end));
+        }
+    }
+
+    #[test]
+    fn branches_only_store_occupied_nibbles() {
+        let addresses = set_operation_addresses();
+        let mut trie = byte_trie(&addresses);
+        let stored = |trie: &Trie<Vec<u8>>| {
+            trie.root.get_next_ref().occupied()
+                + trie.iter_forks().map(|(_, _, occupied)| occupied).sum::<usize>()
+        };
+        let forks = trie.iter_forks().count();
+        assert_eq!(stored(&trie), addresses.len() + forks);
+
+        for address in &addresses[3..5] {
+            trie.remove(address);
+        }
+        trie.remove(&addresses[0]);
+        let forks = trie.iter_forks().count();
+        assert_eq!(stored(&trie), addresses.len() - 3 + forks);
+        assert_eq!(trie.root.get_next_ref().get(&200).get_hash(), trie.get_by_address(&addresses[2]).get_hash());
+        assert!(trie.root.get_next_ref().get(&0).is_none());
+    }
+
+    #[test]
+    fn adding_an_existing_address_is_rejected() {
+        let mut trie: Trie<String> = Trie::default();
+        trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+        trie.add(Leaf::new([3u8; 32], "Some More Data".to_string())).unwrap();
+        let expected = trie.clone();
+
+        let duplicate = trie.add(Leaf::new([0u8; 32], "Other Data".to_string()));
+        assert_eq!(duplicate, Err(InvalidInsertError::DuplicateAddress));
+        assert_eq!(trie, expected);
+        assert_eq!(trie.iter().count(), 2);
+        assert_eq!(trie.iter_forks().count(), 0);
+
+        let fork: Fork<String> = Fork::try_from((
+            Leaf::new([0u8; 32], "Some Data".to_string()),
+            Leaf::new([0u8; 32], "Other Data".to_string()),
+            0,
+        ))
+        .unwrap();
+        assert_eq!(fork.get_next().occupied(), 1);
+    }
+
+    #[test]
+    fn addresses_differing_only_in_the_last_byte_fork_down_to_it() {
+        let mut trie: Trie<String> = Trie::default();
+        let mut address = [7u8; 32];
+        trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+        address[31] = 8;
+        trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+
+        assert_eq!(trie.max_depth(), 32);
+        assert_eq!(trie.iter_forks().count(), 31);
+        let leaves: Vec<(_, &String)> = trie.iter().collect();
+        assert_eq!(leaves, vec![([7u8; 32], &"Some Data".to_string()), (address, &"Some More Data".to_string())]);
+    }
+
+    #[test]
+    fn addresses_sharing_31_nibbles_stop_forking_at_the_last_layer() {
+        let mut address = [42u8; 32];
+        let first = Leaf::new(address, "Some Data".to_string());
+        address[31] = 43;
+        let second = Leaf::new(address, "Some More Data".to_string());
+
+        let batched: Trie<String> = Trie::from_iter(vec![first.clone(), second.clone()]);
+        let mut layered: Trie<String> = Trie::default();
+        layered.add_at_layer(first.clone(), 31).unwrap();
+        layered.add_at_layer(second.clone(), 31).unwrap();
+        assert_eq!(layered.add_at_layer(second.clone(), 32), Err(InvalidInsertError::InvalidAddress));
+        for trie in [batched, layered] {
+            assert_eq!(trie.max_depth(), 32);
+            assert_eq!(trie.get_by_address(&address).as_data(), Some(&second));
+            assert_eq!(trie.get_by_address(&[42u8; 32]).as_data(), Some(&first));
+        }
+
+        let last: Result<Fork<String>, _> = Fork::try_from((first.clone(), second.clone(), 31));
+        assert!(last.is_err());
+        let fork: Fork<String> = Fork::try_from((first, second, 30)).unwrap();
+        assert_eq!(fork.get_next().get_layer(), 31);
+        assert_eq!(fork.get_next().occupied(), 2);
+
+        assert_eq!(Layer::try_from(31usize).map(u8::from), Ok(31));
+        assert_eq!(Layer::try_from(32usize).map(u8::from), Err(LayerOutOfRange(32)));
+        assert_eq!(Layer::try_from(255u8).map(u8::from), Err(LayerOutOfRange(255)));
+    }
+
+    #[test]
+    fn borrowed_trie_iterates_the_same_leaves_every_time() {
+        let mut rng = StdRng::seed_from_u64(522);
+        let addresses = testutil::clustered_addresses(40, 2, &mut rng);
+        let trie = trie_from(&addresses, "Some Data");
+
+        let first: Vec<&Leaf<String>> = (&trie).into_iter().collect();
+        let mut second = vec![];
+        for leaf in &trie {
+            second.push(leaf);
+        }
+        assert_eq!(first, second);
+
+        let mut expected = addresses.clone();
+        expected.sort();
+        let iterated: Vec<_> = first.iter().map(|leaf| leaf.get_address()).collect();
+        assert_eq!(iterated, expected);
+    }
+
+    #[test]
+    fn owned_trie_iterator_yields_every_leaf_of_a_sparse_trie_once() {
+        let mut addresses: Vec<[u8; 32]> = (128..=255u8).step_by(7).map(|nibble| [nibble; 32]).collect();
+        let mut address = [255u8; 32];
+        address[2] = 3;
+        addresses.push(address);
+        address[9] = 0;
+        addresses.push(address);
+        let trie = trie_from(&addresses, "Some Data");
+
+        let mut iterated: Vec<[u8; 32]> = trie
+            .clone()
+            .into_iter()
+            .map(|node| match node {
+                Node::Data { data, .. } => data.get_address(),
+                _ => panic!("Only data nodes are yielded"),
+            })
+            .collect();
+        let mut expected = addresses.clone();
+        expected.sort();
+        assert_eq!(iterated, expected);
+
+        iterated = trie
+            .into_iter()
+            .rev()
+            .map(|node| match node {
+                Node::Data { data, .. } => data.get_address(),
+                _ => panic!("Only data nodes are yielded"),
+            })
+            .collect();
+        expected.reverse();
+        assert_eq!(iterated, expected);
+    }
+
+    #[test]
+    fn len_counts_the_leaves_beneath_every_fork() {
+        let mut trie: Trie<String> = Trie::default();
+        assert_eq!(trie.len(), 0);
+        assert!(trie.is_empty());
+
+        trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+        assert_eq!(trie.len(), 1);
+        assert!(!trie.is_empty());
+
+        let addresses = set_operation_addresses();
+        let mut trie = trie_from(&addresses, "Some Data");
+        assert!(trie.iter_forks().count() > 1);
+        assert_eq!(trie.len(), addresses.len());
+
+        for address in &addresses {
+            trie.remove(address);
+        }
+        assert!(trie.is_empty());
+    }
+
+    thread_local! {
+        static PAYLOAD_CLONES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    /// A payload that counts how often it is cloned on the current thread.
+    #[derive(Debug)]
+    struct Counted(Vec<u8>);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            PAYLOAD_CLONES.with(|clones| clones.set(clones.get() + 1));
+            Counted(self.0.clone())
+        }
+    }
+
+    impl From<Counted> for Vec<u8> {
+        fn from(counted: Counted) -> Vec<u8> {
+            counted.0
+        }
+    }
+
+    fn payload_clones() -> usize {
+        PAYLOAD_CLONES.with(|clones| clones.get())
+    }
+
+    #[test]
+    fn lookups_only_clone_the_node_they_return() {
+        let mut rng = StdRng::seed_from_u64(543);
+        let addresses = testutil::clustered_addresses(300, 2, &mut rng);
+        let mut trie: Trie<Counted> = Trie::default();
+        for address in &addresses {
+            trie.add(Leaf::new(*address, Counted(address.to_vec()))).unwrap();
+        }
+        let before = payload_clones();
+
+        for address in &addresses {
+            assert!(trie.contains(address));
+            let (_, _, node) = trie.traverse_ref(address);
+            assert_eq!(node.as_data().map(|leaf| leaf.get_address()), Some(*address));
+            assert!(trie.root.get_ref(&address[0]).is_fork());
+            assert!(trie.root.get_next_ref().get_ref(&address[0]).is_fork());
+        }
+        assert_eq!(payload_clones(), before);
+
+        for address in &addresses {
+            assert!(trie.get_by_address(address).is_data());
+            assert!(trie.traverse(address).2.is_data());
+        }
+        assert_eq!(payload_clones(), before + 2 * addresses.len());
+    }
+
+    #[test]
+    fn deep_lookups_and_owned_iteration_do_not_clone_forks() {
+        let mut trie: Trie<Counted> = Trie::default();
+        let mut address = [7u8; 32];
+        trie.add(Leaf::new(address, Counted(b"Some Data".to_vec()))).unwrap();
+        address[31] = 8;
+        trie.add(Leaf::new(address, Counted(b"Some More Data".to_vec()))).unwrap();
+        assert_eq!(trie.max_depth(), 32);
+
+        let before = payload_clones();
+        let (depth, _, node) = trie.traverse(&address);
+        assert_eq!((depth, node.as_data().map(|leaf| leaf.get_address())), (31, Some(address)));
+        assert_eq!(payload_clones(), before + 1);
+
+        let (depth, _, node) = trie.traverse(&address[..20]);
+        assert_eq!(depth, 20);
+        assert!(node.is_fork());
+        assert_eq!(payload_clones(), before + 1);
+        drop(node);
+
+        let before = payload_clones();
+        let leaves: Vec<Node<Counted>> = trie.into_iter().collect();
+        assert_eq!(leaves.len(), 2);
+        assert_eq!(payload_clones(), before);
+    }
+
+    #[test]
+    fn trie_with_four_byte_addresses_adds_and_finds_leaves() {
+        let addresses: Vec<[u8; 4]> = vec![[0, 0, 0, 0], [0, 0, 0, 9], [0, 1, 0, 0], [3, 3, 3, 3], [200, 0, 0, 1]];
+        let mut trie: Trie<String, Sha256Algorithm, 4> = Trie::new();
+        for address in &addresses {
+            trie.add(Leaf::new(*address, format!("{:?}", address))).unwrap();
+        }
+        assert_eq!(trie.add(Leaf::new([3, 3, 3, 3], "Other Data".to_string())), Err(InvalidInsertError::DuplicateAddress));
+
+        assert_eq!(trie.len(), addresses.len());
+        assert_eq!(trie.max_depth(), 4);
+        for address in &addresses {
+            let node = trie.get_by_address(address);
+            assert_eq!(node.as_data().map(|leaf| leaf.get_payload()), Some(format!("{:?}", address)));
+            assert!(trie.prove(address).unwrap().verify(&trie.root.get_hash(), address, &format!("{:?}", address)));
+        }
+        assert!(trie.get_by_address(&[0, 0, 0, 1]).is_none());
+        assert!(trie.prove_absence(&[0, 0, 0, 1]).unwrap().verify(&trie.root.get_hash(), &[0, 0, 0, 1]));
+
+        let iterated: Vec<[u8; 4]> = trie.iter_addressed().map(|(address, _)| address).collect();
+        assert_eq!(iterated, addresses);
+        assert_eq!(trie.remove(&[0, 0, 0, 9]).map(|leaf| leaf.get_address()), Some([0, 0, 0, 9]));
+        assert_eq!(trie.len(), addresses.len() - 1);
+    }
+
+    #[test]
+    fn trie_with_four_byte_addresses_round_trips_through_bytes() {
+        let mut trie: Trie<Vec<u8>, Sha256Algorithm, 4> = Trie::new();
+        trie.insert_key(b"alice", b"Some Data".to_vec()).unwrap();
+        trie.insert_key(b"bob", b"Some More Data".to_vec()).unwrap();
+        assert_eq!(Trie::<Vec<u8>, Sha256Algorithm, 4>::key_address(b"bob"), Sha256Algorithm::hash(b"bob")[..4]);
+
+        let decoded: Trie<Vec<u8>, Sha256Algorithm, 4> = Trie::from_bytes(&trie.to_bytes()).unwrap();
+        assert_eq!(decoded, trie);
+        assert_eq!(decoded.get_key(b"alice"), Some(&b"Some Data".to_vec()));
+
+        let wide: Result<Trie<Vec<u8>>, DecodeError> = Trie::from_bytes(&trie.to_bytes());
+        assert!(wide.is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_preserves_a_trie_with_four_byte_addresses() {
+        let mut trie: Trie<String, Sha256Algorithm, 4> = Trie::new();
+        trie.add(Leaf::new([0, 0, 0, 0], "Some Data".to_string())).unwrap();
+        trie.add(Leaf::new([0, 0, 7, 0], "Some More Data".to_string())).unwrap();
+
+        let json = serde_json::to_string(&trie).unwrap();
+        let restored: Trie<String, Sha256Algorithm, 4> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, trie);
+        assert!(serde_json::from_str::<Trie<String>>(&json).is_err());
+    }
+
+    #[test]
+    fn lazy_hashing_after_many_inserts_matches_eager_hashing() {
+        let mut rng = StdRng::seed_from_u64(545);
+        let mut addresses = set_operation_addresses();
+        addresses.extend(testutil::clustered_addresses(80, 2, &mut rng));
+
+        let mut eager: Trie<String, CountingHasher> = Trie::with_hasher();
+        let before = hash_calls();
+        for address in &addresses {
+            eager.add(Leaf::with_hasher(*address, "Some Data".to_string())).unwrap();
+            eager.root_hash();
+        }
+        let eager_hashes = hash_calls() - before;
+
+        let mut lazy: Trie<String, CountingHasher> = Trie::with_hasher();
+        let before = hash_calls();
+        for address in &addresses {
+            lazy.add(Leaf::with_hasher(*address, "Some Data".to_string())).unwrap();
+        }
+        assert!(lazy.root.get_next().is_dirty());
+        let pending = lazy.clone();
+
+        assert_eq!(lazy.root_hash(), eager.root_hash());
+        let lazy_hashes = hash_calls() - before;
+        assert!(!lazy.root.get_next().is_dirty());
+        assert!(lazy_hashes < eager_hashes);
+
+        assert_eq!(pending.root.get_hash(), eager.root.get_hash());
+        assert!(pending.root.get_next().is_dirty());
+
+        let before = hash_calls();
+        assert_eq!(lazy.root_hash(), eager.root_hash());
+        assert_eq!(lazy.root.get_hash(), eager.root.get_hash());
+        assert_eq!(hash_calls(), before);
+        assert_eq!(lazy, eager);
+        assert!(lazy.verify_integrity());
+    }
+
+    /// Returns `addresses` as leaves sorted by address, each holding its
+    /// index in `addresses` as payload.
+    fn sorted_leaves(addresses: &[[u8; 32]]) -> Vec<Leaf<String>> {
+        let mut leaves: Vec<Leaf<String>> = addresses.iter().enumerate().map(|(i, address)| Leaf::new(*address, i.to_string())).collect();
+        leaves.sort_by_key(|leaf| leaf.get_address());
+        leaves
+    }
+
+    #[test]
+    fn from_sorted_builds_the_trie_repeated_add_builds() {
+        let mut rng = StdRng::seed_from_u64(412);
+        let mut addresses = testutil::random_addresses(500, &mut rng);
+        addresses.extend(testutil::clustered_addresses(200, 3, &mut rng));
+        let leaves = sorted_leaves(&addresses);
+
+        let mut expected: Trie<String> = Trie::default();
+        for (i, address) in addresses.iter().enumerate() {
+            expected.add(Leaf::new(*address, i.to_string())).unwrap();
+        }
+        let mut trie = Trie::from_sorted(leaves.clone()).unwrap();
+        assert_eq!(trie.root_hash(), expected.root_hash());
+        assert_eq!(trie, expected);
+        assert!(trie.verify_integrity());
+        assert!(Trie::<String>::from_sorted(vec![]).unwrap().is_empty());
+
+        let mut unsorted = leaves.clone();
+        unsorted.swap(10, 11);
+        assert_eq!(Trie::from_sorted(unsorted).unwrap_err(), InvalidInsertError::Unsorted);
+        let mut repeated = leaves;
+        repeated[11] = repeated[10].clone();
+        assert_eq!(Trie::from_sorted(repeated).unwrap_err(), InvalidInsertError::DuplicateAddress);
+    }
+
+    #[test]
+    fn bulk_builds_are_canonical() {
+        let mut rng = StdRng::seed_from_u64(416);
+        let mut addresses = testutil::random_addresses(400, &mut rng);
+        addresses.extend(testutil::clustered_addresses(200, 3, &mut rng));
+        let leaves = sorted_leaves(&addresses);
+
+        let mut reference: Trie<String> = Trie::default();
+        for leaf in &leaves {
+            reference.add(leaf.clone()).unwrap();
+        }
+        reference.assert_canonical();
+
+        let mut sorted = Trie::from_sorted(leaves.clone()).unwrap();
+        sorted.assert_canonical();
+        assert_eq!(sorted.root_hash(), reference.root_hash());
+
+        let mut batched: Trie<String> = Trie::default();
+        batched.add_many(leaves[300..].to_vec()).unwrap();
+        batched.add_many(leaves[..300].to_vec()).unwrap();
+        batched.assert_canonical();
+        assert_eq!(batched.root_hash(), reference.root_hash());
+
+        #[cfg(feature = "rayon")]
+        {
+            let mut parallel = Trie::from_sorted_par(leaves).unwrap();
+            parallel.assert_canonical();
+            assert_eq!(parallel.root_hash(), reference.root_hash());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "holds a single Leaf")]
+    fn assert_canonical_rejects_a_leaf_placed_too_deep() {
+        let mut trie: Trie<String> = Trie::default();
+        trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+        trie.root.get_next_mut().insert_at_layer(Leaf::new([1u8; 32], "Some More Data".to_string()), 2).unwrap();
+        trie.root.rehash();
+        assert!(trie.verify_integrity());
+        trie.assert_canonical();
+    }
+
+    #[test]
+    #[should_panic(expected = "a cached hash is stale")]
+    fn assert_canonical_rejects_stale_hashes() {
+        let mut trie: Trie<String> = Trie::default();
+        trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+        let data = Leaf::new([0u8; 32], "Some Other Data".to_string());
+        trie.root.get_next_mut()[0] = Node::Data { data, hash: [9u8; 32] };
+        trie.assert_canonical();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn from_sorted_par_builds_the_trie_from_sorted_builds() {
+        let mut rng = StdRng::seed_from_u64(412);
+        let mut addresses = testutil::random_addresses(2000, &mut rng);
+        addresses.extend(testutil::clustered_addresses(500, 2, &mut rng));
+        let leaves = sorted_leaves(&addresses);
+
+        let mut parallel = Trie::from_sorted_par(leaves.clone()).unwrap();
+        let mut serial = Trie::from_sorted(leaves.clone()).unwrap();
+        assert_eq!(parallel.root_hash(), serial.root_hash());
+        assert_eq!(parallel, serial);
+        assert!(parallel.verify_integrity());
+        assert_eq!(Trie::from_sorted_par(leaves.into_iter().rev().collect()).unwrap_err(), InvalidInsertError::Unsorted);
+    }
+
+    fn padded_trie(addresses: &[[u8; 32]]) -> Trie<String, ZeroPadded<Sha256Algorithm>> {
+        let mut trie = Trie::default();
+        for address in addresses {
+            trie.add(Leaf::with_hasher(*address, "Some Data".to_string())).unwrap();
+        }
+        trie
+    }
+
+    #[test]
+    fn none_hash_policies_give_different_roots() {
+        assert_eq!(Sha256Algorithm::none_hash_policy(), NoneHashPolicy::Skip);
+        assert_eq!(ZeroPadded::<Sha256Algorithm>::none_hash_policy(), NoneHashPolicy::ZeroPlaceholder);
+        assert_ne!(ZeroPadded::<Sha256Algorithm>::algorithm_id(), Sha256Algorithm::algorithm_id());
+
+        let addresses = set_operation_addresses();
+        let mut skipping = trie_from(&addresses, "Some Data");
+        assert_eq!(hex(&skipping.root_hash()), "6da44eb2648da77efe67221df24d34560941df298b4c75714b367930fc4c6983");
+
+        let mut padded = padded_trie(&addresses);
+        assert_ne!(padded.root_hash(), skipping.root_hash());
+        assert!(padded.verify_integrity());
+        assert_eq!(Trie::<String, ZeroPadded<Sha256Algorithm>>::default().root_hash(), Trie::<String>::default().root_hash());
+
+        let mut rng = StdRng::seed_from_u64(441);
+        let mut addresses = testutil::random_addresses(300, &mut rng);
+        addresses.extend(testutil::clustered_addresses(100, 2, &mut rng));
+        let mut padded = padded_trie(&addresses);
+        let mut shuffled = addresses.clone();
+        shuffled.reverse();
+        assert_eq!(padded_trie(&shuffled).root_hash(), padded.root_hash());
+        assert_ne!(padded.root_hash(), trie_from(&addresses, "Some Data").root_hash());
+    }
+
+    #[test]
+    fn zero_placeholder_proofs_verify() {
+        let mut rng = StdRng::seed_from_u64(441);
+        let mut addresses = testutil::random_addresses(300, &mut rng);
+        addresses.extend(testutil::clustered_addresses(100, 2, &mut rng));
+        let mut trie = padded_trie(&addresses);
+        let root = trie.root_hash();
+        let payload = "Some Data".to_string();
+
+        for address in addresses.iter().take(50) {
+            let proof = trie.prove(address).unwrap();
+            assert!(proof.steps.iter().all(|step| step.siblings.len() == 8));
+            assert!(proof.verify(&root, address, &payload));
+            assert!(!proof.verify(&root, address, &"Some Other Data".to_string()));
+        }
+
+        let missing = testutil::random_addresses(50, &mut rng);
+        for address in missing.iter().filter(|address| !trie.contains(address)) {
+            assert!(trie.prove_absence(address).unwrap().verify(&root, address));
+        }
+
+        let mut sorted = addresses.clone();
+        sorted.sort();
+        let (start, end) = (sorted[100], sorted[200]);
+        let range = trie.prove_range(&start, &end);
+        assert!(range.verify_range(&root, &start, &end));
+        assert_eq!(range.leaves(&start, &end).len(), 100);
+
+        let mut store = HashMapStore::default();
+        assert_eq!(trie.commit(&mut store), root);
+        assert_eq!(Trie::<String, ZeroPadded<Sha256Algorithm>>::load(root, &store).unwrap(), trie);
+        for address in addresses.iter().take(50) {
+            assert_eq!(Trie::<String, ZeroPadded<Sha256Algorithm>>::try_get(root, &store, address).unwrap(), Some(payload.clone()));
+        }
+
+        #[cfg(feature = "rayon")]
+        {
+            let mut parallel: Trie<String, ZeroPadded<Sha256Algorithm>> = Trie::default();
+            for address in &addresses {
+                parallel.root.get_next_mut().insert(Leaf::with_hasher(*address, payload.clone()));
+            }
+            parallel.root.par_rehash();
+            assert_eq!(parallel.root_hash(), root);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_hashing_matches_sequential_hashing() {
+        let mut rng = StdRng::seed_from_u64(546);
+        let mut addresses = testutil::random_addresses(500, &mut rng);
+        addresses.extend(testutil::clustered_addresses(200, 3, &mut rng));
+
+        let mut sequential: Trie<String> = Trie::default();
+        for address in &addresses {
+            sequential.root.get_next_mut().insert(Leaf::new(*address, "Some Data".to_string()));
+        }
+        let mut parallel = sequential.clone();
+        sequential.root.rehash();
+        parallel.root.par_rehash();
+
+        assert!(!parallel.root.get_next().is_dirty());
+        assert_eq!(parallel.root.get_hash(), sequential.root.get_hash());
+        assert_eq!(parallel, sequential);
+        assert!(parallel.verify_integrity());
+
+        let root = sequential.root_hash();
+        let mut corrupted = sequential.clone();
+        for nibble in 0..=u8::MAX {
+            match corrupted.root.get_ref(&nibble).clone() {
+                Node::Data { data, .. } => corrupted.root.get_next_mut()[nibble] = Node::Data { data, hash: [9u8; 32] },
+                Node::Fork { fork, .. } => corrupted.root.get_next_mut()[nibble] = Node::Fork { fork, hash: [9u8; 32] },
+                Node::None => {}
+            }
+        }
+        corrupted.root.rehash();
+        assert!(!corrupted.verify_integrity());
+        let mut sequential = corrupted.clone();
+        sequential.root.get_next_mut().recompute_hashes();
+        sequential.root.rehash();
+        corrupted.recompute_hashes();
+        assert!(corrupted.verify_integrity());
+        assert_eq!(corrupted.root_hash(), root);
+        assert_eq!(sequential.root_hash(), root);
+    }
+
+    #[test]
+    fn snapshot_is_unaffected_by_later_mutations_of_the_original() {
+        let mut rng = StdRng::seed_from_u64(547);
+        let addresses = testutil::clustered_addresses(64, 2, &mut rng);
+        let mut trie: Trie<Counted> = Trie::default();
+        for address in &addresses[..48] {
+            trie.add(Leaf::new(*address, Counted(address.to_vec()))).unwrap();
+        }
+        let hash = trie.root_hash();
+
+        let before = payload_clones();
+        let mut snapshot = trie.snapshot();
+        assert_eq!(payload_clones(), before);
+
+        for address in &addresses[48..] {
+            trie.add(Leaf::new(*address, Counted(address.to_vec()))).unwrap();
+        }
+        trie.remove(&addresses[0]).unwrap();
+        trie.replace(&addresses[1], Counted(b"Other Data".to_vec())).unwrap();
+        assert_eq!(trie.len(), 63);
+        assert_ne!(trie.root_hash(), hash);
+
+        assert_eq!(snapshot.root_hash(), hash);
+        assert_eq!(snapshot.len(), 48);
+        assert!(snapshot.verify_integrity());
+        for address in &addresses[..2] {
+            let payload = snapshot.find_leaf(address).map(|leaf| leaf.payload_ref().0.clone());
+            assert_eq!(payload, Some(address.to_vec()));
+        }
+        assert!(addresses[48..].iter().all(|address| !snapshot.contains(address)));
+    }
+
+    #[test]
+    fn shared_trie_serves_consistent_reads_while_a_writer_adds_leaves() {
+        let mut rng = StdRng::seed_from_u64(549);
+        let addresses = testutil::clustered_addresses(200, 2, &mut rng);
+        let shared: SharedTrie<String> = SharedTrie::default();
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                let addresses = addresses.clone();
+                thread::spawn(move || {
+                    let mut seen = 0;
+                    while seen < addresses.len() {
+                        let mut snapshot = shared.snapshot();
+                        let len = snapshot.len();
+                        assert!(len >= seen);
+                        assert!(addresses[..len].iter().all(|address| snapshot.contains(address)));
+                        assert!(addresses[len..].iter().all(|address| !snapshot.contains(address)));
+                        if let Some(address) = addresses[..len].last() {
+                            let proof = snapshot.prove(address).unwrap();
+                            assert!(proof.verify(&snapshot.root_hash(), address, &"Some Data".to_string()));
+                            assert!(shared.contains(address));
+                            assert!(shared.get_by_address(address).is_data());
+                        }
+                        seen = len;
+                    }
+                })
+            })
+            .collect();
+
+        let writer = {
+            let shared = shared.clone();
+            let addresses = addresses.clone();
+            thread::spawn(move || {
+                for address in &addresses {
+                    shared.add(Leaf::new(*address, "Some Data".to_string())).unwrap();
+                }
+            })
+        };
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        assert_eq!(shared.root_hash(), trie_from(&addresses, "Some Data").root_hash());
+        assert!(shared.prove(&addresses[0]).is_some());
+        assert_eq!(shared.remove(&addresses[0]).map(|leaf| leaf.get_address()), Some(addresses[0]));
+        assert!(!shared.contains(&addresses[0]));
+    }
+
+    /// Asserts that no `Fork` beneath `branch` is empty or holds a single
+    /// `Leaf` it should have collapsed into.
+    fn assert_no_dangling_forks(branch: &Branch<String>) {
+        for (_, node) in branch.nodes() {
+            if let Node::Fork { fork, .. } = node {
+                let next = fork.get_next_ref();
+                let occupied: Vec<&Node<String>> = next.nodes().map(|(_, node)| node).collect();
+                assert!(!occupied.is_empty());
+                assert!(!(occupied.len() == 1 && occupied[0].is_data()));
+                assert_no_dangling_forks(next);
+            }
+        }
+    }
+
+    #[test]
+    fn retain_keeps_only_matching_leaves_and_collapses_forks() {
+        let mut rng = StdRng::seed_from_u64(550);
+        let mut addresses = testutil::clustered_addresses(150, 2, &mut rng);
+        addresses.extend(set_operation_addresses());
+        let mut trie = trie_from(&addresses, "Some Data");
+        let max_depth = trie.max_depth();
+
+        let mut visited = 0;
+        trie.retain(|address, payload| {
+            visited += 1;
+            assert_eq!(payload, "Some Data");
+            address[31] % 2 == 0
+        });
+        assert_eq!(visited, addresses.len());
+
+        let (even, odd): (Vec<[u8; 32]>, Vec<[u8; 32]>) = addresses.iter().partition(|address| address[31] % 2 == 0);
+        assert!(!even.is_empty() && !odd.is_empty());
+        assert!(even.iter().all(|address| trie.contains(address)));
+        assert!(odd.iter().all(|address| !trie.contains(address)));
+        assert_eq!(trie.len(), even.len());
+        assert!(trie.max_depth() <= max_depth);
+
+        let mut expected = trie_from(&even, "Some Data");
+        assert_eq!(trie.root_hash(), expected.root_hash());
+        assert_eq!(trie, expected);
+        assert!(trie.verify_integrity());
+        assert_no_dangling_forks(trie.root.get_next_ref());
+
+        let hash = trie.root_hash();
+        trie.retain(|_, _| true);
+        assert_eq!(trie.root_hash(), hash);
+        trie.retain(|_, _| false);
+        assert_eq!(trie.root_hash(), Trie::<String>::default().root_hash());
+        assert!(trie.is_empty());
+    }
+
+    #[test]
+    fn map_to_payload_lengths_keeps_addresses_and_rehashes() {
+        let mut rng = StdRng::seed_from_u64(551);
+        let addresses = testutil::clustered_addresses(100, 2, &mut rng);
+        let mut trie: Trie<String> = Trie::default();
+        for (i, address) in addresses.iter().enumerate() {
+            trie.add(Leaf::new(*address, "x".repeat(i % 7))).unwrap();
+        }
+
+        let mut lengths: Trie<Vec<u8>> = trie.map(|payload| vec![payload.len() as u8]);
+        let mapped: Vec<([u8; 32], Vec<u8>)> = lengths.iter_addressed().map(|(address, payload)| (address, payload.clone())).collect();
+        let expected: Vec<([u8; 32], Vec<u8>)> = trie.iter_addressed().map(|(address, payload)| (address, vec![payload.len() as u8])).collect();
+        assert_eq!(mapped, expected);
+        assert_eq!(lengths.len(), addresses.len());
+
+        let mut rebuilt: Trie<Vec<u8>> = Trie::default();
+        for (address, payload) in expected {
+            rebuilt.add(Leaf::new(address, payload)).unwrap();
+        }
+        assert_ne!(lengths.root_hash(), trie.root_hash());
+        assert_eq!(lengths.root_hash(), rebuilt.root_hash());
+        assert!(lengths.verify_integrity());
+
+        #[allow(deprecated)]
+        let mut forwarded: Trie<Vec<u8>> = trie.map_payloads(|payload| vec![payload.len() as u8]);
+        assert_eq!(forwarded.root_hash(), lengths.root_hash());
+    }
+
+    #[test]
+    fn branch_nodes_can_be_read_and_replaced_by_index() {
+        let mut branch: Branch<String> = Branch::new(Layer::Zero);
+        branch.insert(Leaf::new([1u8; 32], "Some Data".to_string()));
+        branch.rehash();
+        assert_eq!(branch[1].as_data().map(|leaf| leaf.get_address()), Some([1u8; 32]));
+        assert!(std::ptr::eq(&branch[1], branch.get_ref(&1)));
+        assert!(branch[5].is_none());
+
+        let data = Leaf::new([5u8; 32], "Some More Data".to_string());
+        let hash = data.get_hash();
+        branch[5] = Node::Data { data, hash };
+        assert!(branch.is_dirty());
+        assert_eq!(branch[5].as_data().map(|leaf| leaf.get_payload()), Some("Some More Data".to_string()));
+        assert!(branch[7].is_none());
+        let _ = &mut branch[7];
+        branch.rehash();
+
+        let mut expected: Branch<String> = Branch::new(Layer::Zero);
+        expected.insert(Leaf::new([1u8; 32], "Some Data".to_string()));
+        expected.insert(Leaf::new([5u8; 32], "Some More Data".to_string()));
+        expected.rehash();
+        assert_eq!(branch.get_hash(), expected.get_hash());
+        assert_eq!(branch.nodes().count(), 2);
+    }
+
+    #[test]
+    fn mutably_indexing_an_empty_slot_leaves_it_unoccupied() {
+        let mut branch: Branch<String> = Branch::new(Layer::Zero);
+        branch.insert(Leaf::new([1u8; 32], "Some Data".to_string()));
+        branch.insert(Leaf::new([5u8; 32], "Some More Data".to_string()));
+        branch.rehash();
+        let before = branch.clone();
+
+        let _ = &mut branch[7];
+        assert!(branch[7].is_none());
+        assert_eq!(branch.occupied(), before.occupied());
+        assert!(branch.structural_eq(&before));
+        assert!(before.structural_eq(&branch));
+        assert_eq!(branch.get_hash(), before.get_hash());
+        assert_eq!(branch.occupied_nodes().count(), 2);
+
+        branch.rehash();
+        assert_eq!(branch.nodes().count(), 2);
+        assert_eq!(branch.get_hash(), before.get_hash());
+    }
+
+    #[test]
+    fn root_iterates_over_the_nodes_of_its_branch() {
+        let mut root: Root<String> = Root::new();
+        for byte in [2u8, 9, 200] {
+            root.get_next_mut().insert(Leaf::new([byte; 32], "Some Data".to_string()));
+        }
+        let mut address = [9u8; 32];
+        address[1] = 0;
+        root.get_next_mut().insert(Leaf::new(address, "Some More Data".to_string()));
+        root.rehash();
+
+        let mut occupied = vec![];
+        for (nibble, node) in (&root).into_iter().enumerate() {
+            if !node.is_none() {
+                occupied.push((nibble, node.is_fork()));
+            }
+        }
+        assert_eq!(occupied, vec![(2, false), (9, true), (200, false)]);
+        assert_eq!((&root).into_iter().count(), 256);
+
+        let borrowed: Vec<Option<[u8; 32]>> = (&mut root).into_iter().map(|node| node.get_hash()).collect();
+        let branch: Vec<Option<[u8; 32]>> = root.get_next_ref().into_iter().map(|node| node.get_hash()).collect();
+        assert_eq!(borrowed, branch);
+        let owned: Vec<Option<[u8; 32]>> = root.into_iter().map(|node| node.get_hash()).collect();
+        assert_eq!(owned, branch);
+    }
+
+    #[test]
+    fn branch_iterators_walk_backwards_and_meet_in_the_middle() {
+        let mut branch: Branch<String> = Branch::new(Layer::Zero);
+        for byte in [3u8, 100, 128, 250] {
+            branch.insert(Leaf::new([byte; 32], "Some Data".to_string()));
+        }
+        branch.rehash();
+        let forward: Vec<Option<[u8; 32]>> = (&branch).into_iter().map(|node| node.get_hash()).collect();
+
+        let occupied: Vec<usize> = (&branch)
+            .into_iter()
+            .rev()
+            .enumerate()
+            .filter(|(_, node)| !node.is_none())
+            .map(|(i, _)| 255 - i)
+            .collect();
+        assert_eq!(occupied, vec![250, 128, 100, 3]);
+        let backward: Vec<Option<[u8; 32]>> = branch.clone().into_iter().rev().map(|node| node.get_hash()).collect();
+        assert_eq!(backward, forward.iter().rev().cloned().collect::<Vec<_>>());
+
+        let mut borrowed = (&branch).into_iter();
+        let mut owned = branch.clone().into_iter();
+        let (mut front, mut back) = (vec![], vec![]);
+        for step in 0.. {
+            let (node, other) = if step % 3 == 0 {
+                (borrowed.next(), owned.next())
+            } else {
+                (borrowed.next_back(), owned.next_back())
+            };
+            assert_eq!(node.as_ref().map(|node| node.get_hash()), other.map(|node| node.get_hash()));
+            match node {
+                Some(node) if step % 3 == 0 => front.push(node.get_hash()),
+                Some(node) => back.push(node.get_hash()),
+                None => break,
+            }
+        }
+        assert!(borrowed.next().is_none() && borrowed.next_back().is_none());
+        assert_eq!(front.len() + back.len(), 256);
+        front.extend(back.into_iter().rev());
+        assert_eq!(front, forward);
+    }
+
+    #[test]
+    fn size_hints_match_the_number_of_nodes_yielded() {
+        let mut rng = StdRng::seed_from_u64(555);
+        let addresses = testutil::clustered_addresses(120, 1, &mut rng);
+        let trie = trie_from(&addresses, "Some Data");
+        let branch = trie.root.get_next_ref();
+
+        let mut nodes = branch.into_iter();
+        assert_eq!(nodes.size_hint(), (256, Some(256)));
+        nodes.next();
+        nodes.next_back();
+        nodes.next_back();
+        assert_eq!(nodes.len(), 253);
+        assert_eq!(nodes.clone().count(), 253);
+
+        let mut owned = branch.clone().into_iter();
+        owned.nth(10);
+        assert_eq!(owned.len(), owned.clone().count());
+        let fork = (0..=255u8).find_map(|nibble| branch.get_ref(&nibble).as_fork().cloned()).unwrap();
+        assert_eq!(fork.into_iter().len(), 256);
+
+        let mut leaves = trie.iter();
+        for yielded in 0..addresses.len() {
+            let (lower, upper) = leaves.size_hint();
+            assert!(lower >= 1 && lower <= addresses.len() - yielded);
+            assert_eq!(upper, None);
+            leaves.next();
+        }
+        assert_eq!(leaves.size_hint(), (0, None));
+        let (lower, _) = trie.clone().into_iter().size_hint();
+        assert!(lower >= 1 && lower <= addresses.len());
+        assert_eq!((&trie).into_iter().size_hint(), trie.iter_addressed().size_hint());
+    }
+
+    #[cfg(feature = "rlp")]
+    #[test]
+    fn rlp_round_trips_a_single_leaf() {
+        let leaf: Leaf<Vec<u8>> = Leaf::new([7u8; 32], b"Some Data".to_vec());
+        let mut trie: Trie<Vec<u8>> = Trie::default();
+        trie.add(leaf.clone()).unwrap();
+
+        let rlp = trie.to_rlp();
+        let decoded: Trie<Vec<u8>> = Trie::from_rlp(&rlp).unwrap();
+        assert_eq!(decoded.find_leaf(&[7u8; 32]), Some(&leaf));
+        assert_eq!(decoded, trie);
+
+        let mut slots = vec![0x80; 256];
+        let mut path = vec![0x20];
+        path.extend_from_slice(&[7u8; 31]);
+        let mut expected_leaf = vec![0, 0xa0];
+        expected_leaf.extend(path);
+        expected_leaf.push(0x89);
+        expected_leaf.extend_from_slice(b"Some Data");
+        expected_leaf[0] = 0xc0 + (expected_leaf.len() - 1) as u8;
+        slots.splice(7..8, expected_leaf);
+        let mut expected = vec![0xf9];
+        expected.extend((slots.len() as u16).to_be_bytes());
+        expected.extend(slots);
+        assert_eq!(rlp, expected);
+    }
+
+    #[cfg(feature = "rlp")]
+    #[test]
+    fn rlp_round_trips_a_trie_and_rejects_malformed_input() {
+        use crate::rlp;
+
+        let mut out = vec![];
+        rlp::encode_bytes(b"dog", &mut out);
+        assert_eq!(out, b"\x83dog");
+        let mut items = vec![];
+        rlp::encode_bytes(b"cat", &mut items);
+        rlp::encode_bytes(b"dog", &mut items);
+        let mut list = vec![];
+        rlp::encode_list(&items, &mut list);
+        assert_eq!(list, b"\xc8\x83cat\x83dog");
+        assert_eq!(rlp::decode(&list, 1), Ok(rlp::Item::List(vec![rlp::Item::Bytes(b"cat"), rlp::Item::Bytes(b"dog")])));
+        let mut long = vec![];
+        rlp::encode_bytes(&[1u8; 56], &mut long);
+        assert_eq!(long[..2], [0xb8, 56]);
+        assert_eq!(rlp::decode(&list, 0), Err(DecodeError::InvalidRlp));
+        assert_eq!(rlp::decode(&[0x81, 0x05], 0), Err(DecodeError::InvalidRlp));
+        assert_eq!(rlp::decode(&[0xb8, 0x05, 1, 2, 3, 4, 5], 0), Err(DecodeError::InvalidRlp));
+
+        let mut rng = StdRng::seed_from_u64(556);
+        let addresses = testutil::clustered_addresses(40, 2, &mut rng);
+        let mut trie: Trie<Vec<u8>> = Trie::default();
+        for address in &addresses {
+            trie.add(Leaf::new(*address, address[..4].to_vec())).unwrap();
+        }
+        let bytes = trie.to_rlp();
+        let mut decoded: Trie<Vec<u8>> = Trie::from_rlp(&bytes).unwrap();
+        assert_eq!(decoded.root_hash(), trie.root_hash());
+        assert_eq!(decoded, trie);
+
+        assert_eq!(Trie::<Vec<u8>>::from_rlp(&bytes[..bytes.len() - 1]), Err(DecodeError::UnexpectedEnd));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(Trie::<Vec<u8>>::from_rlp(&trailing), Err(DecodeError::TrailingBytes));
+        assert_eq!(Trie::<Vec<u8>>::from_rlp(&[0xc0]), Err(DecodeError::InvalidBranch));
+        assert!(Trie::<Vec<u8>, Sha256Algorithm, 4>::from_rlp(&bytes).is_err());
+    }
+
+    #[cfg(feature = "rlp")]
+    #[test]
+    fn rlp_decoding_rejects_lists_nested_deeper_than_a_trie() {
+        use crate::rlp;
+
+        // 100,000 lists nested around an empty one, their prefixes computed
+        // from the inside out, so building them takes linear time.
+        let mut prefixes = vec![];
+        let mut len = 1usize;
+        for _ in 0..100_000 {
+            let prefix = if len <= 55 {
+                vec![0xc0 + len as u8]
+            } else {
+                let bytes = len.to_be_bytes();
+                let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+                let mut prefix = vec![0xf7 + (bytes.len() - skip) as u8];
+                prefix.extend_from_slice(&bytes[skip..]);
+                prefix
+            };
+            len += prefix.len();
+            prefixes.push(prefix);
+        }
+        let mut nested: Vec<u8> = prefixes.into_iter().rev().flatten().collect();
+        nested.push(0xc0);
+        assert_eq!(nested.len(), len);
+        assert_eq!(rlp::decode(&nested, 64), Err(DecodeError::InvalidRlp));
+        assert!(matches!(Trie::<Vec<u8>>::from_rlp(&nested), Err(DecodeError::InvalidRlp)));
+
+        let mut address = [0u8; 32];
+        let mut deepest: Trie<Vec<u8>> = Trie::default();
+        deepest.add(Leaf::new(address, vec![1])).unwrap();
+        address[31] = 1;
+        deepest.add(Leaf::new(address, vec![2])).unwrap();
+        assert_eq!(deepest.max_depth(), 32);
+        assert_eq!(rlp::decode(&deepest.to_rlp(), 63), Err(DecodeError::InvalidRlp));
+        let mut decoded: Trie<Vec<u8>> = Trie::from_rlp(&deepest.to_rlp()).unwrap();
+        assert_eq!(decoded.root_hash(), deepest.root_hash());
+    }
+
+    #[test]
+    fn sparse_merkle_tree_proves_membership_of_every_value() {
+        let mut rng = StdRng::seed_from_u64(557);
+        let addresses = testutil::clustered_addresses(64, 30, &mut rng);
+        let mut tree: SparseMerkleTree<String> = SparseMerkleTree::new();
+        for (i, address) in addresses.iter().enumerate() {
+            tree.update(*address, format!("Data {}", i));
+        }
+        let root = tree.root();
+        assert_eq!(tree.len(), addresses.len());
+        for (i, address) in addresses.iter().enumerate() {
+            let value = format!("Data {}", i);
+            assert_eq!(tree.get(address), Some(&value));
+            let proof = tree.prove(address);
+            assert!(proof.verify(&root, address, Some(&value)));
+            assert!(!proof.verify(&root, address, Some(&"Other Data".to_string())));
+            assert!(!proof.verify::<String>(&root, address, None));
+        }
+    }
+
+    #[test]
+    fn sparse_merkle_tree_proves_non_membership_of_unset_addresses() {
+        let mut rng = StdRng::seed_from_u64(557);
+        let addresses = testutil::random_addresses(32, &mut rng);
+        let absent = testutil::random_addresses(32, &mut rng);
+        let mut tree: SparseMerkleTree<String> = SparseMerkleTree::new();
+        for address in &addresses {
+            tree.update(*address, "Some Data".to_string());
+        }
+        let root = tree.root();
+        for address in &absent {
+            assert_eq!(tree.get(address), None);
+            let proof = tree.prove(address);
+            assert!(proof.verify::<String>(&root, address, None));
+            assert!(!proof.verify(&root, address, Some(&"Some Data".to_string())));
+        }
+        let proof = tree.prove(&absent[0]);
+        tree.update(absent[0], "Some Data".to_string());
+        assert!(!proof.verify::<String>(&tree.root(), &absent[0], None));
+    }
+
+    #[test]
+    fn sparse_merkle_tree_proofs_omit_default_siblings() {
+        let mut tree: SparseMerkleTree<String> = SparseMerkleTree::new();
+        let empty_root = tree.root();
+        assert!(tree.prove(&[7u8; 32]).siblings.is_empty());
+        let mut right = [0u8; 32];
+        right[0] = 0x80;
+        tree.update([0u8; 32], "Some Data".to_string());
+        tree.update(right, "Other Data".to_string());
+        let proof = tree.prove(&[0u8; 32]);
+        assert_eq!(proof.siblings.len(), 1);
+        assert!(proof.verify(&tree.root(), &[0u8; 32], Some(&"Some Data".to_string())));
+        tree.update(right, String::new());
+        tree.update([0u8; 32], String::new());
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), empty_root);
+    }
+
+    #[test]
+    fn zero_hash_is_all_zeros_and_sized_as_the_digest() {
+        assert_eq!(Sha256Algorithm::zero(), [0u8; 32]);
+        assert_eq!(Sha256Algorithm::hash_size(), 32);
+        assert_eq!(Keccak256Algorithm::hash_size(), 32);
+        assert_eq!(Sha256Algorithm::merkle_root(&[]), Sha256Algorithm::zero());
+
+        let trie: Trie<String> = Trie::new();
+        assert_eq!(trie.root.get_next_ref().get_hash(), Sha256Algorithm::zero());
+        assert!(trie.verify_integrity());
+    }
+
     #[test]
     fn traverse_trie_to_find_node_that_exists_returns_the_node() {
 
@@ -1,12 +1,22 @@
 
 #![allow(unused)]
-use crate::hash::{Hasher, Sha256Algorithm};
+use crate::hash::{Hasher, NoneHashPolicy, Sha256Algorithm};
 use crate::layer::Layer;
-pub use std::iter::{Iterator, DoubleEndedIterator, Rev};
-use std::cmp::{Eq, PartialEq};
+use crate::proof::{merkle_levels, merkle_path, outside_range, Divergence, ProofStep, RangeNode, Sibling};
+#[cfg(feature = "rlp")]
+use crate::rlp;
+use crate::store::NodeStore;
+use crate::trie::TrieDiff;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+pub use core::iter::{Iterator, DoubleEndedIterator, Rev};
+use core::cmp::{Eq, PartialEq};
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::Debug;
-use std::fmt::Display;
+use core::fmt::Debug;
+use core::fmt::Display;
 /// Fork, Branch and Leaf nodes.
 ///
 /// Fork Nodes contain a shared nibble.
@@ -15,27 +25,313 @@ use std::fmt::Display;
 /// or as a hashmap with nibble -> node and a hash (the hash of all Fork and branch nodes beneath them.)
 ///
 /// Leaf nodes contain the remainder of the address a value and a hash
-use std::hash::Hash;
-
-pub type RootHash = [u8; 32];
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ops::{Index, IndexMut};
+use alloc::sync::Arc;
+use alloc::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+pub type RootHash<H = Sha256Algorithm> = <H as Hasher>::Hash;
 pub type Nibble = u8;
-pub type Address = [u8; 32];
+pub type Address<const N: usize = 32> = [u8; N];
+
+/// Returns the path an `address` takes through a `Trie`, i.e. the `Nibble`
+/// selecting the slot in the `Branch` at each `Layer`, starting at the `Root`.
+///
+/// This is the single authority for the mapping: the byte at index `i` of
+/// the address (most significant first) is the `Nibble` at `Layer` `i`.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::node::path_of;
+///
+/// let mut address = [0u8; 32];
+/// address[0] = 0xab;
+/// address[31] = 0xcd;
+/// let path = path_of(&address);
+/// assert_eq!(path.len(), 32);
+/// assert_eq!((path[0], path[31]), (0xab, 0xcd));
+/// ```
+pub fn path_of<const N: usize>(address: &Address<N>) -> Vec<Nibble> {
+    (0..address.len()).map(|layer| nibble_at(address, layer)).collect()
+}
+
+/// Returns the `Nibble` that `address` takes in the `Branch` at `layer`,
+/// see `path_of`.
+pub(crate) fn nibble_at<const N: usize>(address: &Address<N>, layer: usize) -> Nibble {
+    address[layer]
+}
+
+/// Renders `bytes` as lowercase hexadecimal.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Renders the first 4 bytes of `bytes` as hexadecimal, followed by `..`
+/// if there are more.
+pub(crate) fn abbreviate(bytes: &[u8]) -> String {
+    if bytes.len() > 4 {
+        format!("{}..", to_hex(&bytes[..4]))
+    } else {
+        to_hex(bytes)
+    }
+}
+
+/// Returns the hash an occupied slot of a `Branch` contributes to the hash
+/// of the `Branch`, i.e. the hash of `nibble || hash`, so that the same node
+/// at a different `Nibble` yields a different `Branch` hash.
+pub(crate) fn slot_hash<H: Hasher>(nibble: Nibble, hash: &RootHash<H>) -> RootHash<H> {
+    let mut preimage = vec![nibble];
+    preimage.extend_from_slice(hash.as_ref());
+    H::hash(&preimage)
+}
+
+/// Returns the leaves of the binary Merkle tree a `Branch` with the occupied
+/// `slots`, in `Nibble` order, is hashed over, i.e. their slot hashes, along
+/// with a `Hasher::zero` for every unoccupied slot under
+/// `NoneHashPolicy::ZeroPlaceholder`.
+pub(crate) fn merkle_leaves<H: Hasher>(slots: Vec<(Nibble, RootHash<H>)>) -> Vec<RootHash<H>> {
+    if H::none_hash_policy() == NoneHashPolicy::Skip || slots.is_empty() {
+        return slots.into_iter().map(|(_, hash)| hash).collect();
+    }
+    let mut leaves = vec![H::zero(); usize::from(Nibble::MAX) + 1];
+    for (nibble, hash) in slots {
+        leaves[usize::from(nibble)] = hash;
+    }
+    leaves
+}
+
+/// Returns the index among the `merkle_leaves` of a `Branch` of the slot at
+/// `nibble`, the `position`th of its occupied slots.
+pub(crate) fn merkle_index<H: Hasher>(position: usize, nibble: Nibble) -> usize {
+    match H::none_hash_policy() {
+        NoneHashPolicy::Skip => position,
+        NoneHashPolicy::ZeroPlaceholder => usize::from(nibble),
+    }
+}
 
 #[derive(Debug)]
 pub struct InvalidBranchInsert;
 
 impl Display for InvalidBranchInsert {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(f, "Unable to insert the node into the Branch")
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for InvalidBranchInsert {
     fn description(&self) -> &str {
         "Unable to insert the node into the Branch"
     }
 }
 
+/// Errors returned when decoding a `Trie` from the bytes written by
+/// `Trie::to_bytes`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes ended in the middle of a node.
+    UnexpectedEnd,
+    /// The bytes were written with a different `Hasher`, identified by the
+    /// contained `Hasher::algorithm_id`.
+    AlgorithmMismatch(u8),
+    /// A node type tag other than `Data` or `Fork` was found.
+    InvalidTag(u8),
+    /// The occupied `Nibble`s of a `Branch` weren't in ascending order, or
+    /// a `Fork` was found at the last `Layer`.
+    InvalidBranch,
+    /// A `Leaf` address doesn't follow the path of `Nibble`s leading to it,
+    /// or a path of a `MultiProof` is empty, malformed or out of order.
+    InvalidPath,
+    /// A payload couldn't be converted back into `P`.
+    InvalidPayload,
+    /// Bytes were left over after the `Trie` was decoded.
+    TrailingBytes,
+    /// A node referenced by hash is missing from the `NodeStore`.
+    MissingNode,
+    /// A node read from the `NodeStore` doesn't hash to the hash it is
+    /// stored under.
+    HashMismatch,
+    /// The bytes aren't in the canonical RLP encoding.
+    InvalidRlp,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            DecodeError::AlgorithmMismatch(id) => {
+                write!(f, "The Trie was encoded with another hash algorithm ({})", id)
+            }
+            _ => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DecodeError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
+impl DecodeError {
+    fn message(&self) -> &str {
+        match self {
+            DecodeError::UnexpectedEnd => "The encoded Trie ended unexpectedly",
+            DecodeError::AlgorithmMismatch(_) => "The Trie was encoded with another hash algorithm",
+            DecodeError::InvalidTag(_) => "Unknown node type tag in the encoded Trie",
+            DecodeError::InvalidBranch => "Invalid Branch in the encoded Trie",
+            DecodeError::InvalidPath => "Leaf address doesn't match its path in the encoded Trie",
+            DecodeError::InvalidPayload => "Unable to decode a Leaf payload in the encoded Trie",
+            DecodeError::TrailingBytes => "Unexpected bytes after the encoded Trie",
+            DecodeError::MissingNode => "A node is missing from the NodeStore",
+            DecodeError::HashMismatch => "A node doesn't match the hash it is stored under",
+            DecodeError::InvalidRlp => "The encoded Trie isn't canonical RLP",
+        }
+    }
+}
+
+/// Groups `leaves` by their `Nibble` at `layer`, in `Nibble` order.
+fn group_by_nibble<P, H, const N: usize>(leaves: Vec<Leaf<P, H, N>>, layer: usize) -> BTreeMap<Nibble, Vec<Leaf<P, H, N>>>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    let mut groups: BTreeMap<Nibble, Vec<Leaf<P, H, N>>> = BTreeMap::new();
+    for leaf in leaves {
+        groups.entry(nibble_at(&leaf.address, layer)).or_default().push(leaf);
+    }
+    groups
+}
+
+/// Places `next`, the hashed `Branch` a `Fork` at `nibble` is left with by
+/// `Branch::intersection` or `Branch::difference`, among the `kept` nodes of
+/// the `Branch` above it, unless it is empty, or holds a single `Leaf`, which
+/// is moved up to `loose` to be reinserted in place of the `Fork`.
+fn place_fork<P, H, const N: usize>(kept: &mut BTreeMap<Nibble, Node<P, H, N>>, loose: &mut Vec<Leaf<P, H, N>>, nibble: Nibble, next: Branch<P, H, N>)
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    let single = {
+        let mut occupied = next.occupied_nodes();
+        match (occupied.next(), occupied.next()) {
+            (None, _) => return,
+            (Some((_, Node::Data { data, .. })), None) => Some(data.clone()),
+            _ => None,
+        }
+    };
+    match single {
+        Some(data) => loose.push(data),
+        None => {
+            let fork = Fork { nibble, next: Arc::new(next) };
+            let hash = fork.get_hash();
+            kept.insert(nibble, Node::Fork { fork, hash });
+        }
+    }
+}
+
+/// Returns the node at `nibble` of a `Branch` at `layer` once the leaves of
+/// `group`, all taking that `Nibble`, are inserted into the `existing` one,
+/// see `Branch::insert_many`. Every `Branch` beneath it is hashed.
+fn insert_group<P, H, const N: usize>(existing: Option<Node<P, H, N>>, nibble: Nibble, mut group: Vec<Leaf<P, H, N>>, layer: usize) -> Node<P, H, N>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    match existing {
+        None if group.len() == 1 => {
+            let data = group.remove(0).at_layer(layer);
+            let hash = data.get_hash();
+            Node::Data { data, hash }
+        }
+        existing => {
+            let next = Layer::try_from(layer + 1).expect("distinct addresses sharing a Nibble diverge further down the address");
+            let mut fork = match existing {
+                Some(Node::Fork { fork, .. }) => fork,
+                Some(Node::Data { data, .. }) => {
+                    group.push(data);
+                    Fork::with_hasher(nibble, next)
+                }
+                _ => Fork::with_hasher(nibble, next),
+            };
+            Arc::make_mut(&mut fork.next).insert_many(group);
+            let hash = fork.get_hash();
+            Node::Fork { fork, hash }
+        }
+    }
+}
+
+/// Node type tags written by `Branch::encode`.
+const DATA_TAG: u8 = 0;
+const FORK_TAG: u8 = 1;
+
+/// The hex-prefix flags opening the path of an RLP encoded leaf and
+/// extension node respectively, see `Branch::encode_rlp`.
+#[cfg(feature = "rlp")]
+const RLP_LEAF_PREFIX: u8 = 0x20;
+#[cfg(feature = "rlp")]
+const RLP_EXTENSION_PREFIX: u8 = 0x00;
+
+/// Converts stored bytes back into a hash of `H`.
+pub(crate) fn hash_from<H: Hasher>(bytes: &[u8]) -> Result<RootHash<H>, DecodeError> {
+    RootHash::<H>::try_from(bytes.to_vec()).map_err(|_| DecodeError::InvalidBranch)
+}
+
+/// Reads the `Branch` hash stored under the hash of a `Root` or `Fork`,
+/// checking it hashes to that hash.
+fn load_hash<H: Hasher, S: NodeStore<H>>(hash: &RootHash<H>, store: &S) -> Result<RootHash<H>, DecodeError> {
+    let next = hash_from::<H>(&store.get(hash).ok_or(DecodeError::MissingNode)?)?;
+    if H::hash(next.as_ref()) != *hash {
+        return Err(DecodeError::HashMismatch);
+    }
+    Ok(next)
+}
+
+/// Reads the record `Branch::commit` stored under `hash`, returning the
+/// `Nibble`, node type tag and hash of each occupied slot, in order. The
+/// hashes aren't checked, see `verify_slots`.
+fn load_slots<H: Hasher, S: NodeStore<H>>(hash: &RootHash<H>, store: &S) -> Result<Vec<(Nibble, u8, RootHash<H>)>, DecodeError> {
+    let record = store.get(hash).ok_or(DecodeError::MissingNode)?;
+    let mut bytes = record.as_slice();
+    let mut slots: Vec<(Nibble, u8, RootHash<H>)> = vec![];
+    while !bytes.is_empty() {
+        let nibble = take(&mut bytes, 1)?[0];
+        if slots.last().is_some_and(|(previous, _, _)| *previous >= nibble) {
+            return Err(DecodeError::InvalidBranch);
+        }
+        let tag = take(&mut bytes, 1)?[0];
+        let child = hash_from::<H>(take(&mut bytes, H::hash_size())?)?;
+        slots.push((nibble, tag, child));
+    }
+    Ok(slots)
+}
+
+/// Checks that the `slots` read by `load_slots` fold into `hash`, the hash
+/// of the `Branch` they were stored under, returning the levels of the
+/// Merkle tree they fold through, see `merkle_levels`.
+fn verify_slots<H: Hasher>(slots: &[(Nibble, u8, RootHash<H>)], hash: &RootHash<H>) -> Result<Vec<Vec<RootHash<H>>>, DecodeError> {
+    let hashes = slots.iter().map(|(nibble, _, child)| (*nibble, slot_hash::<H>(*nibble, child))).collect();
+    let levels = merkle_levels::<H>(merkle_leaves::<H>(hashes));
+    if levels.last().map_or_else(H::zero, |root| root[0]) != *hash {
+        return Err(DecodeError::HashMismatch);
+    }
+    Ok(levels)
+}
+
+/// Splits the first `len` bytes off `bytes`.
+pub(crate) fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], DecodeError> {
+    if bytes.len() < len {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
 /// An enum to contain (and insert into Branches) different Node types, i.e. a Fork node or a Data node
 /// This makes it simple to store the same `type` within a given `Branch` node in a `mpt::trie::Trie<P>`
 /// Also contains some helper functions to quickly determine the type of the node.
@@ -68,18 +364,24 @@ impl Error for InvalidBranchInsert {
 ///     hash: fork.get_hash(),
 /// };
 /// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "P: serde::Serialize, H::Hash: serde::Serialize",
+    deserialize = "P: serde::Deserialize<'de>, H::Hash: serde::Deserialize<'de>"
+)))]
 #[derive(Clone, Debug)]
-pub enum Node<P>
+pub enum Node<P, H = Sha256Algorithm, const N: usize = 32>
 where
     P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
-    Data { data: Leaf<P>, hash: RootHash },
-    Fork { fork: Fork<P>, hash: RootHash },
+    Data { data: Leaf<P, H, N>, hash: RootHash<H> },
+    Fork { fork: Fork<P, H, N>, hash: RootHash<H> },
     None,
 }
 
 /// The `Root` node struct is the root of a Trie, contains the first branch, initialized
-/// with 256 `Node::None` enums, representing each possible `Nibble`. The `Root` node's branch
+/// empty, with every possible `Nibble` unoccupied. The `Root` node's branch
 /// is always `Layer::Zero`.
 ///
 /// # Example
@@ -89,21 +391,27 @@ where
 ///
 /// let root: Root<String> = Root::default();
 /// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "P: serde::Serialize, H::Hash: serde::Serialize",
+    deserialize = "P: serde::Deserialize<'de>, H::Hash: serde::Deserialize<'de>"
+)))]
 #[derive(Clone, Debug)]
-pub struct Root<P>
+pub struct Root<P, H = Sha256Algorithm, const N: usize = 32>
 where
     P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
-    next: Branch<P>,
-    hash: RootHash,
+    next: Arc<Branch<P, H, N>>,
+    hash: RootHash<H>,
 }
 
 /// The `Branch` struct is a container for the various nodes in a trie at a given layer.
 /// The `Branch` struct maintains a `Layer` for quick determination of which layer within
-/// the trie this particular `Branch` sits, and then a `Vec<Node<P>>` which always has
-/// 256 `Node` variants, i.e. either a `Node::Data`, `Node::Fork` or `Node::None`. The
-/// index positions of each `Node` in the `Branch` `nibbles` field is the `Nibble` that
-/// this particular node represents. At a given `Nibble`, a node can either contain a `Node::Data`
+/// the trie this particular `Branch` sits, and then a `BTreeMap<Nibble, Node<P>>` which only
+/// holds the occupied `Nibble`s, i.e. either a `Node::Data` or a `Node::Fork`, any other
+/// `Nibble` reading as `Node::None`. The key of each `Node` in the `Branch` `nibbles` field is
+/// the `Nibble` that this particular node represents. At a given `Nibble`, a node can either contain a `Node::Data`
 /// if there is no shared nibble with any other `Leaf`, or if there is 1 more more `Leaf` sharing
 /// a given `Nibble`, at a given `Layer`, then the `Node` at the `Branch` `nibble` will be a
 /// `Node::Fork`, under which a new new `Branch` and the relevant leaves will sit.
@@ -117,20 +425,42 @@ where
 /// let branch: Branch<String> = Branch::new(Layer::One);
 ///
 /// ```
-#[derive(Clone, Debug)]
-pub struct Branch<P>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "P: serde::Serialize, H::Hash: serde::Serialize",
+    deserialize = "P: serde::Deserialize<'de>, H::Hash: serde::Deserialize<'de>"
+)))]
+#[derive(Clone)]
+pub struct Branch<P, H = Sha256Algorithm, const N: usize = 32>
 where
     P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
     layer: Layer,
-    nibbles: Vec<Node<P>>,
-    hash: RootHash,
+    // The occupied slots are deliberately always kept in a sparse map, with
+    // no dense 256 slot array once a `Branch` fills up. Per
+    // `benches/branch_storage.rs`, direct indexing saves at most ~11ns per
+    // `Branch`, i.e. 3-5% of a lookup in tries of 100k to 1M leaves, whose
+    // `Root` `Branch` and the ones below it are the only dense ones. A second
+    // representation would have to be handled by every method reading
+    // `nibbles`, by serialization and by commitment, for a few nanoseconds.
+    #[cfg_attr(feature = "serde", serde(with = "occupied_nibbles"))]
+    nibbles: BTreeMap<Nibble, Node<P, H, N>>,
+    hash: RootHash<H>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    dirty: bool,
+    /// The levels of the binary Merkle tree over the slot hashes as of the
+    /// last `hash_nibbles`, see `merkle_levels`, so that proofs read their
+    /// `Sibling` hashes rather than recompute them.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    levels: Vec<Vec<RootHash<H>>>,
 }
 
 /// `Fork` nodes are added to a `Trie` when there is a shared `Nibble` between
 /// Two `Leaf` node's at the current `Layer` of the previous `Branch` node.
 /// `Fork` nodes contain the shared `Nibble` and the `next` `Branch`. The `Branch`
-/// in the `Fork` node is `Boxed` to prevent infinite recursion.
+/// in the `Fork` node is behind an `Arc`, to prevent infinite recursion and so
+/// that cloned tries share it until either of them mutates it.
 ///
 /// # Example
 ///
@@ -144,13 +474,19 @@ where
 ///
 /// ```
 ///
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "P: serde::Serialize, H::Hash: serde::Serialize",
+    deserialize = "P: serde::Deserialize<'de>, H::Hash: serde::Deserialize<'de>"
+)))]
 #[derive(Clone, Debug)]
-pub struct Fork<P>
+pub struct Fork<P, H = Sha256Algorithm, const N: usize = 32>
 where
     P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
     nibble: Nibble,
-    next: Box<Branch<P>>,
+    next: Arc<Branch<P, H, N>>,
 }
 
 /// The `Leaf` is the basic data containing node for a `Trie`. The `Leaf` node
@@ -182,82 +518,368 @@ where
 /// let leaf = Leaf::new(address, payload);
 ///
 /// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "P: serde::Serialize",
+    deserialize = "P: serde::Deserialize<'de>"
+)))]
 #[derive(Clone, Debug)]
-pub struct Leaf<P>
+pub struct Leaf<P, H = Sha256Algorithm, const N: usize = 32>
 where
     P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
-    pub nibble: Nibble,
-    address: Address,
+    nibble: Nibble,
+    #[cfg_attr(feature = "serde", serde(with = "address_bytes"))]
+    address: Address<N>,
     remainder: Vec<u8>,
     payload: P,
+    hasher: PhantomData<H>,
+}
+
+/// A borrowed reference to any hashed node within a `Trie`, i.e. a `Branch`,
+/// a `Fork` or a `Leaf`. Returned by `Trie::node_by_hash` so that a hash
+/// (e.g. one referenced in a proof) can be resolved back to its node.
+#[derive(Debug)]
+pub enum NodeRef<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    Branch(&'a Branch<P, H, N>),
+    Fork(&'a Fork<P, H, N>),
+    Leaf(&'a Leaf<P, H, N>),
+}
+
+/// Serializes the `nibbles` of a `Branch` as a sequence of `(Nibble, Node)`
+/// pairs for the occupied slots, so an empty `Branch` costs no entries.
+#[cfg(feature = "serde")]
+mod occupied_nibbles {
+    use super::{BTreeMap, Hasher, Nibble, Node, Vec};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use core::fmt::Debug;
+
+    pub(super) fn serialize<P, H, S, const N: usize>(nibbles: &BTreeMap<Nibble, Node<P, H, N>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        P: Clone + Debug + Into<Vec<u8>> + Serialize,
+        H: Hasher,
+        H::Hash: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(nibbles.iter())
+    }
+
+    pub(super) fn deserialize<'de, P, H, D, const N: usize>(deserializer: D) -> Result<BTreeMap<Nibble, Node<P, H, N>>, D::Error>
+    where
+        P: Clone + Debug + Into<Vec<u8>> + Deserialize<'de>,
+        H: Hasher,
+        H::Hash: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let occupied: Vec<(Nibble, Node<P, H, N>)> = Vec::deserialize(deserializer)?;
+        Ok(occupied.into_iter().filter(|(_, node)| !node.is_none()).collect())
+    }
+}
+
+/// Serializes the `address` of a `Leaf` as a tuple of its bytes, just as
+/// serde does for the arrays it supports, which stop at 32 bytes.
+#[cfg(feature = "serde")]
+mod address_bytes {
+    use super::Address;
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserializer, Serializer};
+    use core::fmt;
+
+    pub(super) fn serialize<S, const N: usize>(address: &Address<N>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for byte in address {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+
+    pub(super) fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<Address<N>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct AddressVisitor<const N: usize>;
+
+        impl<'de, const N: usize> Visitor<'de> for AddressVisitor<N> {
+            type Value = Address<N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an address of {} bytes", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Address<N>, A::Error> {
+                let mut address = [0u8; N];
+                for (index, byte) in address.iter_mut().enumerate() {
+                    *byte = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(index, &self))?;
+                }
+                Ok(address)
+            }
+        }
+
+        deserializer.deserialize_tuple(N, AddressVisitor::<N>)
+    }
 }
 
 /// A type that implements Iterator for a Branch Node
 /// So that the Nodes in the Branch can be iterated over.
 #[derive(Clone, Debug)]
-pub struct BranchIntoIter<P>
+pub struct BranchIntoIter<P, H = Sha256Algorithm, const N: usize = 32>
 where
     P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
-    branch: Branch<P>,
+    branch: Branch<P, H, N>,
     layer: Layer,
-    index: u8,
+    index: usize,
+    back: usize,
 }
 
 /// A Type that implements Iterator for a borrowed and mutably borrowed
 /// Branch.
 #[derive(Clone, Debug)]
-pub struct BranchIterator<'a, P>
+pub struct BranchIterator<'a, P, H = Sha256Algorithm, const N: usize = 32>
 where
     P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
-    branch: &'a Branch<P>,
+    branch: &'a Branch<P, H, N>,
     layer: Layer,
-    index: u8,
+    index: usize,
+    back: usize,
+}
+
+/// A type that implements Iterator for an owned Fork, yielding the
+/// Nodes of the Branch the Fork points to.
+#[derive(Clone, Debug)]
+pub struct ForkIntoIterator<P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    inner: BranchIntoIter<P, H, N>,
+}
+
+/// A type that implements Iterator for a borrowed Fork, yielding the
+/// Nodes of the Branch the Fork points to.
+#[derive(Clone, Debug)]
+pub struct ForkIterator<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    inner: BranchIterator<'a, P, H, N>,
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Root<P> {
+impl<P: Clone + Debug + Into<Vec<u8>>, const N: usize> Root<P, Sha256Algorithm, N> {
     /// Generates a new, empty `Root`, i.e. a `Root` with a `next` that
-    /// has a `nibbles` field containing 256 `Node::None`. This method
-    /// is also invoked by `Root::default()`
-    pub fn new() -> Root<P> {
-        let next = Branch::new(Layer::Zero);
-        let hash = Sha256Algorithm::hash(&next.get_hash());
+    /// has no occupied `nibbles`, hashed with
+    /// `Sha256Algorithm`.
+    pub fn new() -> Root<P, Sha256Algorithm, N> {
+        Root::with_hasher()
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Root<P, H, N> {
+    /// Generates a new, empty `Root` hashed with `H`. This method is
+    /// also invoked by `Root::default()`
+    pub fn with_hasher() -> Root<P, H, N> {
+        Root::from_branch(Branch::with_hasher(Layer::Zero))
+    }
+
+    /// Returns a clone of the `Branch` in the `Root` node. The branches of
+    /// its forks are shared with the `Root` rather than copied, see
+    /// `get_next_ref` to borrow it instead.
+    pub fn get_next(&self) -> Branch<P, H, N> {
+        Branch::clone(&self.next)
+    }
+
+    /// Returns a reference to the `Branch` in the `Root` node.
+    pub fn get_next_ref(&self) -> &Branch<P, H, N> {
+        &self.next
+    }
 
-        Root { next, hash }
+    /// Consumes the `Root` and returns its `Branch`, without cloning it
+    /// unless it is shared with another `Root`.
+    pub(crate) fn into_next(self) -> Branch<P, H, N> {
+        Arc::unwrap_or_clone(self.next)
     }
 
-    /// Returns the `Branch` in the `Root` node.
-    pub fn get_next(&self) -> Branch<P> {
-        self.next.clone()
+    /// Builds a `Root` with the same shape whose `Leaf` payloads are
+    /// transformed by `f`, recomputing every hash along the way.
+    pub(crate) fn map_payloads<Q, F>(&self, f: &F) -> Root<Q, H, N>
+    where
+        Q: Clone + Debug + Into<Vec<u8>>,
+        F: Fn(&P) -> Q,
+    {
+        Root::from_branch(self.next.map_payloads(f))
     }
 
-    /// Returns a mutable reference to the next branch.
-    pub fn get_next_mut(&mut self) -> &mut Branch<P> {
-        &mut self.next
+    /// Builds a `Root` holding every `Leaf` of `self` and `other`, preferring
+    /// the payloads of `self` where both hold a `Leaf` at the same address.
+    pub(crate) fn union(&self, other: &Root<P, H, N>) -> Root<P, H, N> {
+        Root::from_branch(self.next.union(&other.next))
     }
 
-    /// Returns the branch's hash
-    pub fn get_hash(&self) -> RootHash {
+    /// Builds a `Root` holding the leaves of `self` whose address also has a
+    /// `Leaf` in `other`, see `Branch::intersection`.
+    pub(crate) fn intersection(&self, other: &Root<P, H, N>) -> Root<P, H, N> {
+        Root::from_branch(self.next.intersection(&other.next))
+    }
+
+    /// Builds a `Root` holding the leaves of `self` whose address has no
+    /// `Leaf` in `other`, see `Branch::difference`.
+    pub(crate) fn difference(&self, other: &Root<P, H, N>) -> Root<P, H, N> {
+        Root::from_branch(self.next.difference(&other.next))
+    }
+
+    /// Appends the binary encoding of the `Root` `Branch` to `out`, see
+    /// `Branch::encode`.
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        self.next.encode(out);
+    }
+
+    /// Decodes a `Root` written by `encode` from the front of `bytes`,
+    /// recomputing every hash.
+    pub(crate) fn decode(bytes: &mut &[u8]) -> Result<Root<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Ok(Root::from_branch(Branch::decode(bytes, &[])?))
+    }
+
+    /// Appends the RLP encoding of the `Root` `Branch` to `out`, see
+    /// `Branch::encode_rlp`.
+    #[cfg(feature = "rlp")]
+    pub(crate) fn encode_rlp(&self, out: &mut Vec<u8>) {
+        self.next.encode_rlp(out);
+    }
+
+    /// Decodes a `Root` written by `encode_rlp`, recomputing every hash. The
+    /// `Branch` at `Layer` `i` is a list nested `2 * i + 1` deep, and its
+    /// leaves and forks one deeper, so lists nested deeper than `2 * N` are
+    /// rejected before decoding them.
+    #[cfg(feature = "rlp")]
+    pub(crate) fn decode_rlp(bytes: &[u8]) -> Result<Root<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Ok(Root::from_branch(Branch::decode_rlp(&rlp::decode(bytes, 2 * N)?, &[])?))
+    }
+
+    /// Writes every node beneath the `Root` to `store`, see `Branch::commit`,
+    /// along with the `Root` itself, i.e. the hash of its `Branch`.
+    pub(crate) fn commit<S: NodeStore<H>>(&self, store: &mut S) {
+        self.next.commit(store);
+        store.put(self.get_hash(), self.next.get_hash().as_ref().to_vec());
+    }
+
+    /// Rebuilds the `Root` stored under `hash` in `store` by `commit`,
+    /// checking every node against the hash it is stored under.
+    pub(crate) fn load<S: NodeStore<H>>(hash: &RootHash<H>, store: &S) -> Result<Root<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let next = load_hash::<H, S>(hash, store)?;
+        let root = Root::from_branch(Branch::load(&next, store, &[])?);
+        if root.hash != *hash {
+            return Err(DecodeError::HashMismatch);
+        }
+        Ok(root)
+    }
+
+    /// Looks up the `Leaf` at `address` in the `Trie` stored under `hash` in
+    /// `store` by `commit`, see `Branch::load_leaf`.
+    pub(crate) fn load_leaf<S: NodeStore<H>>(hash: &RootHash<H>, store: &S, address: &Address<N>) -> Result<Option<Leaf<P, H, N>>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Branch::load_leaf(&load_hash::<H, S>(hash, store)?, store, address)
+    }
+
+    /// Collects the `ProofStep`s for the `Leaf` at `address` in the `Trie`
+    /// stored under `hash` in `store` by `commit`, see `Branch::load_proof`.
+    pub(crate) fn load_proof<S: NodeStore<H>>(hash: &RootHash<H>, store: &S, address: &Address<N>) -> Result<Option<Vec<ProofStep<H>>>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Branch::<P, H, N>::load_proof(&load_hash::<H, S>(hash, store)?, store, address)
+    }
+
+    /// Wraps an already hashed `Layer::Zero` `Branch` in a new `Root`.
+    fn from_branch(next: Branch<P, H, N>) -> Root<P, H, N> {
+        let hash = H::hash(next.get_hash().as_ref());
+
+        Root { next: Arc::new(next), hash }
+    }
+
+    /// Returns a mutable reference to the next branch, first copying it if
+    /// it is shared with a clone of the `Root`. The `Root` hash isn't
+    /// refreshed by mutating the `Branch`, call `rehash` once done.
+    pub fn get_next_mut(&mut self) -> &mut Branch<P, H, N> {
+        Arc::make_mut(&mut self.next)
+    }
+
+    /// Recomputes the `Root` hash from the hash of its `Branch`, first
+    /// hashing every dirty `Branch`, see `Branch::rehash`. Must be called
+    /// after mutating the `Branch` through `get_next_mut`.
+    pub fn rehash(&mut self) {
+        if self.next.dirty {
+            Arc::make_mut(&mut self.next).rehash();
+        }
+        self.hash = H::hash(self.next.get_hash().as_ref());
+    }
+
+    /// Returns the branch's hash, computed on the fly, and not cached, while
+    /// its `Branch` is dirty, see `rehash`, which `Trie::root_hash` calls.
+    pub fn get_hash(&self) -> RootHash<H> {
+        if self.next.dirty {
+            return H::hash(self.next.get_hash().as_ref());
+        }
         self.hash
     }
 
     /// Get's a node from the `Root` `Branch`
-    pub fn get(&self, index: &u8) -> Node<P> {
-        self.get_next().get(index)
+    pub fn get(&self, index: &u8) -> Node<P, H, N> {
+        self.next.get(index)
+    }
+
+    /// Returns a reference to the node at `index` in the `Root` `Branch`.
+    pub fn get_ref(&self, index: &u8) -> &Node<P, H, N> {
+        self.next.get_ref(index)
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Branch<P> {
-    /// Given a `Layer`, returns a new `Branch`.
-    pub fn new(layer: Layer) -> Branch<P> {
-        let mut nibbles: Vec<Node<P>> = Vec::with_capacity(256);
-        nibbles.extend(vec![Node::None; 256]);
+impl<P: Clone + Debug + Into<Vec<u8>>, const N: usize> Branch<P, Sha256Algorithm, N> {
+    /// Given a `Layer`, returns a new `Branch` hashed with `Sha256Algorithm`.
+    pub fn new(layer: Layer) -> Branch<P, Sha256Algorithm, N> {
+        Branch::with_hasher(layer)
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Branch<P, H, N> {
+    /// Each byte of an address is the `Nibble` at its own `Layer`, so an
+    /// address must have at least one byte and at most one per `Layer`,
+    /// i.e. 32. Evaluated, and so checked at compile time, by `with_hasher`.
+    const ADDRESS_LEN_IN_RANGE: () = assert!(N > 0 && N <= 32, "the address length must lie within 1..=32");
+
+    /// Given a `Layer`, returns a new `Branch` hashed with `H`.
+    pub fn with_hasher(layer: Layer) -> Branch<P, H, N> {
+        let () = Self::ADDRESS_LEN_IN_RANGE;
+        let hash = H::zero();
         Branch {
             layer,
-            nibbles,
-            hash: [0u8; 32],
+            nibbles: BTreeMap::new(),
+            hash,
+            dirty: false,
+            levels: Vec::new(),
         }
     }
 
@@ -269,7 +891,15 @@ impl<P: Clone + Debug + Into<Vec<u8>>> Branch<P> {
     /// is inserted, and the `Leaf` node that the new `Leaf` had a shared
     /// Nibble with, along with the new `Leaf` attempting to be inserted
     /// are moved down to a new `Branch` that is created when a new
-    /// `Fork` is created.
+    /// `Fork` is created. A `Leaf` with the same address as one already in
+    /// the `Branch` overwrites it, rather than forking down past the end of
+    /// the address.
+    ///
+    /// Nothing is hashed yet, the `Branch` and every `Branch` below it on the
+    /// path of the `Leaf` are marked dirty instead, so that inserting many
+    /// leaves hashes each `Branch` once, when `rehash` is called. Until then
+    /// the hash cached in a `Fork` on the path is stale, and `get_hash`
+    /// computes the hashes of dirty nodes on the fly.
     ///
     /// # Example
     ///
@@ -289,37 +919,111 @@ impl<P: Clone + Debug + Into<Vec<u8>>> Branch<P> {
     ///     _ => { panic!("Not the right type of Node") }
     /// }
     /// ```
-    pub fn insert(&mut self, leaf: Leaf<P>) {
-        let index = leaf.nibble as usize;
-        let node = &self.nibbles[index].clone();
-        match node.clone() {
+    pub fn insert(&mut self, leaf: Leaf<P, H, N>) {
+        let layer: u8 = self.layer.clone().into();
+        let leaf = leaf.at_layer(layer as usize);
+        let index = leaf.nibble;
+        match self.nibbles.remove(&index).unwrap_or(Node::None) {
             Node::Fork { mut fork, hash } => {
                 fork.insert(leaf);
-                let hash = fork.get_hash();
-                self.nibbles[index] = Node::Fork { fork, hash };
-                self.hash_nibbles();
+                self.nibbles.insert(index, Node::Fork { fork, hash });
             }
-            Node::Data { data, hash } => {
-                let mut layer: u8 = self.layer.clone().into();
-                let fork = Fork::from((leaf, data.clone(), layer as usize));
+            Node::Data { data, .. } if data.address != leaf.address => {
+                let mut fork = Fork::try_from((leaf, data, layer as usize)).expect("distinct addresses sharing a Nibble diverge further down the address");
+                // The new `Branch` holds only the two leaves, hashing it now
+                // costs what the next `rehash` would and leaves it clean.
+                Arc::make_mut(&mut fork.next).rehash();
                 let hash = fork.get_hash();
-                self.nibbles[index] = Node::Fork { fork, hash };
-                self.hash_nibbles();
+                self.nibbles.insert(index, Node::Fork { fork, hash });
             }
-            Node::None => {
+            Node::Data { .. } | Node::None => {
                 let hash = leaf.get_hash();
-                self.nibbles[index] = Node::Data {
+                self.nibbles.insert(index, Node::Data {
                     data: leaf,
-                    hash: hash,
-                };
-                self.hash_nibbles();
+                    hash,
+                });
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Inserts `leaves`, whose addresses must be distinct and not yet in this
+    /// `Branch`, grouping them by their `Nibble` at this `Layer` so that this
+    /// `Branch`, and every `Branch` beneath it gaining a `Leaf`, is rehashed
+    /// once rather than once per `Leaf`. The result is the same as inserting
+    /// the leaves one at a time.
+    pub(crate) fn insert_many(&mut self, leaves: Vec<Leaf<P, H, N>>) {
+        let layer: u8 = self.layer.clone().into();
+        let layer = layer as usize;
+        for (nibble, group) in group_by_nibble(leaves, layer) {
+            let node = insert_group(self.nibbles.remove(&nibble), nibble, group, layer);
+            self.nibbles.insert(nibble, node);
+        }
+        self.hash_nibbles();
+    }
+
+    /// Places `node` at index position `nibble`, replacing whatever was
+    /// there, and rehashes the `Branch`.
+    pub(crate) fn set(&mut self, nibble: Nibble, node: Node<P, H, N>) {
+        self.nibbles.insert(nibble, node);
+        self.hash_nibbles();
+    }
+
+    /// Places `leaf` directly in the `Branch` at `layer` on its path, e.g. when
+    /// reconstructing a dumped `Trie` whose layers are known, without searching
+    /// for the point at which it diverges from other leaves. Missing `Fork`
+    /// nodes on the path are created. Fails if the path runs into a `Leaf` or
+    /// the slot at `layer` is already occupied.
+    pub(crate) fn insert_at_layer(
+        &mut self,
+        leaf: Leaf<P, H, N>,
+        layer: usize,
+    ) -> Result<(), InvalidBranchInsert> {
+        let own_layer: u8 = self.layer.clone().into();
+        let own_layer = own_layer as usize;
+        let index = nibble_at(&leaf.address, own_layer);
+        if own_layer == layer {
+            if !self.slot(index).is_none() {
+                return Err(InvalidBranchInsert);
+            }
+            let leaf = leaf.at_layer(layer);
+            let hash = leaf.get_hash();
+            self.nibbles.insert(index, Node::Data { data: leaf, hash });
+        } else {
+            if self.slot(index).is_none() {
+                let next = Layer::try_from(own_layer + 1).map_err(|_| InvalidBranchInsert)?;
+                let fork = Fork::with_hasher(index, next);
+                let hash = fork.get_hash();
+                self.nibbles.insert(index, Node::Fork { fork, hash });
+            }
+            match self.nibbles.get_mut(&index) {
+                Some(Node::Fork { fork, hash }) => {
+                    Arc::make_mut(&mut fork.next).insert_at_layer(leaf, layer)?;
+                    *hash = fork.get_hash();
+                }
+                _ => return Err(InvalidBranchInsert),
             }
         }
+        self.hash_nibbles();
+        Ok(())
+    }
+
+    /// Returns the `Node` sitting at index position `nibble`, `Node::None`
+    /// if it is unoccupied.
+    pub fn get(&self, nibble: &Nibble) -> Node<P, H, N> {
+        self.slot(*nibble).clone()
     }
 
-    /// Returns the `Node` sitting at index position `nibble`
-    pub fn get(&self, nibble: &Nibble) -> Node<P> {
-        self.nibbles[*nibble as usize].clone()
+    /// Returns a reference to the `Node` sitting at index position `nibble`,
+    /// `Node::None` if it is unoccupied, without cloning it like `get` does.
+    pub fn get_ref(&self, nibble: &Nibble) -> &Node<P, H, N> {
+        self.slot(*nibble)
+    }
+
+    /// Returns a reference to the `Node` at `nibble`, or to a `Node::None` if
+    /// the `nibble` is unoccupied, since only occupied slots are stored.
+    fn slot(&self, nibble: Nibble) -> &Node<P, H, N> {
+        self.nibbles.get(&nibble).unwrap_or(&Node::None)
     }
 
     /// Returns the u8 representation of the `Layer`
@@ -330,364 +1034,1953 @@ impl<P: Clone + Debug + Into<Vec<u8>>> Branch<P> {
 
     /// Returns a [u8; 32] representing the Sha256 hash
     /// of the current branch (i.e. the hash of all the hashes at each `nibble`)
-    pub fn get_hash(&self) -> RootHash {
-        self.hash
+    /// If the `Branch` is dirty, the hash is computed from the cached hashes
+    /// of its clean nodes and the computed hashes of its dirty ones, without
+    /// being cached, as that takes `&mut self`, see `rehash`.
+    pub fn get_hash(&self) -> RootHash<H> {
+        if !self.dirty {
+            return self.hash;
+        }
+        H::merkle_root(&merkle_leaves::<H>(self.slot_hashes()))
     }
 
-    /// Reverses the order of the nodes. Returns a cloned version so original stays in correct order
-    pub fn reverse_nibbles(&self) -> Branch<P> {
-        let mut rev_branch = self.clone();
-        rev_branch.nibbles.reverse();
-        rev_branch
-    } 
-
-    /// Get's all the not-None Nodes from the branch, concatenates their hashes
-    /// in order of their index, and hashes the concatenated hash.
-    pub fn hash_nibbles(&mut self) {
-        let mut hash_options: Vec<Option<[u8; 32]>> =
-            self.nibbles.iter().map(|node| node.get_hash()).collect();
-        hash_options.retain(|hash| hash.is_some());
-        let mut hashes: Vec<[u8; 32]> = hash_options.iter().map(|hash| hash.unwrap()).collect();
-        let concat: Vec<u8> = hashes.into_iter().flatten().collect();
-        let hash = Sha256Algorithm::hash(&concat);
-        self.hash = hash;
+    /// Returns true if a `Leaf` was inserted into this `Branch`, or beneath
+    /// it, since it was last hashed, see `rehash`.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
     }
-}
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Fork<P> {
-    /// Creates a new `Fork` given a shared `nibble` and the `layer` + 1
-    /// at which the shared `nibble` was discovered, so that a new
-    /// `Branch` with the conflicting `Leaf` nodes can be created.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use mmpt::node::Fork;
-    /// use mmpt::layer::Layer;
-    ///
-    /// let fork: Fork<String> = Fork::new(5, Layer::Two);
-    /// println!("{:?}", fork);
-    /// ```
-    pub fn new(nibble: Nibble, layer: Layer) -> Fork<P> {
-        Fork {
-            nibble,
-            next: Box::new(Branch::new(layer)),
+    /// Recomputes and caches the hash of this `Branch` if it is dirty, along
+    /// with the hashes of the dirty nodes beneath it, bottom up. Clean
+    /// subtrees keep their cached hashes.
+    pub fn rehash(&mut self) {
+        if self.dirty {
+            self.hash_nibbles();
         }
     }
 
-    /// Returns the `dereferenced` i.e. `Unboxed` `Branch`
-    /// underpinning this `Fork`
-    pub fn get_next(&self) -> Branch<P> {
-        *self.next.clone()
+    /// Merges `other`, a `Branch` at the same `Layer`, into a copy of `self`,
+    /// preferring the payloads of `self` where both hold a `Leaf` at the same
+    /// address. Subtrees with equal hashes are taken as they are.
+    pub(crate) fn union(&self, other: &Branch<P, H, N>) -> Branch<P, H, N> {
+        let mut branch = self.clone();
+        if self.get_hash() == other.get_hash() {
+            return branch;
+        }
+        let layer: u8 = self.layer.clone().into();
+        for (index, theirs) in other.nibbles.iter() {
+            let merged = match (self.slot(*index), theirs) {
+                (_, Node::None) => continue,
+                (ours, theirs) if ours.get_hash() == theirs.get_hash() => continue,
+                (Node::None, theirs) => theirs.clone(),
+                (Node::Fork { fork, .. }, Node::Fork { fork: other_fork, .. }) => {
+                    let fork = Fork {
+                        nibble: fork.nibble,
+                        next: Arc::new(fork.next.union(&other_fork.next)),
+                    };
+                    let hash = fork.get_hash();
+                    Node::Fork { fork, hash }
+                }
+                (Node::Fork { fork, .. }, Node::Data { data, .. }) => {
+                    if fork.next.find_leaf(&data.address).is_some() {
+                        continue;
+                    }
+                    let mut fork = fork.clone();
+                    fork.insert(data.clone());
+                    let hash = fork.get_hash();
+                    Node::Fork { fork, hash }
+                }
+                (Node::Data { data, .. }, Node::Fork { fork, .. }) => {
+                    let mut fork = fork.clone();
+                    if fork.next.find_leaf(&data.address).is_some() {
+                        Arc::make_mut(&mut fork.next).replace_payload(&data.address, data.payload.clone());
+                    } else {
+                        fork.insert(data.clone());
+                    }
+                    let hash = fork.get_hash();
+                    Node::Fork { fork, hash }
+                }
+                (Node::Data { data, .. }, Node::Data { data: other_data, .. }) => {
+                    if data.address == other_data.address {
+                        continue;
+                    }
+                    let fork = Fork::try_from((data.clone(), other_data.clone(), layer as usize)).expect("distinct addresses sharing a Nibble diverge further down the address");
+                    let hash = fork.get_hash();
+                    Node::Fork { fork, hash }
+                }
+            };
+            branch.nibbles.insert(*index, merged);
+        }
+        branch.hash_nibbles();
+        branch
     }
 
-    /// Returns the hash of the `Branch` underpinning this `Fork`
-    /// hash.
-    pub fn get_hash(&self) -> RootHash {
-        Sha256Algorithm::hash(&self.next.get_hash())
+    /// Builds a `Branch` holding the leaves of `self` whose address also has
+    /// a `Leaf` beneath `other`, a `Branch` at the same `Layer`, keeping the
+    /// payloads of `self`. Nodes with equal hashes are taken as they are,
+    /// sharing their `Branch` with `self`, and the leaves that have to move
+    /// to this `Layer` are inserted together, see `with_remainder`.
+    pub(crate) fn intersection(&self, other: &Branch<P, H, N>) -> Branch<P, H, N> {
+        if self.get_hash() == other.get_hash() {
+            return self.clone();
+        }
+        let mut kept = BTreeMap::new();
+        let mut loose = vec![];
+        for (nibble, ours) in self.occupied_nodes() {
+            match (ours, other.slot(*nibble)) {
+                (_, Node::None) => {}
+                (ours, theirs) if ours.get_hash() == theirs.get_hash() => {
+                    kept.insert(*nibble, ours.clone());
+                }
+                (Node::Fork { fork, .. }, Node::Fork { fork: other_fork, .. }) => {
+                    place_fork(&mut kept, &mut loose, fork.nibble, fork.next.intersection(&other_fork.next))
+                }
+                (Node::Fork { fork, .. }, Node::Data { data, .. }) => {
+                    loose.extend(fork.next.find_leaf(&data.address).cloned())
+                }
+                (Node::Data { data, .. }, theirs) => {
+                    if theirs.find_leaf(&data.address).is_some() {
+                        kept.insert(*nibble, ours.clone());
+                    }
+                }
+                (Node::None, _) => {}
+            }
+        }
+        self.with_remainder(kept, loose)
     }
 
-    /// Inserts a leaf into the `Branch` in the `Fork`.
-    pub fn insert(&mut self, leaf: Leaf<P>) {
-        self.next.insert(leaf);
+    /// Builds a `Branch` holding the leaves of `self` whose address has no
+    /// `Leaf` beneath `other`, a `Branch` at the same `Layer`. Nodes with
+    /// equal hashes are skipped in bulk, those `other` has no counterpart
+    /// for are taken as they are, see `intersection`.
+    pub(crate) fn difference(&self, other: &Branch<P, H, N>) -> Branch<P, H, N> {
+        if self.get_hash() == other.get_hash() {
+            return self.with_remainder(BTreeMap::new(), vec![]);
+        }
+        let mut kept = BTreeMap::new();
+        let mut loose = vec![];
+        for (nibble, ours) in self.occupied_nodes() {
+            match (ours, other.slot(*nibble)) {
+                (ours, Node::None) => {
+                    kept.insert(*nibble, ours.clone());
+                }
+                (ours, theirs) if ours.get_hash() == theirs.get_hash() => {}
+                (Node::Fork { fork, .. }, Node::Fork { fork: other_fork, .. }) => {
+                    place_fork(&mut kept, &mut loose, fork.nibble, fork.next.difference(&other_fork.next))
+                }
+                (Node::Fork { fork, .. }, Node::Data { data, .. }) => {
+                    if fork.next.find_leaf(&data.address).is_none() {
+                        kept.insert(*nibble, ours.clone());
+                    } else {
+                        let mut leaves = vec![];
+                        fork.next.collect_leaves(&mut leaves);
+                        loose.extend(leaves.into_iter().filter(|leaf| leaf.address != data.address).cloned());
+                    }
+                }
+                (Node::Data { data, .. }, theirs) => {
+                    if theirs.find_leaf(&data.address).is_none() {
+                        kept.insert(*nibble, ours.clone());
+                    }
+                }
+                (Node::None, _) => {}
+            }
+        }
+        self.with_remainder(kept, loose)
     }
 
-    /// Get the node at the index in the `Fork` `Branch`
-    pub fn get(&self, index: &u8) -> Node<P> {
-        self.get_next().get(index)
+    /// Builds a `Branch` at the `Layer` of `self` from the `kept` nodes, all
+    /// hashed, and the `loose` leaves, whose `Nibble`s none of them take, by
+    /// a single `insert_many`, so that it is hashed exactly once.
+    fn with_remainder(&self, kept: BTreeMap<Nibble, Node<P, H, N>>, loose: Vec<Leaf<P, H, N>>) -> Branch<P, H, N> {
+        let mut branch = Branch {
+            layer: self.layer.clone(),
+            nibbles: kept,
+            hash: H::zero(),
+            dirty: true,
+            levels: Vec::new(),
+        };
+        branch.insert_many(loose);
+        branch
     }
-}
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Leaf<P> {
-    /// Returns a new `Leaf` node given an `address`
-    /// and a `payload`.
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use mmpt::node::Leaf;
-    ///
-    /// let address = [0u8; 32];
-    /// let payload = "Some Data".to_string();
-    ///
-    /// let leaf: Leaf<String> = Leaf::new(address, payload);
-    ///
-    /// println!("{:?}", leaf.get_payload());
-    /// println!("{:?}", leaf.get_hash());
-    /// ```
-    pub fn new(address: [u8; 32], payload: P) -> Leaf<P> {
-        let nibble = address[0];
-        let remainder = address[1..].to_vec();
-        let payload = payload;
-
-        Leaf {
-            nibble,
-            address,
-            remainder,
-            payload,
+    /// Records in `diff` the addresses at which this `Branch` and `other`, a
+    /// `Branch` at the same `Layer`, differ. Subtrees with equal hashes are
+    /// skipped in bulk, and where the two hold a `Leaf` and a `Fork` at the
+    /// same `Nibble`, only the leaves beneath them are compared.
+    pub(crate) fn collect_diff(&self, other: &Branch<P, H, N>, diff: &mut TrieDiff<N>) {
+        if self.get_hash() == other.get_hash() {
+            return;
+        }
+        let nibbles: BTreeSet<&Nibble> = self.nibbles.keys().chain(other.nibbles.keys()).collect();
+        for nibble in nibbles {
+            match (self.slot(*nibble), other.slot(*nibble)) {
+                (ours, theirs) if ours.get_hash() == theirs.get_hash() => {}
+                (Node::Fork { fork, .. }, Node::Fork { fork: other_fork, .. }) => {
+                    fork.next.collect_diff(&other_fork.next, diff)
+                }
+                (ours, theirs) => {
+                    let (mut our_leaves, mut their_leaves) = (vec![], vec![]);
+                    ours.collect_leaves(&mut our_leaves);
+                    theirs.collect_leaves(&mut their_leaves);
+                    let mut theirs: BTreeMap<Address<N>, &Leaf<P, H, N>> =
+                        their_leaves.into_iter().map(|leaf| (leaf.address, leaf)).collect();
+                    for leaf in our_leaves {
+                        match theirs.remove(&leaf.address) {
+                            None => diff.only_in_self.push(leaf.address),
+                            Some(other) if other.get_hash() != leaf.get_hash() => diff.changed.push(leaf.address),
+                            Some(_) => {}
+                        }
+                    }
+                    diff.only_in_other.extend(theirs.into_keys());
+                }
+            }
         }
     }
-    /// Returns the payload for the current leaf
-    pub fn get_payload(&self) -> P {
-        self.payload.clone()
+
+    /// Collects every `Leaf` beneath this `Branch` in ascending address order.
+    pub(crate) fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a Leaf<P, H, N>>) {
+        self.nibbles.values().for_each(|node| node.collect_leaves(leaves));
     }
 
-    pub fn get_address(&self) -> Address {
-        self.address
+    /// Returns the smallest address beneath this `Branch` by descending into
+    /// the lowest occupied `Nibble` at every `Layer`.
+    pub(crate) fn min_address(&self) -> Option<Address<N>> {
+        self.nibbles.values().next()?.boundary_address(Branch::min_address)
     }
 
-    /// Returns the hash of the current leaf
-    pub fn get_hash(&self) -> RootHash {
+    /// Returns the largest address beneath this `Branch` by descending into
+    /// the highest occupied `Nibble` at every `Layer`.
+    pub(crate) fn max_address(&self) -> Option<Address<N>> {
+        self.nibbles.values().next_back()?.boundary_address(Branch::max_address)
+    }
+
+    /// Removes the `Leaf` at `address` from beneath this `Branch`, returning it.
+    /// A `Fork` left holding a single `Leaf` is collapsed into that `Leaf`, so
+    /// the shape stays what inserting the remaining leaves would produce. Every
+    /// node on the path is rehashed. Returns `None` if there is no such `Leaf`.
+    pub(crate) fn remove(&mut self, address: &Address<N>) -> Option<Leaf<P, H, N>> {
+        let layer: u8 = self.layer.clone().into();
+        let node = self.nibbles.get_mut(&nibble_at(address, layer as usize))?;
+        let removed = match node {
+            Node::Data { data, .. } if &data.address == address => {
+                match core::mem::replace(node, Node::None) {
+                    Node::Data { data, .. } => data,
+                    _ => unreachable!("the node was just matched as a Leaf"),
+                }
+            }
+            Node::Fork { fork, .. } => {
+                let removed = Arc::make_mut(&mut fork.next).remove(address)?;
+                Branch::collapse_fork(node, layer as usize);
+                if let Node::Fork { fork, hash } = node {
+                    *hash = fork.get_hash();
+                }
+                removed
+            }
+            _ => return None,
+        };
+        self.hash_nibbles();
+        Some(removed)
+    }
+
+    /// Removes every `Leaf` beneath this `Branch` for which `f` returns false,
+    /// collapsing the forks left holding a single `Leaf`, see `collapse_fork`.
+    /// The branches losing a `Leaf` are marked dirty rather than rehashed,
+    /// see `rehash`. Returns true if any `Leaf` was removed.
+    pub(crate) fn retain<F: FnMut(&Address<N>, &P) -> bool>(&mut self, f: &mut F) -> bool {
+        let layer: u8 = self.layer.clone().into();
+        let mut removed = false;
+        for node in self.nibbles.values_mut() {
+            match node {
+                Node::Data { data, .. } => {
+                    if !f(&data.address, &data.payload) {
+                        *node = Node::None;
+                        removed = true;
+                    }
+                }
+                Node::Fork { fork, .. } => {
+                    if Arc::make_mut(&mut fork.next).retain(f) {
+                        Branch::collapse_fork(node, layer as usize);
+                        removed = true;
+                    }
+                }
+                Node::None => {}
+            }
+        }
+        if removed {
+            self.nibbles.retain(|_, node| !node.is_none());
+            self.dirty = true;
+        }
+        removed
+    }
+
+    /// Removes the leaves at `addresses` from beneath this `Branch` in a single
+    /// traversal, descending once into each `Fork` with the run of addresses
+    /// taking its `Nibble`, so `addresses` should be sorted. Forks are
+    /// collapsed and branches marked dirty as in `retain`. Returns the number
+    /// of leaves removed.
+    pub(crate) fn remove_many(&mut self, addresses: &[Address<N>]) -> usize {
+        let layer: u8 = self.layer.clone().into();
+        let mut removed = 0;
+        for run in addresses.chunk_by(|a, b| nibble_at(a, layer as usize) == nibble_at(b, layer as usize)) {
+            let node = match self.nibbles.get_mut(&nibble_at(&run[0], layer as usize)) {
+                Some(node) => node,
+                None => continue,
+            };
+            match node {
+                Node::Data { data, .. } => {
+                    if run.contains(&data.address) {
+                        *node = Node::None;
+                        removed += 1;
+                    }
+                }
+                Node::Fork { fork, .. } => {
+                    let count = Arc::make_mut(&mut fork.next).remove_many(run);
+                    if count > 0 {
+                        Branch::collapse_fork(node, layer as usize);
+                        removed += count;
+                    }
+                }
+                Node::None => {}
+            }
+        }
+        if removed > 0 {
+            self.nibbles.retain(|_, node| !node.is_none());
+            self.dirty = true;
+        }
+        removed
+    }
+
+    /// Replaces the `Fork` in `node` with the `Leaf` beneath it once its
+    /// `Branch` holds a single `Leaf`, and with `Node::None` once it holds
+    /// nothing, so the shape stays what inserting the remaining leaves would
+    /// produce. `layer` is the `Layer` of the `Branch` holding `node`.
+    fn collapse_fork(node: &mut Node<P, H, N>, layer: usize) {
+        let collapsed = match node {
+            Node::Fork { fork, .. } if fork.next.occupied() == 0 => Node::None,
+            Node::Fork { fork, .. } => match fork.next.single_leaf() {
+                Some(leaf) => {
+                    let data = leaf.clone().at_layer(layer);
+                    let hash = data.get_hash();
+                    Node::Data { data, hash }
+                }
+                None => return,
+            },
+            _ => return,
+        };
+        *node = collapsed;
+    }
+
+    /// Returns the `Leaf` in this `Branch` if it is the only occupied slot.
+    fn single_leaf(&self) -> Option<&Leaf<P, H, N>> {
+        let mut occupied = self.occupied_nodes().map(|(_, node)| node);
+        match (occupied.next(), occupied.next()) {
+            (Some(Node::Data { data, .. }), None) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Follows `address` through this `Branch` and any `Fork` beneath it,
+    /// returning the `Leaf` stored at exactly that address, if there is one.
+    pub(crate) fn find_leaf(&self, address: &Address<N>) -> Option<&Leaf<P, H, N>> {
+        match self.find_data(address)? {
+            Node::Data { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Follows `address` through this `Branch` and any `Fork` beneath it,
+    /// returning the `Node::Data` holding exactly that address, if there is one.
+    pub(crate) fn find_data(&self, address: &Address<N>) -> Option<&Node<P, H, N>> {
+        let layer: u8 = self.layer.clone().into();
+        self.slot(nibble_at(address, layer as usize)).find_data(address)
+    }
+
+    /// Replaces the payload of the `Leaf` at `address` beneath this `Branch`,
+    /// rehashing every node on the path to it, and returns the old payload.
+    /// Returns `None` without touching anything if there is no such `Leaf`.
+    pub(crate) fn replace_payload(&mut self, address: &Address<N>, payload: P) -> Option<P> {
+        let layer: u8 = self.layer.clone().into();
+        let index = nibble_at(address, layer as usize);
+        let old = match self.nibbles.get_mut(&index)? {
+            Node::Data { data, hash } if &data.address == address => {
+                let old = core::mem::replace(&mut data.payload, payload);
+                *hash = data.get_hash();
+                old
+            }
+            Node::Fork { fork, hash } => {
+                let old = Arc::make_mut(&mut fork.next).replace_payload(address, payload)?;
+                *hash = fork.get_hash();
+                old
+            }
+            _ => return None,
+        };
+        self.hash_nibbles();
+        Some(old)
+    }
+
+    /// Returns the number of `Nibble` slots that aren't `Node::None`.
+    pub(crate) fn occupied(&self) -> usize {
+        self.nibbles.values().filter(|node| !node.is_none()).count()
+    }
+
+    /// Returns an iterator over the `Nibble`s that aren't `Node::None` and
+    /// references to their nodes, in `Nibble` order.
+    pub(crate) fn occupied_nodes(&self) -> impl Iterator<Item = (&Nibble, &Node<P, H, N>)> {
+        self.nibbles.iter().filter(|(_, node)| !node.is_none())
+    }
+
+    /// Returns an iterator over the `Nibble`s held and references to their
+    /// nodes, in `Nibble` order, including any slot `IndexMut` left holding
+    /// `Node::None`, see `occupied_nodes`.
+    pub(crate) fn nodes(&self) -> alloc::collections::btree_map::Iter<'_, Nibble, Node<P, H, N>> {
+        self.nibbles.iter()
+    }
+
+    /// Reverses the order of the nodes. Returns a cloned version so original stays in correct order
+    pub fn reverse_nibbles(&self) -> Branch<P, H, N> {
+        let mut rev_branch = self.clone();
+        rev_branch.nibbles = self
+            .nibbles
+            .iter()
+            .map(|(nibble, node)| (Nibble::MAX - nibble, node.clone()))
+            .collect();
+        rev_branch
+    } 
+
+    /// Drops any `Node::None` left in the branch by a removal, then takes all
+    /// the other Nodes from the branch, binds each hash to its
+    /// position by hashing `nibble || hash`, and folds those, in order of
+    /// their index, into a binary Merkle root, see `Hasher::merkle_root`.
+    pub fn hash_nibbles(&mut self) {
+        self.nibbles.retain(|_, node| !node.is_none());
+        for node in self.nibbles.values_mut() {
+            if let Node::Fork { fork, hash } = node {
+                if fork.next.dirty {
+                    Arc::make_mut(&mut fork.next).hash_nibbles();
+                    *hash = fork.get_hash();
+                }
+            }
+        }
+        self.cache_levels(merkle_leaves::<H>(self.slot_hashes()));
+    }
+
+    /// Caches the Merkle tree over the slot `hashes`, see `merkle_leaves`,
+    /// and its root as the hash of this `Branch`, which is then clean. The
+    /// root is the one `Hasher::merkle_root` folds `hashes` into.
+    fn cache_levels(&mut self, hashes: Vec<RootHash<H>>) {
+        self.levels = merkle_levels::<H>(hashes);
+        self.hash = self.levels.last().map_or_else(H::zero, |root| root[0]);
+        self.dirty = false;
+    }
+
+    /// Returns true if `levels` holds the Merkle tree the hash of this
+    /// `Branch` was computed from, i.e. it is clean and wasn't deserialized
+    /// since it was last hashed.
+    fn levels_cached(&self) -> bool {
+        let width = match H::none_hash_policy() {
+            NoneHashPolicy::ZeroPlaceholder if !self.nibbles.is_empty() => usize::from(Nibble::MAX) + 1,
+            _ => self.nibbles.len(),
+        };
+        !self.dirty && self.levels.first().map_or(0, Vec::len) == width
+    }
+
+    /// Returns each occupied `Nibble` in order along with the hash its node
+    /// contributes to the hash of the `Branch`, see `slot_hash`.
+    fn slot_hashes(&self) -> Vec<(Nibble, RootHash<H>)> {
+        self.nibbles
+            .iter()
+            .filter_map(|(nibble, node)| Some((*nibble, slot_hash::<H>(*nibble, &node.get_hash()?))))
+            .collect()
+    }
+
+    /// Collects the `ProofStep`s from this `Branch` down to the `Branch`
+    /// holding the `Leaf` at `address`, deepest first. Returns `None` if there
+    /// is no `Leaf` at `address`.
+    pub(crate) fn prove(&self, address: &Address<N>) -> Option<Vec<ProofStep<H>>> {
+        let layer: u8 = self.layer.clone().into();
+        let nibble = nibble_at(address, layer as usize);
+        let mut steps = match self.slot(nibble) {
+            Node::Data { data, .. } if data.address == *address => vec![],
+            Node::Fork { fork, .. } => fork.next.prove(address)?,
+            _ => return None,
+        };
+        steps.push(ProofStep {
+            nibble,
+            siblings: self.siblings_of(nibble)?,
+        });
+        Some(steps)
+    }
+
+    /// Collects the `ProofStep`s from this `Branch` down to the `Branch` in
+    /// which the path of `address` diverges from the `Trie`, deepest first,
+    /// along with how it diverges there. Returns `None` if there is a `Leaf`
+    /// at `address`.
+    pub(crate) fn prove_absence(&self, address: &Address<N>) -> Option<(Vec<ProofStep<H>>, Divergence<H, N>)> {
+        let layer: u8 = self.layer.clone().into();
+        let nibble = nibble_at(address, layer as usize);
+        match self.slot(nibble) {
+            Node::None => {
+                let occupied = self
+                    .nibbles
+                    .iter()
+                    .filter_map(|(nibble, node)| Some((*nibble, node.get_hash()?)))
+                    .collect();
+                Some((vec![], Divergence::Empty { occupied }))
+            }
+            Node::Data { data, .. } if data.address == *address => None,
+            Node::Data { data, .. } => {
+                let divergence = Divergence::Leaf {
+                    address: data.address,
+                    payload: data.payload.clone().into(),
+                    siblings: self.siblings_of(nibble)?,
+                };
+                Some((vec![], divergence))
+            }
+            Node::Fork { fork, .. } => {
+                let (mut steps, divergence) = fork.next.prove_absence(address)?;
+                steps.push(ProofStep {
+                    nibble,
+                    siblings: self.siblings_of(nibble)?,
+                });
+                Some((steps, divergence))
+            }
+        }
+    }
+
+    /// Collects the slots of this `Branch` for a `RangeProof` over
+    /// `[start, end)`, `path` being the prefix shared by the addresses
+    /// beneath it. Slots outside the range are held by their hash, the others
+    /// by their `Leaf`, or by the slots of their `Fork`'s `Branch`, recursively.
+    pub(crate) fn prove_range(&self, path: &mut Vec<u8>, start: &Address<N>, end: &Address<N>) -> Vec<(Nibble, RangeNode<H, N>)> {
+        let mut slots = vec![];
+        for (nibble, node) in self.nibbles.iter() {
+            let hash = match node.get_hash() {
+                Some(hash) => hash,
+                None => continue,
+            };
+            path.push(*nibble);
+            let slot = match node {
+                _ if outside_range(path, start, end) => RangeNode::Hash(hash),
+                Node::Data { data, .. } => RangeNode::Leaf {
+                    address: data.address,
+                    payload: data.payload.clone().into(),
+                },
+                Node::Fork { fork, .. } => RangeNode::Fork(fork.next.prove_range(path, start, end)),
+                Node::None => unreachable!("unoccupied slots have no hash"),
+            };
+            path.pop();
+            slots.push((*nibble, slot));
+        }
+        slots
+    }
+
+    /// Appends the binary encoding of this `Branch` to `out`: the number of
+    /// occupied `Nibble`s as a big endian `u16`, then for each one, in order,
+    /// the `Nibble`, a node type tag and the node. A `Leaf` is written as its
+    /// address followed by its serialized payload, prefixed with its length as
+    /// a big endian `u32`, and a `Fork` as its `Branch`, recursively.
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        out.extend((self.occupied() as u16).to_be_bytes());
+        for (nibble, node) in self.nibbles.iter() {
+            match node {
+                Node::Data { data, .. } => {
+                    let payload: Vec<u8> = data.payload.clone().into();
+                    out.push(*nibble);
+                    out.push(DATA_TAG);
+                    out.extend(data.address);
+                    out.extend((payload.len() as u32).to_be_bytes());
+                    out.extend(payload);
+                }
+                Node::Fork { fork, .. } => {
+                    out.push(*nibble);
+                    out.push(FORK_TAG);
+                    fork.next.encode(out);
+                }
+                Node::None => {}
+            }
+        }
+    }
+
+    /// Returns the number of bytes `encode` appends for this `Branch`: the
+    /// count of occupied slots, then for each the `Nibble` and tag, followed
+    /// by the address and length prefixed payload of a `Leaf`, or the
+    /// encoding of the `Branch` of a `Fork`.
+    pub(crate) fn encoded_len(&self) -> usize {
+        2 + self
+            .nibbles
+            .values()
+            .map(|node| match node {
+                Node::Data { data, .. } => {
+                    let payload: Vec<u8> = data.payload.clone().into();
+                    2 + N + 4 + payload.len()
+                }
+                Node::Fork { fork, .. } => 2 + fork.next.encoded_len(),
+                Node::None => 0,
+            })
+            .sum::<usize>()
+    }
+
+    /// Decodes a `Branch` written by `encode` from the front of `bytes`,
+    /// where `path` holds the `Nibble`s leading to it, which also gives its
+    /// `Layer`. Every hash is recomputed rather than read.
+    pub(crate) fn decode(bytes: &mut &[u8], path: &[Nibble]) -> Result<Branch<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let layer = path.len();
+        let count = u16::from_be_bytes(take(bytes, 2)?.try_into().unwrap());
+        let mut branch = Branch::with_hasher(Layer::try_from(layer).map_err(|_| DecodeError::InvalidBranch)?);
+        let mut previous: Option<Nibble> = None;
+        for _ in 0..count {
+            let nibble = take(bytes, 1)?[0];
+            if previous.is_some_and(|previous| previous >= nibble) {
+                return Err(DecodeError::InvalidBranch);
+            }
+            previous = Some(nibble);
+            let node = match take(bytes, 1)?[0] {
+                DATA_TAG => {
+                    let address = take(bytes, N)?;
+                    let len = u32::from_be_bytes(take(bytes, 4)?.try_into().unwrap());
+                    let data = Leaf::decode(address, take(bytes, len as usize)?, path, nibble)?;
+                    let hash = data.get_hash();
+                    Node::Data { data, hash }
+                }
+                FORK_TAG => {
+                    if layer + 1 >= N {
+                        return Err(DecodeError::InvalidBranch);
+                    }
+                    let mut next_path = path.to_vec();
+                    next_path.push(nibble);
+                    let fork = Fork {
+                        nibble,
+                        next: Arc::new(Branch::decode(bytes, &next_path)?),
+                    };
+                    let hash = fork.get_hash();
+                    Node::Fork { fork, hash }
+                }
+                tag => return Err(DecodeError::InvalidTag(tag)),
+            };
+            branch.nibbles.insert(nibble, node);
+        }
+        branch.hash_nibbles();
+        Ok(branch)
+    }
+
+    /// Appends the RLP encoding of this `Branch` to `out`, laid out like the
+    /// nodes of an Ethereum trie, but with a slot per byte rather than per
+    /// hex digit:
+    ///
+    /// * a `Branch` is a list of 256 slots, an empty string for each
+    ///   unoccupied one, without a value slot,
+    /// * a `Leaf` is a leaf node, `[0x20 ++ remainder, payload]`, where the
+    ///   remainder is the rest of its address past its `Nibble`,
+    /// * a `Fork` is an extension node with an empty shared path,
+    ///   `[0x00, branch]`, holding its `Branch` inline.
+    ///
+    /// Hashes aren't written, `decode_rlp` recomputes them.
+    #[cfg(feature = "rlp")]
+    pub(crate) fn encode_rlp(&self, out: &mut Vec<u8>) {
+        let layer: u8 = self.layer.clone().into();
+        let mut slots = vec![];
+        for nibble in 0..=Nibble::MAX {
+            match self.slot(nibble) {
+                Node::Data { data, .. } => {
+                    let mut path = vec![RLP_LEAF_PREFIX];
+                    path.extend_from_slice(&data.address[layer as usize + 1..]);
+                    let mut leaf = vec![];
+                    rlp::encode_bytes(&path, &mut leaf);
+                    rlp::encode_bytes(&data.payload.clone().into(), &mut leaf);
+                    rlp::encode_list(&leaf, &mut slots);
+                }
+                Node::Fork { fork, .. } => {
+                    let mut extension = vec![];
+                    rlp::encode_bytes(&[RLP_EXTENSION_PREFIX], &mut extension);
+                    fork.next.encode_rlp(&mut extension);
+                    rlp::encode_list(&extension, &mut slots);
+                }
+                Node::None => rlp::encode_bytes(&[], &mut slots),
+            }
+        }
+        rlp::encode_list(&slots, out);
+    }
+
+    /// Decodes a `Branch` written by `encode_rlp` from its RLP `item`, where
+    /// `path` holds the `Nibble`s leading to it, which also gives its
+    /// `Layer`. Every hash is recomputed rather than read.
+    #[cfg(feature = "rlp")]
+    pub(crate) fn decode_rlp(item: &rlp::Item<'_>, path: &[Nibble]) -> Result<Branch<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let layer = path.len();
+        let slots = match item {
+            rlp::Item::List(slots) if slots.len() == Nibble::MAX as usize + 1 => slots,
+            _ => return Err(DecodeError::InvalidBranch),
+        };
+        let mut branch = Branch::with_hasher(Layer::try_from(layer).map_err(|_| DecodeError::InvalidBranch)?);
+        for (nibble, slot) in (0..=Nibble::MAX).zip(slots) {
+            let node = match slot {
+                rlp::Item::Bytes([]) => continue,
+                rlp::Item::List(node) => match node.as_slice() {
+                    [rlp::Item::Bytes([RLP_LEAF_PREFIX, remainder @ ..]), rlp::Item::Bytes(payload)] => {
+                        let mut address = path.to_vec();
+                        address.push(nibble);
+                        address.extend_from_slice(remainder);
+                        if address.len() != N {
+                            return Err(DecodeError::InvalidPath);
+                        }
+                        let data = Leaf::decode(&address, payload, path, nibble)?;
+                        let hash = data.get_hash();
+                        Node::Data { data, hash }
+                    }
+                    [rlp::Item::Bytes([RLP_EXTENSION_PREFIX]), next] => {
+                        if layer + 1 >= N {
+                            return Err(DecodeError::InvalidBranch);
+                        }
+                        let mut next_path = path.to_vec();
+                        next_path.push(nibble);
+                        let fork = Fork {
+                            nibble,
+                            next: Arc::new(Branch::decode_rlp(next, &next_path)?),
+                        };
+                        let hash = fork.get_hash();
+                        Node::Fork { fork, hash }
+                    }
+                    _ => return Err(DecodeError::InvalidBranch),
+                },
+                _ => return Err(DecodeError::InvalidBranch),
+            };
+            branch.nibbles.insert(nibble, node);
+        }
+        branch.hash_nibbles();
+        Ok(branch)
+    }
+
+    /// Writes every node beneath this `Branch` to `store`, followed by the
+    /// `Branch` itself, each keyed by its hash. A `Leaf` is stored as its
+    /// address followed by its serialized payload, a `Fork` as the hash of its
+    /// `Branch`, and a `Branch` as the `Nibble`, node type tag and hash of
+    /// each occupied slot, in order.
+    pub(crate) fn commit<S: NodeStore<H>>(&self, store: &mut S) {
+        let mut record = vec![];
+        for (nibble, node) in self.nibbles.iter() {
+            let hash = match node.get_hash() {
+                Some(hash) => hash,
+                None => continue,
+            };
+            let tag = match node {
+                Node::Data { data, .. } => {
+                    let mut leaf = data.address.to_vec();
+                    leaf.extend(data.payload.clone().into());
+                    store.put(hash, leaf);
+                    DATA_TAG
+                }
+                Node::Fork { fork, .. } => {
+                    fork.next.commit(store);
+                    store.put(hash, fork.next.get_hash().as_ref().to_vec());
+                    FORK_TAG
+                }
+                Node::None => continue,
+            };
+            record.push(*nibble);
+            record.push(tag);
+            record.extend_from_slice(hash.as_ref());
+        }
+        store.put(self.get_hash(), record);
+    }
+
+    /// Rebuilds the `Branch` stored under `hash` in `store` by `commit`,
+    /// where `path` holds the `Nibble`s leading to it, checking every node
+    /// against the hash it is stored under.
+    pub(crate) fn load<S: NodeStore<H>>(hash: &RootHash<H>, store: &S, path: &[Nibble]) -> Result<Branch<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let layer = path.len();
+        let mut branch = Branch::with_hasher(Layer::try_from(layer).map_err(|_| DecodeError::InvalidBranch)?);
+        for (nibble, tag, child) in load_slots::<H, S>(hash, store)? {
+            let node = match tag {
+                DATA_TAG => {
+                    let leaf = store.get(&child).ok_or(DecodeError::MissingNode)?;
+                    let (address, payload) = leaf.split_at(leaf.len().min(N));
+                    let data = Leaf::decode(address, payload, path, nibble)?;
+                    let hash = data.get_hash();
+                    Node::Data { data, hash }
+                }
+                FORK_TAG => {
+                    if layer + 1 >= N {
+                        return Err(DecodeError::InvalidBranch);
+                    }
+                    let mut next_path = path.to_vec();
+                    next_path.push(nibble);
+                    let next = load_hash::<H, S>(&child, store)?;
+                    let fork = Fork {
+                        nibble,
+                        next: Arc::new(Branch::load(&next, store, &next_path)?),
+                    };
+                    let hash = fork.get_hash();
+                    Node::Fork { fork, hash }
+                }
+                tag => return Err(DecodeError::InvalidTag(tag)),
+            };
+            if node.get_hash() != Some(child) {
+                return Err(DecodeError::HashMismatch);
+            }
+            branch.nibbles.insert(nibble, node);
+        }
+        branch.hash_nibbles();
+        if branch.hash != *hash {
+            return Err(DecodeError::HashMismatch);
+        }
+        Ok(branch)
+    }
+
+    /// Follows `address` down from the `Branch` stored under `hash` in
+    /// `store` by `commit`, reading only the records on its path and checking
+    /// each against the hash it is stored under. `step` is called for every
+    /// `Branch` on the way, top down, with the levels of the Merkle tree over
+    /// its slots and the index of the slot the path takes. Returns the `Leaf`
+    /// at `address`, `Ok(None)` if the path ends in an unoccupied `Nibble` or
+    /// another `Leaf`, and a `DecodeError` if a record is missing or tampered.
+    fn load_path<S, F>(hash: &RootHash<H>, store: &S, address: &Address<N>, mut step: F) -> Result<Option<Leaf<P, H, N>>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+        S: NodeStore<H>,
+        F: FnMut(&[Vec<RootHash<H>>], usize),
+    {
+        let mut hash = *hash;
+        for layer in 0..N {
+            let slots = load_slots::<H, S>(&hash, store)?;
+            let levels = verify_slots::<H>(&slots, &hash)?;
+            let nibble = nibble_at(address, layer);
+            let index = match slots.iter().position(|(occupied, _, _)| *occupied == nibble) {
+                Some(index) => index,
+                None => return Ok(None),
+            };
+            step(&levels, merkle_index::<H>(index, nibble));
+            let (_, tag, child) = &slots[index];
+            match *tag {
+                DATA_TAG => {
+                    let leaf = store.get(child).ok_or(DecodeError::MissingNode)?;
+                    let (stored, payload) = leaf.split_at(leaf.len().min(N));
+                    let data = Leaf::decode(stored, payload, &address[..layer], nibble)?;
+                    if data.get_hash() != *child {
+                        return Err(DecodeError::HashMismatch);
+                    }
+                    return Ok(Some(data).filter(|data| data.address == *address));
+                }
+                FORK_TAG => hash = load_hash::<H, S>(child, store)?,
+                tag => return Err(DecodeError::InvalidTag(tag)),
+            }
+        }
+        Err(DecodeError::InvalidBranch)
+    }
+
+    /// Looks up the `Leaf` at `address` beneath the `Branch` stored under
+    /// `hash` in `store` by `commit`, see `load_path`.
+    pub(crate) fn load_leaf<S: NodeStore<H>>(hash: &RootHash<H>, store: &S, address: &Address<N>) -> Result<Option<Leaf<P, H, N>>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Self::load_path(hash, store, address, |_, _| {})
+    }
+
+    /// Collects the `ProofStep`s for the `Leaf` at `address` from the
+    /// `Branch` stored under `hash` in `store` by `commit` down, top down,
+    /// reading only the records on its path, see `load_path`. Returns
+    /// `Ok(None)` if there is no `Leaf` at `address`.
+    pub(crate) fn load_proof<S: NodeStore<H>>(hash: &RootHash<H>, store: &S, address: &Address<N>) -> Result<Option<Vec<ProofStep<H>>>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let mut steps = vec![];
+        let leaf = Self::load_path(hash, store, address, |levels, index| {
+            steps.push(ProofStep {
+                nibble: nibble_at(address, steps.len()),
+                siblings: merkle_path::<H>(levels, index),
+            });
+        })?;
+        Ok(leaf.map(|_| steps))
+    }
+
+    /// Returns the `Sibling` hashes folding the hash of the node at `nibble`
+    /// up to the hash of this `Branch`, or `None` if `nibble` is unoccupied.
+    /// They are read from the cached `levels` unless the `Branch` is dirty,
+    /// so proving against a clean `Trie` hashes nothing.
+    fn siblings_of(&self, nibble: Nibble) -> Option<Vec<Sibling<H>>> {
+        if self.levels_cached() {
+            let index = self.nibbles.keys().position(|occupied| *occupied == nibble)?;
+            return Some(merkle_path::<H>(&self.levels, merkle_index::<H>(index, nibble)));
+        }
+        let slots = self.slot_hashes();
+        let index = slots.iter().position(|(occupied, _)| *occupied == nibble)?;
+        Some(merkle_path::<H>(&merkle_levels::<H>(merkle_leaves::<H>(slots)), merkle_index::<H>(index, nibble)))
+    }
+
+    /// Builds a `Branch` with the same shape whose `Leaf` payloads are
+    /// transformed by `f`, recomputing every hash along the way.
+    pub(crate) fn map_payloads<Q, F>(&self, f: &F) -> Branch<Q, H, N>
+    where
+        Q: Clone + Debug + Into<Vec<u8>>,
+        F: Fn(&P) -> Q,
+    {
+        let nibbles = self
+            .nibbles
+            .iter()
+            .map(|(nibble, node)| match node {
+                Node::Data { data, .. } => {
+                    let data = Leaf {
+                        nibble: data.nibble,
+                        address: data.address,
+                        remainder: data.remainder.clone(),
+                        payload: f(&data.payload),
+                        hasher: PhantomData,
+                    };
+                    let hash = data.get_hash();
+                    (*nibble, Node::Data { data, hash })
+                }
+                Node::Fork { fork, .. } => {
+                    let fork = Fork {
+                        nibble: fork.nibble,
+                        next: Arc::new(fork.next.map_payloads(f)),
+                    };
+                    let hash = fork.get_hash();
+                    (*nibble, Node::Fork { fork, hash })
+                }
+                Node::None => (*nibble, Node::None),
+            })
+            .collect();
+
+        let mut branch = Branch {
+            layer: self.layer.clone(),
+            nibbles,
+            hash: H::zero(),
+            dirty: false,
+            levels: Vec::new(),
+        };
+        branch.hash_nibbles();
+        branch
+    }
+
+    /// Searches this `Branch` and every node beneath it for a node whose
+    /// hash is `hash`, returning the first match in depth first order. Hashes
+    /// are read through `get_hash`, so dirty nodes are found by the hash they
+    /// will have once rehashed.
+    pub(crate) fn find_by_hash(&self, hash: &RootHash<H>) -> Option<NodeRef<'_, P, H, N>> {
+        if &self.get_hash() == hash {
+            return Some(NodeRef::Branch(self));
+        }
+        self.nibbles.values().find_map(|node| {
+            let matches = node.get_hash().as_ref() == Some(hash);
+            match node {
+                Node::Data { data, .. } if matches => Some(NodeRef::Leaf(data)),
+                Node::Fork { fork, .. } if matches => Some(NodeRef::Fork(fork)),
+                Node::Fork { fork, .. } => fork.next.find_by_hash(hash),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns true if `other` has the same `Layer` and the same nodes at the
+    /// same `Nibble`s as this `Branch`, all the way down, comparing addresses
+    /// and serialized payloads of the leaves rather than any cached hash.
+    pub(crate) fn structural_eq(&self, other: &Branch<P, H, N>) -> bool {
+        self.layer.clone() as u8 == other.layer.clone() as u8
+            && self.occupied() == other.occupied()
+            && self.occupied_nodes().zip(other.occupied_nodes()).all(|(ours, theirs)| {
+                ours.0 == theirs.0
+                    && match (ours.1, theirs.1) {
+                        (Node::Data { data, .. }, Node::Data { data: other, .. }) => {
+                            data.nibble == other.nibble
+                                && data.address == other.address
+                                && data.remainder == other.remainder
+                                && data.payload.clone().into() == other.payload.clone().into()
+                        }
+                        (Node::Fork { fork, .. }, Node::Fork { fork: other, .. }) => {
+                            fork.nibble == other.nibble && fork.next.structural_eq(&other.next)
+                        }
+                        _ => false,
+                    }
+            })
+    }
+
+    /// Follows `prefix` from this `Branch`, returning the `Branch` at which it
+    /// runs out, or the `Leaf` the path runs into first if its address starts
+    /// with `prefix`. Returns `None` if no `Leaf` beneath this `Branch` has an
+    /// address starting with `prefix`.
+    pub(crate) fn find_prefix(&self, prefix: &[u8]) -> Option<NodeRef<'_, P, H, N>> {
+        let layer: u8 = self.layer.clone().into();
+        let nibble = match prefix.get(layer as usize) {
+            Some(nibble) => *nibble,
+            None => return Some(NodeRef::Branch(self)),
+        };
+        match self.slot(nibble) {
+            Node::Data { data, .. } if data.address.starts_with(prefix) => Some(NodeRef::Leaf(data)),
+            Node::Fork { fork, .. } => fork.next.find_prefix(prefix),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of leaves in this `Branch` and every `Fork`
+    /// beneath it.
+    pub(crate) fn leaf_count(&self) -> usize {
+        self.nibbles
+            .values()
+            .map(|node| match node {
+                Node::Data { .. } => 1,
+                Node::Fork { fork, .. } => fork.next.leaf_count(),
+                Node::None => 0,
+            })
+            .sum()
+    }
+
+    /// Returns the number of `Branch` nodes on the longest path from this
+    /// `Branch` down to a `Leaf`, `0` if there are no leaves beneath it.
+    pub(crate) fn max_depth(&self) -> usize {
+        self.nibbles
+            .values()
+            .map(|node| match node {
+                Node::Data { .. } => 1,
+                Node::Fork { fork, .. } => 1 + fork.next.max_depth(),
+                Node::None => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the address of the first `Leaf` beneath this `Branch` that sits
+    /// deeper than `limit`, given that this `Branch` is at `depth`.
+    pub(crate) fn find_deeper_than(&self, limit: usize, depth: usize) -> Option<Address<N>> {
+        self.nibbles.values().find_map(|node| match node {
+            Node::Data { data, .. } if depth > limit => Some(data.get_address()),
+            Node::Fork { fork, .. } => fork.next.find_deeper_than(limit, depth + 1),
+            _ => None,
+        })
+    }
+
+    /// Writes a line per occupied `Nibble` of this `Branch` to `f`, indented
+    /// by `depth`, each followed by the lines of the `Branch` beneath it if
+    /// it holds a `Fork`.
+    pub(crate) fn fmt_tree(&self, f: &mut core::fmt::Formatter, depth: usize) -> core::fmt::Result {
+        let indent = "  ".repeat(depth);
+        for (nibble, node) in self.nibbles.iter() {
+            let hash = match node.get_hash() {
+                Some(hash) => hash,
+                None => continue,
+            };
+            match node {
+                Node::Data { data, .. } => writeln!(
+                    f,
+                    "{}{:02x} leaf {} {}",
+                    indent,
+                    nibble,
+                    to_hex(&data.address),
+                    to_hex(hash.as_ref())
+                )?,
+                Node::Fork { fork, .. } => {
+                    writeln!(f, "{}{:02x} fork {}", indent, nibble, to_hex(hash.as_ref()))?;
+                    fork.next.fmt_tree(f, depth + 1)?;
+                }
+                Node::None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends to `out` the Graphviz DOT statements for this `Branch`, named
+    /// `id`, and for every occupied `Nibble` beneath it: a node labeled with
+    /// the `Nibble` and abbreviated hash of each `Leaf` and `Fork`, and an
+    /// edge to it from its `Branch`, as well as to the `Branch` of each `Fork`.
+    pub(crate) fn write_dot(&self, id: &str, out: &mut String) {
+        let layer: u8 = self.layer.clone().into();
+        out.push_str(&format!(
+            "  {} [shape=box, label=\"branch {}\\n{}\"];\n",
+            id,
+            layer,
+            abbreviate(self.get_hash().as_ref())
+        ));
+        for (nibble, node) in self.nibbles.iter() {
+            let hash = match node.get_hash() {
+                Some(hash) => hash,
+                None => continue,
+            };
+            let slot = format!("{}_{:02x}", id, nibble);
+            match node {
+                Node::Data { data, .. } => out.push_str(&format!(
+                    "  {} [shape=ellipse, label=\"{:02x} leaf {}\\n{}\"];\n",
+                    slot,
+                    nibble,
+                    abbreviate(&data.address),
+                    abbreviate(hash.as_ref())
+                )),
+                Node::Fork { fork, .. } => {
+                    let next = format!("{}_b", slot);
+                    out.push_str(&format!(
+                        "  {} [shape=diamond, label=\"{:02x} fork\\n{}\"];\n",
+                        slot,
+                        nibble,
+                        abbreviate(hash.as_ref())
+                    ));
+                    fork.next.write_dot(&next, out);
+                    out.push_str(&format!("  {} -> {};\n", slot, next));
+                }
+                Node::None => continue,
+            }
+            out.push_str(&format!("  {} -> {};\n", id, slot));
+        }
+    }
+
+    /// Returns true if every hash cached in this `Branch` and beneath it
+    /// matches the one recomputed from its contents, checking the children of
+    /// each node before the node itself. A dirty `Branch` has no cached hash
+    /// yet, nor has a `Fork` onto one, so only their nodes are checked.
+    pub(crate) fn verify_hashes(&self) -> bool {
+        let children = self.nibbles.values().all(|node| match node {
+            Node::Data { data, hash } => data.get_hash() == *hash,
+            Node::Fork { fork, hash } => fork.next.verify_hashes() && (fork.next.dirty || fork.get_hash() == *hash),
+            Node::None => true,
+        });
+        children && (self.dirty || H::merkle_root(&merkle_leaves::<H>(self.slot_hashes())) == self.hash)
+    }
+
+    /// Recomputes every hash cached in this `Branch` and beneath it from
+    /// their contents, bottom up.
+    pub(crate) fn recompute_hashes(&mut self) {
+        for node in self.nibbles.values_mut() {
+            match node {
+                Node::Data { data, hash } => *hash = data.get_hash(),
+                Node::Fork { fork, hash } => {
+                    Arc::make_mut(&mut fork.next).recompute_hashes();
+                    *hash = fork.get_hash();
+                }
+                Node::None => {}
+            }
+        }
+        self.hash_nibbles();
+    }
+
+    /// Returns true if any `Fork` beneath this `Branch` wraps a `Branch` that
+    /// is empty, or whose only occupied `Nibble` is a `Node::Data` that should
+    /// have been promoted in place of the `Fork`. A single `Node::Fork` child
+    /// is fine, that is how a longer shared prefix is represented.
+    pub(crate) fn has_dangling_forks(&self) -> bool {
+        self.nibbles.values().any(|node| match node {
+            Node::Fork { fork, .. } => {
+                let mut occupied = fork.next.occupied_nodes().map(|(_, node)| node);
+                match (occupied.next(), occupied.next()) {
+                    (None, _) | (Some(Node::Data { .. }), None) => true,
+                    _ => fork.next.has_dangling_forks(),
+                }
+            }
+            _ => false,
+        })
+    }
+
+    /// Returns why this `Branch`, reached by the `Nibble`s of `path`, isn't
+    /// shaped the way inserting its leaves one at a time shapes it, if it
+    /// isn't, see `Trie::assert_canonical`. Nothing is hashed.
+    pub(crate) fn canonical_error(&self, path: &mut Vec<Nibble>) -> Option<String> {
+        let layer: u8 = self.layer.clone().into();
+        if layer as usize != path.len() {
+            return Some(format!("the Branch at {} is at layer {}", to_hex(path), layer));
+        }
+        for (nibble, node) in self.occupied_nodes() {
+            path.push(*nibble);
+            let error = match node {
+                Node::Data { data, .. } => (!data.address.starts_with(path)
+                    || data.nibble != *nibble
+                    || data.remainder != data.address[path.len()..])
+                    .then(|| format!("the Leaf {} sits off its path at {}", to_hex(&data.address), to_hex(path))),
+                Node::Fork { fork, .. } if fork.nibble != *nibble => {
+                    Some(format!("the Fork at {} holds the Nibble {}", to_hex(path), fork.nibble))
+                }
+                Node::Fork { fork, .. } => {
+                    let mut occupied = fork.next.occupied_nodes().map(|(_, node)| node);
+                    match (occupied.next(), occupied.next()) {
+                        (None, _) => Some(format!("the Fork at {} is empty", to_hex(path))),
+                        (Some(Node::Data { .. }), None) => Some(format!("the Fork at {} holds a single Leaf", to_hex(path))),
+                        _ => fork.next.canonical_error(path),
+                    }
+                }
+                Node::None => None,
+            };
+            path.pop();
+            if error.is_some() {
+                return error;
+            }
+        }
+        None
+    }
+
+    /// Counts the `Fork` nodes beneath this `Branch`, returning the total
+    /// along with how many of them wrap a `Branch` with a single occupied `Nibble`.
+    pub(crate) fn fork_counts(&self) -> (usize, usize) {
+        self.nibbles.values().fold((0, 0), |(total, single), node| match node {
+            Node::Fork { fork, .. } => {
+                let (inner_total, inner_single) = fork.next.fork_counts();
+                (
+                    total + inner_total + 1,
+                    single + inner_single + usize::from(fork.next.occupied() == 1),
+                )
+            }
+            _ => (total, single),
+        })
+    }
+}
+
+/// Parallel counterparts of `Branch::rehash` and `Branch::hash_nibbles`,
+/// available with the `rayon` feature.
+#[cfg(feature = "rayon")]
+impl<P, H, const N: usize> Branch<P, H, N>
+where
+    P: Clone + Debug + Into<Vec<u8>> + Send + Sync,
+    H: Hasher + Send + Sync,
+    RootHash<H>: Send + Sync,
+{
+    /// Same as `insert_many`, but builds the nodes of the `Nibble`s gaining a
+    /// `Leaf`, every `Branch` beneath them included, in parallel. Each
+    /// `Nibble` roots an independent subtree, so the result is identical.
+    pub(crate) fn par_insert_many(&mut self, leaves: Vec<Leaf<P, H, N>>) {
+        let layer: u8 = self.layer.clone().into();
+        let layer = layer as usize;
+        let groups: Vec<_> = group_by_nibble(leaves, layer)
+            .into_iter()
+            .map(|(nibble, group)| (nibble, self.nibbles.remove(&nibble), group))
+            .collect();
+        let nodes: Vec<(Nibble, Node<P, H, N>)> = groups
+            .into_par_iter()
+            .map(|(nibble, existing, group)| (nibble, insert_group(existing, nibble, group, layer)))
+            .collect();
+        self.nibbles.extend(nodes);
+        self.par_hash_nibbles();
+    }
+
+    /// Same as `recompute_hashes`, but recomputes the hashes beneath each
+    /// `Nibble`, and then hashes the slots, in parallel, see
+    /// `par_hash_nibbles`. The hashes are identical.
+    pub(crate) fn par_recompute_hashes(&mut self) {
+        self.nibbles.par_iter_mut().for_each(|(_, node)| match node {
+            Node::Data { data, hash } => *hash = data.get_hash(),
+            Node::Fork { fork, hash } => {
+                Arc::make_mut(&mut fork.next).par_recompute_hashes();
+                *hash = fork.get_hash();
+            }
+            Node::None => {}
+        });
+        self.par_hash_nibbles();
+    }
+
+    /// Recomputes and caches the hash of this `Branch` if it is dirty, like
+    /// `rehash`, but hashes the dirty `Fork`s of each dirty `Branch` in
+    /// parallel, see `par_hash_nibbles`.
+    pub fn par_rehash(&mut self) {
+        if self.dirty {
+            self.par_hash_nibbles();
+        }
+    }
+
+    /// Same as `hash_nibbles`, but rehashes the dirty `Fork`s beneath this
+    /// `Branch`, and then hashes its slots, in parallel. The slot hashes are
+    /// collected in `Nibble` order, so the hash is identical to the one
+    /// `hash_nibbles` computes.
+    pub fn par_hash_nibbles(&mut self) {
+        self.nibbles.retain(|_, node| !node.is_none());
+        self.nibbles.par_iter_mut().for_each(|(_, node)| {
+            if let Node::Fork { fork, hash } = node {
+                if fork.next.dirty {
+                    Arc::make_mut(&mut fork.next).par_hash_nibbles();
+                    *hash = fork.get_hash();
+                }
+            }
+        });
+        let hashes: Vec<(Nibble, RootHash<H>)> = self
+            .nibbles
+            .par_iter()
+            .filter_map(|(nibble, node)| Some((*nibble, slot_hash::<H>(*nibble, &node.get_hash()?))))
+            .collect();
+        self.cache_levels(merkle_leaves::<H>(hashes));
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<P, H, const N: usize> Root<P, H, N>
+where
+    P: Clone + Debug + Into<Vec<u8>> + Send + Sync,
+    H: Hasher + Send + Sync,
+    RootHash<H>: Send + Sync,
+{
+    /// Same as `rehash`, but hashes the dirty branches in parallel, see
+    /// `Branch::par_rehash`.
+    pub fn par_rehash(&mut self) {
+        if self.next.dirty {
+            Arc::make_mut(&mut self.next).par_rehash();
+        }
+        self.hash = H::hash(self.next.get_hash().as_ref());
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, const N: usize> Fork<P, Sha256Algorithm, N> {
+    /// Creates a new `Fork` given a shared `nibble` and the `layer` + 1
+    /// at which the shared `nibble` was discovered, so that a new
+    /// `Branch` with the conflicting `Leaf` nodes can be created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mmpt::node::Fork;
+    /// use mmpt::layer::Layer;
+    ///
+    /// let fork: Fork<String> = Fork::new(5, Layer::Two);
+    /// println!("{:?}", fork);
+    /// ```
+    pub fn new(nibble: Nibble, layer: Layer) -> Fork<P, Sha256Algorithm, N> {
+        Fork::with_hasher(nibble, layer)
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Fork<P, H, N> {
+    /// Creates a new `Fork` whose `Branch` is hashed with `H`, see `new`.
+    pub fn with_hasher(nibble: Nibble, layer: Layer) -> Fork<P, H, N> {
+        Fork {
+            nibble,
+            next: Arc::new(Branch::with_hasher(layer)),
+        }
+    }
+
+    /// Returns a clone of the `Branch` underpinning this `Fork`. The
+    /// branches of its own forks are shared rather than copied, see
+    /// `get_next_ref` to borrow it instead.
+    pub fn get_next(&self) -> Branch<P, H, N> {
+        Branch::clone(&self.next)
+    }
+
+    /// Returns a reference to the `Branch` underpinning this `Fork`
+    pub fn get_next_ref(&self) -> &Branch<P, H, N> {
+        &self.next
+    }
+
+    /// Returns the hash of the `Branch` underpinning this `Fork`
+    /// hash.
+    pub fn get_hash(&self) -> RootHash<H> {
+        H::hash(self.next.get_hash().as_ref())
+    }
+
+    /// Inserts a leaf into the `Branch` in the `Fork`.
+    pub fn insert(&mut self, leaf: Leaf<P, H, N>) {
+        Arc::make_mut(&mut self.next).insert(leaf);
+    }
+
+    /// Get the node at the index in the `Fork` `Branch`
+    pub fn get(&self, index: &u8) -> Node<P, H, N> {
+        self.next.get(index)
+    }
+
+    /// Returns a reference to the node at `index` in the `Fork` `Branch`.
+    pub fn get_ref(&self, index: &u8) -> &Node<P, H, N> {
+        self.next.get_ref(index)
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, const N: usize> Leaf<P, Sha256Algorithm, N> {
+    /// Returns a new `Leaf` node given an `address`
+    /// and a `payload`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::node::Leaf;
+    ///
+    /// let address = [0u8; 32];
+    /// let payload = "Some Data".to_string();
+    ///
+    /// let leaf: Leaf<String> = Leaf::new(address, payload);
+    ///
+    /// println!("{:?}", leaf.get_payload());
+    /// println!("{:?}", leaf.get_hash());
+    /// ```
+    pub fn new(address: Address<N>, payload: P) -> Leaf<P, Sha256Algorithm, N> {
+        Leaf::with_hasher(address, payload)
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Leaf<P, H, N> {
+    /// Returns a new `Leaf` node hashed with `H`, see `new`.
+    pub fn with_hasher(address: Address<N>, payload: P) -> Leaf<P, H, N> {
+        let nibble = nibble_at(&address, 0);
+        let remainder = address[1..].to_vec();
+        let payload = payload;
+
+        Leaf {
+            nibble,
+            address,
+            remainder,
+            payload,
+            hasher: PhantomData,
+        }
+    }
+
+    /// Returns the payload for the current leaf
+    pub fn get_payload(&self) -> P {
+        self.payload.clone()
+    }
+
+    /// Returns a reference to the payload for the current leaf
+    pub(crate) fn payload_ref(&self) -> &P {
+        &self.payload
+    }
+
+    /// Returns the `Nibble` the leaf sits at in its `Branch`, i.e. the byte
+    /// of the address at the `Layer` of that `Branch`.
+    pub fn get_nibble(&self) -> Nibble {
+        self.nibble
+    }
+
+    /// Returns the part of the address following the `Nibble` of the leaf,
+    /// i.e. the bytes below the `Branch` it sits in.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut address = [0u8; 32];
+    /// address[1] = 1;
+    /// let leaf: Leaf<String> = Leaf::new(address, "Some Data".to_string());
+    ///
+    /// assert_eq!(leaf.get_nibble(), 0);
+    /// assert_eq!(leaf.get_remainder(), &address[1..]);
+    /// ```
+    pub fn get_remainder(&self) -> &[u8] {
+        &self.remainder
+    }
+
+    pub fn get_address(&self) -> Address<N> {
+        self.address
+    }
+
+    /// Returns the full sequence of `Nibble` values that define the path
+    /// to this `Leaf`, i.e. the `Nibble` taken at each `Layer` starting from
+    /// the `Root`. Branches are indexed by whole bytes, so this is each byte
+    /// of the address in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut address = [0u8; 32];
+    /// address[1] = 7;
+    /// let leaf: Leaf<String> = Leaf::new(address, "Some Data".to_string());
+    /// assert_eq!(leaf.address_nibbles()[..3], [0, 7, 0]);
+    /// ```
+    pub fn address_nibbles(&self) -> Vec<Nibble> {
+        path_of(&self.address)
+    }
+
+    /// Returns the hash of the current leaf
+    pub fn get_hash(&self) -> RootHash<H> {
         self.hash()
     }
 
+    /// Rebuilds a decoded `Leaf` sitting at `nibble` in the `Branch` reached
+    /// by `path`, checking its `address` follows that path.
+    pub(crate) fn decode(address: &[u8], payload: &[u8], path: &[Nibble], nibble: Nibble) -> Result<Leaf<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let layer = path.len();
+        let address: Address<N> = address.try_into().map_err(|_| DecodeError::UnexpectedEnd)?;
+        if address[..layer] != *path || nibble_at(&address, layer) != nibble {
+            return Err(DecodeError::InvalidPath);
+        }
+        let payload = P::try_from(payload.to_vec()).map_err(|_| DecodeError::InvalidPayload)?;
+        Ok(Leaf::with_hasher(address, payload).at_layer(layer))
+    }
+
+    /// Re-derives the `nibble` and `remainder` of the `Leaf` for a `Branch`
+    /// sitting at `layer`, i.e. `nibble` becomes `address[layer]`.
+    pub(crate) fn at_layer(self, layer: usize) -> Leaf<P, H, N> {
+        Leaf {
+            nibble: nibble_at(&self.address, layer),
+            remainder: self.address[layer + 1..].to_vec(),
+            ..self
+        }
+    }
+
     /// Hashes the serialized payload of the current leaf.
-    fn hash(&self) -> RootHash {
+    fn hash(&self) -> RootHash<H> {
         let mut to_hash = vec![];
         to_hash.extend(self.address);
         to_hash.extend(&self.payload.clone().into());
-        Sha256Algorithm::hash(&to_hash)
+        H::hash(&to_hash)
     }
 }
 
 /// Converts two `Leaf` nodes with a shared nibble at a given layer, into a `Fork` with a new `Branch`
 /// with the two `Leaf` nodes inserted into the new `Branch`. If another shared `Nibble` exists,
 /// the `branch.insert()` method recursively keeps adding new `Fork` nodes and `Branch` nodes
-/// until a unique nibble is found.
-impl<P: Clone + Debug + Into<Vec<u8>>> From<(Leaf<P>, Leaf<P>, usize)> for Fork<P> {
+/// until a unique nibble is found, which at the latest is the last `Nibble` of the addresses.
+/// Two `Leaf` nodes with the same address never fork, the second one overwrites the first.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> TryFrom<(Leaf<P, H, N>, Leaf<P, H, N>, usize)> for Fork<P, H, N> {
+    type Error = InvalidBranchInsert;
+
     /// Takes two `Leaf` nodes w a shared `Nibble` and a `Layer`
-    /// (represented as a `u8`) and converts them to and returns a new
-    /// `Fork`
-    fn from(i: (Leaf<P>, Leaf<P>, usize)) -> Fork<P> {
+    /// (represented as a `usize`) and converts them to and returns a new
+    /// `Fork`. Fails if the `Layer` is the last one of the addresses, as
+    /// there is no `Nibble` left beneath it for the new `Branch`.
+    fn try_from(i: (Leaf<P, H, N>, Leaf<P, H, N>, usize)) -> Result<Fork<P, H, N>, InvalidBranchInsert> {
         let nibble = i.0.nibble;
-        let leaf_1 = Leaf {
-            nibble: i.0.remainder[0],
-            address: i.0.get_address(),
-            remainder: i.0.remainder[1..].to_vec(),
-            payload: i.0.payload,
-        };
-        let leaf_2 = Leaf {
-            nibble: i.1.remainder[0],
-            address: i.1.get_address(),
-            remainder: i.1.remainder[1..].to_vec(),
-            payload: i.1.payload,
-        };
-
         let layer = i.2 + 1;
-        let mut next: Box<Branch<P>> = Box::new(Branch::new(layer.into()));
-        let hash = [0u8; 32];
+        if layer >= N {
+            return Err(InvalidBranchInsert);
+        }
+        let next_layer = Layer::try_from(layer).map_err(|_| InvalidBranchInsert)?;
+        let leaf_1 = i.0.at_layer(layer);
+        let leaf_2 = i.1.at_layer(layer);
+
+        let next: Arc<Branch<P, H, N>> = Arc::new(Branch::with_hasher(next_layer));
 
         let mut fork = Fork { nibble, next };
         fork.insert(leaf_1);
         fork.insert(leaf_2);
 
-        fork
+        Ok(fork)
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Default for Root<P> {
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Default for Root<P, H, N> {
     /// Creates and returns a `Root` node. `Root` node is always
     /// the default, i.e. initialized with an empty `Branch`
     fn default() -> Self {
-        Self::new()
+        Self::with_hasher()
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Node<P> {
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Node<P, H, N> {
     /// If the `Node` variant is `Node::None` return true
     /// Otherwise return false
     pub fn is_none(&self) -> bool {
-        match self {
-            Node::None => true,
-            _ => false,
-        }
+        matches!(self, Node::None)
     }
 
     /// If the `Node` variant is `Node::Fork` return true
     /// otherwise, return false
     pub fn is_fork(&self) -> bool {
-        match self {
-            Node::Fork { .. } => true,
-            _ => false,
-        }
+        matches!(self, Node::Fork { .. })
     }
 
     /// If the `Node` variant is `Node::Data` return true
     /// otherwise return false
     pub fn is_data(&self) -> bool {
+        matches!(self, Node::Data { .. })
+    }
+
+    /// Returns a reference to the `Leaf` if the `Node` variant is
+    /// `Node::Data`, otherwise returns `None`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let node = trie.get(&0);
+    /// assert_eq!(node.as_data().map(|leaf| leaf.get_address()), Some([0u8; 32]));
+    /// assert!(node.as_fork().is_none());
+    /// ```
+    pub fn as_data(&self) -> Option<&Leaf<P, H, N>> {
         match self {
-            Node::Data { .. } => true,
-            _ => false,
+            Node::Data { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the `Fork` if the `Node` variant is
+    /// `Node::Fork`, otherwise returns `None`
+    pub fn as_fork(&self) -> Option<&Fork<P, H, N>> {
+        match self {
+            Node::Fork { fork, .. } => Some(fork),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Leaf` if the `Node` variant is `Node::Data`, otherwise
+    /// returns `None`
+    pub fn into_data(self) -> Option<Leaf<P, H, N>> {
+        match self {
+            Node::Data { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Fork` if the `Node` variant is `Node::Fork`, otherwise
+    /// returns `None`
+    pub fn into_fork(self) -> Option<Fork<P, H, N>> {
+        match self {
+            Node::Fork { fork, .. } => Some(fork),
+            _ => None,
+        }
+    }
+
+    pub fn get_hash(&self) -> Option<RootHash<H>> {
+        match self {
+            Node::Fork { fork, .. } if fork.next.dirty => Some(fork.get_hash()),
+            Node::Fork { hash, .. } => Some(*hash),
+            Node::Data { hash, .. } => Some(*hash),
+            Node::None => None,
         }
     }
 
-    pub fn get_hash(&self) -> Option<[u8; 32]> {
+    /// Returns the `Leaf` at exactly `address` if this is that `Leaf` or a
+    /// `Fork` holding it.
+    pub(crate) fn find_leaf(&self, address: &Address<N>) -> Option<&Leaf<P, H, N>> {
         match self {
-            Node::Fork { hash, .. } => return Some(*hash),
-            Node::Data { hash, .. } => return Some(*hash),
-            Node::None => return None,
+            Node::Data { data, .. } if &data.address == address => Some(data),
+            Node::Fork { fork, .. } => fork.next.find_leaf(address),
+            _ => None,
+        }
+    }
+
+    /// Returns this `Node` if it is the `Node::Data` at exactly `address`, or
+    /// the `Node::Data` at `address` beneath this `Fork`.
+    pub(crate) fn find_data(&self, address: &Address<N>) -> Option<&Node<P, H, N>> {
+        match self {
+            Node::Data { data, .. } if &data.address == address => Some(self),
+            Node::Fork { fork, .. } => fork.next.find_data(address),
+            _ => None,
+        }
+    }
+
+    /// Returns the address of this `Leaf`, or applies `descend` to the `Branch`
+    /// underpinning this `Fork`.
+    fn boundary_address(&self, descend: fn(&Branch<P, H, N>) -> Option<Address<N>>) -> Option<Address<N>> {
+        match self {
+            Node::Data { data, .. } => Some(data.address),
+            Node::Fork { fork, .. } => descend(&fork.next),
+            Node::None => None,
+        }
+    }
+
+    /// Collects this `Leaf`, or every `Leaf` beneath this `Fork`.
+    pub(crate) fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a Leaf<P, H, N>>) {
+        match self {
+            Node::Data { data, .. } => leaves.push(data),
+            Node::Fork { fork, .. } => fork.next.collect_leaves(leaves),
+            Node::None => {}
         }
     }
 }
 
 /// Implements PartialEq for the `Leaf` node.
 /// Two `Leaf` nodes are equal if they have the same hash.
-impl<P: Clone + Debug + Into<Vec<u8>>> PartialEq for Leaf<P> {
-    fn eq(&self, other: &Leaf<P>) -> bool {
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PartialEq for Leaf<P, H, N> {
+    fn eq(&self, other: &Leaf<P, H, N>) -> bool {
         self.get_hash() == other.get_hash()
     }
-    fn ne(&self, other: &Leaf<P>) -> bool {
-        self.get_hash() != other.get_hash()
-    }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Eq for Leaf<P> {}
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Eq for Leaf<P, H, N> {}
 
 /// Implements PartialEq for `Fork` node. Two `Fork nodes
 /// are equal if they have the same hash.
-impl<P: Clone + Debug + Into<Vec<u8>>> PartialEq for Fork<P> {
-    fn eq(&self, other: &Fork<P>) -> bool {
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PartialEq for Fork<P, H, N> {
+    fn eq(&self, other: &Fork<P, H, N>) -> bool {
         self.get_hash() == other.get_hash()
     }
-
-    fn ne(&self, other: &Fork<P>) -> bool {
-        self.get_hash() != other.get_hash()
-    }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Eq for Fork<P> {}
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Eq for Fork<P, H, N> {}
 
 /// Implements PartialEq for the `Branch` node. Two `Branch` nodes
 /// are equal if they have the same hash.
-impl<P: Clone + Debug + Into<Vec<u8>>> PartialEq for Branch<P> {
-    fn eq(&self, other: &Branch<P>) -> bool {
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PartialEq for Branch<P, H, N> {
+    fn eq(&self, other: &Branch<P, H, N>) -> bool {
         self.get_hash() == other.get_hash()
     }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Eq for Branch<P, H, N> {}
 
-    fn ne(&self, other: &Branch<P>) -> bool {
-        self.get_hash() != other.get_hash()
+/// Borrows the `Node` at a `Nibble`, `Node::None` if the slot is unoccupied,
+/// like `Branch::get_ref`.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Index<Nibble> for Branch<P, H, N> {
+    type Output = Node<P, H, N>;
+
+    fn index(&self, nibble: Nibble) -> &Node<P, H, N> {
+        self.slot(nibble)
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Eq for Branch<P> {}
+/// Mutably borrows the `Node` at a `Nibble`, occupying the slot with a
+/// `Node::None` first if it is empty. The `Branch` is marked dirty, and its
+/// hash recomputed from the hashes stored in its nodes by the next `rehash`,
+/// which also drops the slots left holding `Node::None`. Until then those
+/// slots still count as unoccupied, see `occupied`.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IndexMut<Nibble> for Branch<P, H, N> {
+    fn index_mut(&mut self, nibble: Nibble) -> &mut Node<P, H, N> {
+        self.dirty = true;
+        self.nibbles.entry(nibble).or_insert(Node::None)
+    }
+}
 
 /// Implements PartialEq for the `Root` node. Two `Root` nodes
 /// are equal if they have the same hash.
-impl<P: Clone + Debug + Into<Vec<u8>>> PartialEq for Root<P> {
-    fn eq(&self, other: &Root<P>) -> bool {
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PartialEq for Root<P, H, N> {
+    fn eq(&self, other: &Root<P, H, N>) -> bool {
         self.get_hash() == other.get_hash()
     }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Eq for Root<P, H, N> {}
+
+/// Implements Debug for the `Branch` node. Only occupied nibbles are
+/// stored, so they are printed keyed by their `Nibble`, a line per node.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Debug for Branch<P, H, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Branch")
+            .field("layer", &self.layer)
+            .field("nibbles", &self.nibbles)
+            .field("hash", &self.get_hash())
+            .finish()
+    }
+}
 
-    fn ne(&self, other: &Root<P>) -> bool {
-        self.get_hash() != other.get_hash()
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Clone for NodeRef<'_, P, H, N> {
+    fn clone(&self) -> Self {
+        *self
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Eq for Root<P> {}
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Copy for NodeRef<'_, P, H, N> {}
 
 /// Implements IntoIterator, converting a `Branch` node into a `BranchIntoIter`
 /// which can then be iterated over. 
-impl<P: Clone + Debug + Into<Vec<u8>>> IntoIterator for Branch<P> {
-    type Item = Node<P>;
-    type IntoIter = BranchIntoIter<P>;
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for Branch<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = BranchIntoIter<P, H, N>;
 
     fn into_iter(self) -> Self::IntoIter {
         let layer = self.layer.clone();
         BranchIntoIter {
             branch: self,
-            layer: layer,
-            index: 0u8,
+            layer,
+            index: 0,
+            back: Nibble::MAX as usize + 1,
         }
     }
 }
 
 /// Build a type from Branch that implements Iterator
-impl<'a, P: Clone + Debug + Into<Vec<u8>>> IntoIterator for &'a Branch<P> {
-    type Item = Node<P>;
-    type IntoIter = BranchIterator<'a, P>;
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for &'a Branch<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = BranchIterator<'a, P, H, N>;
 
     fn into_iter(self) -> Self::IntoIter {
         let layer = self.layer.clone();
         BranchIterator {
             branch: self,
-            layer: layer,
-            index: 0u8,
+            layer,
+            index: 0,
+            back: Nibble::MAX as usize + 1,
         }
     }
 }
 
 /// Builds a type from a borrowed mutable Branch that implements Iterator
-impl<'a, P: Clone + Debug + Into<Vec<u8>>> IntoIterator for &'a mut Branch<P> {
-    type Item = Node<P>;
-    type IntoIter = BranchIterator<'a, P>;
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for &'a mut Branch<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = BranchIterator<'a, P, H, N>;
 
     fn into_iter(self) -> Self::IntoIter {
         let layer = self.layer.clone();
         BranchIterator {
             branch: self,
-            layer: layer,
-            index: 0u8,
+            layer,
+            index: 0,
+            back: Nibble::MAX as usize + 1,
         }
     }
 }
 
+/// Converts a `Root` into a `BranchIntoIter` over its `Branch`, which is only
+/// cloned if it is shared with another `Root`.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for Root<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = BranchIntoIter<P, H, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_next().into_iter()
+    }
+}
+
+/// Builds a BranchIterator over the Branch of a borrowed Root.
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for &'a Root<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = BranchIterator<'a, P, H, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.next.as_ref().into_iter()
+    }
+}
+
+/// Builds a BranchIterator over the Branch of a mutably borrowed Root,
+/// without copying the Branch if it is shared.
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for &'a mut Root<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = BranchIterator<'a, P, H, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.next.as_ref().into_iter()
+    }
+}
+
 /// Implements Iterator for the BranchIterator type.
-impl<'a, P: Clone + Debug + Into<Vec<u8>>> Iterator for BranchIterator<'a, P> {
-    type Item = Node<P>;
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for BranchIterator<'a, P, H, N> {
+    type Item = Node<P, H, N>;
     
-    fn next(&mut self) -> Option<Node<P>> {
-        if let None = self.index.checked_add(1) {
+    fn next(&mut self) -> Option<Node<P, H, N>> {
+        if self.index >= self.back {
             return None;
-        } else {
-            self.index += 1;
-            return Some(self.branch.nibbles[self.index as usize].clone());
         }
+        let node = self.branch.get(&(self.index as Nibble));
+        self.index += 1;
+        Some(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.index;
+        (len, Some(len))
     }
 }
 
-/// Implements Iterator for BranchIntoIterator type.
-impl<P: Clone + Debug + Into<Vec<u8>>> Iterator for BranchIntoIter<P> {
-    type Item = Node<P>;
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> ExactSizeIterator for BranchIterator<'_, P, H, N> {}
 
-    fn next(&mut self) -> Option<Node<P>> {
-        if let None = self.index.checked_add(1) {
+/// Yields the Nodes of the borrowed Branch from `Nibble` 255 downwards,
+/// stopping where `next` left off.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> DoubleEndedIterator for BranchIterator<'_, P, H, N> {
+    fn next_back(&mut self) -> Option<Node<P, H, N>> {
+        if self.index >= self.back {
             return None;
-        } else {
-            self.index += 1;
-            return Some(self.branch.nibbles[self.index as usize].clone());
         }
+        self.back -= 1;
+        Some(self.branch.get(&(self.back as Nibble)))
     }
 }
 
-/// Converts a Fork into a BranchIntoIterator type
-impl<P: Clone + Debug + Into<Vec<u8>>> IntoIterator for Fork<P> {
-    type Item = Node<P>;
-    type IntoIter = BranchIntoIter<P>;
+/// Implements Iterator for BranchIntoIterator type, moving each `Node` out
+/// of the owned `Branch` rather than cloning it.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for BranchIntoIter<P, H, N> {
+    type Item = Node<P, H, N>;
 
-    fn into_iter(self) -> Self::IntoIter {
-        let layer = self.get_next().get_layer().clone();
-        BranchIntoIter {
-            branch: self.into(),
-            layer: layer.into(),
-            index: 0u8,
+    fn next(&mut self) -> Option<Node<P, H, N>> {
+        if self.index >= self.back {
+            return None;
         }
+        let node = self.branch.nibbles.remove(&(self.index as Nibble));
+        self.index += 1;
+        Some(node.unwrap_or(Node::None))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.index;
+        (len, Some(len))
     }
 }
 
-/// Convert Fork into the branch underpinning it.
-impl<P: Clone + Debug + Into<Vec<u8>>> From<Fork<P>> for Branch<P> {
-    fn from(i: Fork<P>) -> Branch<P> {
-        i.get_next().clone()
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> ExactSizeIterator for BranchIntoIter<P, H, N> {}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> BranchIntoIter<P, H, N> {
+    /// Returns how many of the slots left to yield are occupied.
+    pub(crate) fn occupied_len(&self) -> usize {
+        self.branch.nibbles.values().filter(|node| !node.is_none()).count()
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> DoubleEndedIterator for BranchIntoIter<P> {
-    
-    fn next_back(&mut self) -> Option<Self::Item> {
-        if let 0u8 = self.index {
-            self.branch = self.branch.reverse_nibbles();
+/// Moves the Nodes out of the owned Branch from `Nibble` 255 downwards,
+/// stopping where `next` left off.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> DoubleEndedIterator for BranchIntoIter<P, H, N> {
+    fn next_back(&mut self) -> Option<Node<P, H, N>> {
+        if self.index >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        let node = self.branch.nibbles.remove(&(self.back as Nibble));
+        Some(node.unwrap_or(Node::None))
+    }
+}
+
+/// Converts a Fork into a ForkIntoIterator over the Branch it points to.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for Fork<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = ForkIntoIterator<P, H, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ForkIntoIterator {
+            inner: Arc::unwrap_or_clone(self.next).into_iter(),
+        }
+    }
+}
+
+/// Builds a ForkIterator over the Branch a borrowed Fork points to.
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for &'a Fork<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = ForkIterator<'a, P, H, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ForkIterator {
+            inner: self.next.as_ref().into_iter(),
         }
+    }
+}
+
+/// Implements Iterator for the ForkIntoIterator type.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for ForkIntoIterator<P, H, N> {
+    type Item = Node<P, H, N>;
+
+    fn next(&mut self) -> Option<Node<P, H, N>> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> ExactSizeIterator for ForkIntoIterator<P, H, N> {}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> DoubleEndedIterator for ForkIntoIterator<P, H, N> {
+    fn next_back(&mut self) -> Option<Node<P, H, N>> {
+        self.inner.next_back()
+    }
+}
+
+/// Implements Iterator for the ForkIterator type.
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for ForkIterator<'a, P, H, N> {
+    type Item = Node<P, H, N>;
+
+    fn next(&mut self) -> Option<Node<P, H, N>> {
+        self.inner.next()
+    }
 
-        self.next()
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
     }
-}
\ No newline at end of file
+}
+
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> ExactSizeIterator for ForkIterator<'a, P, H, N> {}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> DoubleEndedIterator for ForkIterator<'_, P, H, N> {
+    fn next_back(&mut self) -> Option<Node<P, H, N>> {
+        self.inner.next_back()
+    }
+}
+
+/// Convert Fork into the branch underpinning it.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> From<Fork<P, H, N>> for Branch<P, H, N> {
+    fn from(i: Fork<P, H, N>) -> Branch<P, H, N> {
+        Arc::unwrap_or_clone(i.next)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::hash::{Hasher, Sha256Algorithm};
+use crate::node::{nibble_at, slot_hash, Address, Leaf, Nibble, RootHash};
+use crate::proof::{Proof, ProofError, Sibling};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+/// A node of the binary Merkle tree a `Branch` folds its slot hashes into,
+/// as far as a `PartialTrie` knows it.
+#[derive(Clone, Debug)]
+enum PartialNode<P, H, const N: usize>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    /// A subtree known by its hash alone.
+    Hash(RootHash<H>),
+    /// Two subtrees hashed together by `Hasher::concat_and_hash`.
+    Pair(Box<PartialNode<P, H, N>>, Box<PartialNode<P, H, N>>),
+    /// The slot at `Nibble` holding a `Leaf`.
+    Leaf(Nibble, Leaf<P, H, N>),
+    /// The slot at `Nibble` holding a `Fork`, by the Merkle tree of its
+    /// `Branch`.
+    Fork(Nibble, Box<PartialNode<P, H, N>>),
+}
+
+/// A minimal `Trie` holding only the leaves some `Proof`s were given for,
+/// see `from_proof` and `merge_partial`, along with the sibling hashes those
+/// proofs hold, e.g. for a stateless client to answer queries against a
+/// `Root` hash it got from elsewhere.
+/// Every part of the `Trie` off the paths of its leaves is known by its hash
+/// alone, so `root_hash` is that of the `Trie` the proofs were built from.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+/// use mmpt::partial::PartialTrie;
+///
+/// let mut trie: Trie<String> = Trie::default();
+/// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+/// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+///
+/// let proof = trie.prove(&[0u8; 32]).unwrap();
+/// let partial = PartialTrie::from_proof(&proof, &[0u8; 32], &"Some Data".to_string()).unwrap();
+/// assert_eq!(partial.root_hash(), trie.root_hash());
+/// assert_eq!(partial.get(&[0u8; 32]), Some(&"Some Data".to_string()));
+/// assert_eq!(partial.get(&[1u8; 32]), None);
+/// ```
+#[derive(Clone, Debug)]
+pub struct PartialTrie<P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    root: PartialNode<P, H, N>,
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PartialTrie<P, H, N> {
+    /// Builds the `PartialTrie` holding the `Leaf` with `address` and
+    /// `payload` that `proof` was given for, and the sibling hashes of each
+    /// of its steps. Returns `ProofError::InvalidPath` if the steps don't
+    /// follow the path `address` takes.
+    ///
+    /// Nothing is checked against a `Root` hash, so the `Proof` is only
+    /// known to be valid once `root_hash` is compared to a trusted one.
+    pub fn from_proof(proof: &Proof<H>, address: &Address<N>, payload: &P) -> Result<PartialTrie<P, H, N>, ProofError> {
+        let steps = &proof.steps;
+        if steps.is_empty() || steps.len() > N {
+            return Err(ProofError::InvalidPath);
+        }
+        if steps.iter().enumerate().any(|(layer, step)| step.nibble != nibble_at(address, layer)) {
+            return Err(ProofError::InvalidPath);
+        }
+
+        let layer = steps.len() - 1;
+        let leaf = Leaf::with_hasher(*address, payload.clone()).at_layer(layer);
+        let mut node = with_siblings(PartialNode::Leaf(steps[layer].nibble, leaf), &steps[layer].siblings);
+        for step in steps[..layer].iter().rev() {
+            node = with_siblings(PartialNode::Fork(step.nibble, Box::new(node)), &step.siblings);
+        }
+        Ok(PartialTrie { root: node })
+    }
+
+    /// Returns the `Root` hash of the `Trie` this `PartialTrie` was built
+    /// from, rebuilt from its leaves and sibling hashes.
+    pub fn root_hash(&self) -> RootHash<H> {
+        H::hash(self.root.hash().as_ref())
+    }
+
+    /// Merges `other` into this `PartialTrie`, so it holds the leaves of
+    /// both and answers everything either does, e.g. to combine the
+    /// witnesses of the transactions of a block. Where one holds a hash and
+    /// the other the nodes beneath it, the nodes are kept once they hash to
+    /// it, so sibling hashes covering the leaves of the other are dropped.
+    ///
+    /// Returns `ProofError::Conflict`, leaving this `PartialTrie` unchanged,
+    /// if the two hold different hashes or nodes at the same position, i.e.
+    /// don't commit to the same `Root` hash.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    /// use mmpt::partial::PartialTrie;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// let proof = trie.prove(&[0u8; 32]).unwrap();
+    /// let mut partial = PartialTrie::from_proof(&proof, &[0u8; 32], &"Some Data".to_string()).unwrap();
+    /// let proof = trie.prove(&[1u8; 32]).unwrap();
+    /// let other = PartialTrie::from_proof(&proof, &[1u8; 32], &"Some More Data".to_string()).unwrap();
+    ///
+    /// partial.merge_partial(other).unwrap();
+    /// assert_eq!(partial.root_hash(), trie.root_hash());
+    /// assert_eq!(partial.get(&[1u8; 32]), Some(&"Some More Data".to_string()));
+    /// ```
+    pub fn merge_partial(&mut self, other: PartialTrie<P, H, N>) -> Result<(), ProofError> {
+        self.root = self.root.clone().merge(other.root)?;
+        Ok(())
+    }
+
+    /// Returns the payload of the `Leaf` at `address`. `None` only means the
+    /// `PartialTrie` doesn't hold it, not that the `Trie` it was built from
+    /// doesn't, see `Trie::prove_absence` for that.
+    pub fn get(&self, address: &Address<N>) -> Option<&P> {
+        let mut node = &self.root;
+        for layer in 0..N {
+            match node.slot(nibble_at(address, layer))? {
+                PartialNode::Leaf(_, leaf) if leaf.get_address() == *address => return Some(leaf.payload_ref()),
+                PartialNode::Fork(_, next) => node = next,
+                _ => return None,
+            }
+        }
+        None
+    }
+}
+
+/// Wraps `node` in the `Pair`s that `siblings`, lowest level first, fold it
+/// up through.
+fn with_siblings<P, H, const N: usize>(node: PartialNode<P, H, N>, siblings: &[Sibling<H>]) -> PartialNode<P, H, N>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    siblings.iter().fold(node, |node, sibling| match sibling {
+        Sibling::Left(left) => PartialNode::Pair(Box::new(PartialNode::Hash(*left)), Box::new(node)),
+        Sibling::Right(right) => PartialNode::Pair(Box::new(node), Box::new(PartialNode::Hash(*right))),
+    })
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PartialNode<P, H, N> {
+    /// Returns the hash of this node, i.e. of its subtree of the Merkle tree
+    /// of a `Branch`.
+    fn hash(&self) -> RootHash<H> {
+        match self {
+            PartialNode::Hash(hash) => *hash,
+            PartialNode::Pair(left, right) => H::concat_and_hash(&left.hash(), Some(&right.hash())),
+            PartialNode::Leaf(nibble, leaf) => slot_hash::<H>(*nibble, &leaf.get_hash()),
+            PartialNode::Fork(nibble, next) => slot_hash::<H>(*nibble, &H::hash(next.hash().as_ref())),
+        }
+    }
+
+    /// Merges two nodes at the same position, see `PartialTrie::merge_partial`.
+    fn merge(self, other: PartialNode<P, H, N>) -> Result<PartialNode<P, H, N>, ProofError> {
+        match (self, other) {
+            (PartialNode::Hash(hash), node) | (node, PartialNode::Hash(hash)) => {
+                if node.hash() != hash {
+                    return Err(ProofError::Conflict);
+                }
+                Ok(node)
+            }
+            (PartialNode::Pair(left, right), PartialNode::Pair(other_left, other_right)) => Ok(PartialNode::Pair(
+                Box::new(left.merge(*other_left)?),
+                Box::new(right.merge(*other_right)?),
+            )),
+            (PartialNode::Leaf(nibble, leaf), PartialNode::Leaf(other_nibble, other)) if nibble == other_nibble && leaf == other => {
+                Ok(PartialNode::Leaf(nibble, leaf))
+            }
+            (PartialNode::Fork(nibble, next), PartialNode::Fork(other_nibble, other)) if nibble == other_nibble => {
+                Ok(PartialNode::Fork(nibble, Box::new(next.merge(*other)?)))
+            }
+            _ => Err(ProofError::Conflict),
+        }
+    }
+
+    /// Returns the `Leaf` or `Fork` slot at `nibble` in this Merkle tree, if
+    /// it isn't hidden behind a hash.
+    fn slot(&self, nibble: Nibble) -> Option<&PartialNode<P, H, N>> {
+        match self {
+            PartialNode::Hash(_) => None,
+            PartialNode::Pair(left, right) => left.slot(nibble).or_else(|| right.slot(nibble)),
+            PartialNode::Leaf(slot, _) | PartialNode::Fork(slot, _) => (*slot == nibble).then_some(self),
+        }
+    }
+}
@@ -0,0 +1,774 @@
+use crate::hash::{Hasher, Sha256Algorithm};
+use crate::node::{hash_from, merkle_leaves, nibble_at, slot_hash, take, Address, DecodeError, Leaf, Nibble, RootHash};
+use alloc::collections::btree_map::Entry;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+/// A hash met while folding a `ProofStep` back up to the hash of its `Branch`,
+/// tagged with the side it is concatenated on.
+#[derive(Clone, Debug)]
+pub enum Sibling<H = Sha256Algorithm>
+where
+    H: Hasher,
+{
+    Left(RootHash<H>),
+    Right(RootHash<H>),
+}
+
+/// The part of a `Proof` covering a single `Branch`: the `Nibble` taken out
+/// of it and the `Sibling` hashes needed to rebuild its binary Merkle root
+/// from the hash of the node at that `Nibble`, lowest level first.
+#[derive(Clone, Debug)]
+pub struct ProofStep<H = Sha256Algorithm>
+where
+    H: Hasher,
+{
+    pub nibble: Nibble,
+    pub siblings: Vec<Sibling<H>>,
+}
+
+/// A Merkle inclusion proof for a single `Leaf`, as returned by `Trie::prove`.
+/// The `steps` are ordered from the `Root` `Branch` down to the `Branch`
+/// holding the `Leaf`, so the `Branch` at `Layer` `i` is covered by `steps[i]`.
+///
+/// A `Proof` is specific to the `Hasher` `H` of the `Trie` it was built from:
+/// it only verifies when rehashed with `H`, against a root hashed with `H`,
+/// even between hashers whose hashes have the same size.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let mut trie: Trie<String> = Trie::default();
+/// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+/// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+///
+/// let proof = trie.prove(&[0u8; 32]).unwrap();
+/// let root = proof.compute_root(&[0u8; 32], &"Some Data".to_string());
+/// assert_eq!(root, trie.root.get_hash());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Proof<H = Sha256Algorithm>
+where
+    H: Hasher,
+{
+    pub steps: Vec<ProofStep<H>>,
+}
+
+/// How the path of an absent address diverges from a `Trie`, in the last
+/// `Branch` on that path.
+#[derive(Clone, Debug)]
+pub enum Divergence<H = Sha256Algorithm, const N: usize = 32>
+where
+    H: Hasher,
+{
+    /// The `Nibble` the address takes is unoccupied. Holds every occupied
+    /// `Nibble` of the `Branch` in order, along with the hash of its node, so
+    /// the whole `Branch` hash can be rebuilt without that `Nibble`.
+    Empty { occupied: Vec<(Nibble, RootHash<H>)> },
+    /// The `Nibble` the address takes holds a `Leaf` with a different
+    /// `address` sharing the path so far. Holds that `Leaf`'s serialized
+    /// `payload` so its hash can be rebuilt, and the `Sibling` hashes folding
+    /// it up to the `Branch` hash.
+    Leaf {
+        address: Address<N>,
+        payload: Vec<u8>,
+        siblings: Vec<Sibling<H>>,
+    },
+}
+
+/// A Merkle exclusion proof showing no `Leaf` exists at an address, as
+/// returned by `Trie::prove_absence`. The `steps` cover the `Branch`es from
+/// the `Root` down to, but not including, the `Branch` at which the path of
+/// the address diverges, which is covered by `divergence`.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let mut trie: Trie<String> = Trie::default();
+/// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+///
+/// let proof = trie.prove_absence(&[1u8; 32]).unwrap();
+/// assert!(proof.verify(&trie.root.get_hash(), &[1u8; 32]));
+/// assert!(!proof.verify(&trie.root.get_hash(), &[0u8; 32]));
+/// assert!(trie.prove_absence(&[0u8; 32]).is_none());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExclusionProof<H = Sha256Algorithm, const N: usize = 32>
+where
+    H: Hasher,
+{
+    pub steps: Vec<ProofStep<H>>,
+    pub divergence: Divergence<H, N>,
+}
+
+/// A slot of a `Branch` covered by a `RangeProof`.
+#[derive(Clone, Debug)]
+pub enum RangeNode<H = Sha256Algorithm, const N: usize = 32>
+where
+    H: Hasher,
+{
+    /// A node none of whose addresses lie in the range, by its hash.
+    Hash(RootHash<H>),
+    /// A `Leaf` whose slot reaches into the range, by its address and its
+    /// serialized `payload`. The address itself may lie just outside the
+    /// range, showing there is nothing else in the slot.
+    Leaf { address: Address<N>, payload: Vec<u8> },
+    /// A `Fork` whose `Branch` reaches into the range, by every occupied slot
+    /// of that `Branch`, in order.
+    Fork(Vec<(Nibble, RangeNode<H, N>)>),
+}
+
+/// A Merkle proof of every `Leaf` with an address in a range `[start, end)`,
+/// as returned by `Trie::prove_range`, e.g. for a peer syncing a slice of the
+/// address space to check it received all of it. Every occupied slot of the
+/// `Root` `Branch` is held, in order, those whose addresses all fall outside
+/// the range by their hash alone, the others opened down to their leaves.
+/// As the hash of a `Branch` commits to all of its slots, no `Leaf` in the
+/// range can be left out without the `Root` hash changing.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let mut trie: Trie<Vec<u8>> = Trie::default();
+/// for i in 0..4u8 {
+///     trie.add(Leaf::new([i; 32], vec![i])).unwrap();
+/// }
+///
+/// let proof = trie.prove_range(&[1u8; 32], &[3u8; 32]);
+/// assert!(proof.verify_range(&trie.root_hash(), &[1u8; 32], &[3u8; 32]));
+/// assert_eq!(proof.leaves(&[1u8; 32], &[3u8; 32]), vec![([1u8; 32], &[1u8][..]), ([2u8; 32], &[2u8][..])]);
+/// assert!(!proof.verify_range(&trie.root_hash(), &[0u8; 32], &[3u8; 32]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RangeProof<H = Sha256Algorithm, const N: usize = 32>
+where
+    H: Hasher,
+{
+    pub slots: Vec<(Nibble, RangeNode<H, N>)>,
+}
+
+/// The position of a sibling hash in a `MultiProof`: the `Nibble`s leading
+/// to the `Branch` it is in, and the sides taken from the top of the binary
+/// Merkle tree of that `Branch` down to it, `true` being the right one.
+type Position = (Vec<Nibble>, Vec<bool>);
+
+/// Several `Proof`s of the same `Trie` merged into one, as returned by
+/// `dedup_proofs`, holding each sibling hash once however many of the proofs
+/// hold it. Every proof is kept as its path, i.e. per step its `Nibble` and
+/// the sides it takes down the Merkle tree of the `Branch`, top first, and
+/// every sibling hash by its position, so proofs of leaves sharing a path
+/// share the hashes along it. The paths are sorted, and a proof given twice
+/// is kept once.
+///
+/// As a `Proof` doesn't hold its `Leaf`, a sibling hash covering the path of
+/// another proof is kept, unlike in a `PartialTrie`, so `expand` gives back
+/// every proof without needing the leaves.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+/// use mmpt::proof::dedup_proofs;
+///
+/// let mut trie: Trie<String> = Trie::default();
+/// for i in 0..4u8 {
+///     trie.add(Leaf::new([i; 32], "Some Data".to_string())).unwrap();
+/// }
+///
+/// let proofs = vec![trie.prove(&[0u8; 32]).unwrap(), trie.prove(&[1u8; 32]).unwrap()];
+/// let multi = dedup_proofs(&proofs).unwrap();
+/// assert_eq!(multi.hash_count(), 3);
+/// assert_eq!(multi.expand(), proofs);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiProof<H = Sha256Algorithm>
+where
+    H: Hasher,
+{
+    paths: Vec<Vec<(Nibble, Vec<bool>)>>,
+    hashes: BTreeMap<Position, RootHash<H>>,
+}
+
+/// Errors returned when building a `PartialTrie` or a `MultiProof` from
+/// proofs.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProofError {
+    /// The steps of a `Proof` don't follow the path of the address it was
+    /// given for, or there are none, or more than the address has bytes.
+    InvalidPath,
+    /// Two proofs or partial tries hold different nodes or hashes at the
+    /// same position, i.e. they don't commit to the same `Root` hash.
+    Conflict,
+}
+
+impl Display for ProofError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ProofError {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
+impl ProofError {
+    fn message(&self) -> &str {
+        match self {
+            ProofError::InvalidPath => "The Proof doesn't follow the path of its address",
+            ProofError::Conflict => "The proofs don't commit to the same Root hash",
+        }
+    }
+}
+
+impl<H: Hasher> ProofStep<H> {
+    /// Rebuilds the hash of the `Branch` this step covers, given the hash of
+    /// the node at `nibble`.
+    pub fn branch_hash(&self, child: RootHash<H>) -> RootHash<H> {
+        self.siblings
+            .iter()
+            .fold(slot_hash::<H>(self.nibble, &child), |hash, sibling| match sibling {
+                Sibling::Left(left) => H::concat_and_hash(left, Some(&hash)),
+                Sibling::Right(right) => H::concat_and_hash(&hash, Some(right)),
+            })
+    }
+}
+
+impl<H: Hasher> Proof<H> {
+    /// Returns true if this `Proof` shows a `Leaf` with `address` and `payload`
+    /// is committed to by `root`. The path must be the one `address` takes,
+    /// i.e. the `Nibble` of every step must be the `Nibble` of `address` at
+    /// that `Layer`, and the `Root` hash recomputed along it must be `root`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// let root = trie.root.get_hash();
+    /// let proof = trie.prove(&[0u8; 32]).unwrap();
+    /// assert!(proof.verify(&root, &[0u8; 32], &"Some Data".to_string()));
+    /// assert!(!proof.verify(&root, &[0u8; 32], &"Other Data".to_string()));
+    /// ```
+    pub fn verify<P, const N: usize>(&self, root: &RootHash<H>, address: &Address<N>, payload: &P) -> bool
+    where
+        P: Clone + Debug + Into<Vec<u8>>,
+    {
+        if self.steps.is_empty() || self.steps.len() > address.len() {
+            return false;
+        }
+        let on_path = self
+            .steps
+            .iter()
+            .enumerate()
+            .all(|(layer, step)| step.nibble == nibble_at(address, layer));
+
+        on_path && self.compute_root(address, payload) == *root
+    }
+
+    /// Returns the number of `Branch`es the `Proof` covers, i.e. the `Layer`
+    /// of the `Branch` holding the `Leaf` plus one.
+    ///
+    /// Checking it, along with `encoded_size`, before `verify` bounds the work
+    /// a proof received from an untrusted peer can cause. With `N` byte
+    /// addresses no valid `Proof` is longer than `N`, and as each `Branch`
+    /// holds at most 256 slots, no step has more than 8 siblings.
+    pub fn path_len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns the number of bytes the `Proof` takes when sent as, per step,
+    /// its `Nibble`, its number of siblings, and each `Sibling` as a side
+    /// byte followed by its hash. At most `2 + 8 * (1 + H::hash_size())`
+    /// bytes per step are needed, so a proof for 32 byte addresses hashed to
+    /// 32 bytes larger than `32 * 266 = 8512` bytes should be rejected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// let proof = trie.prove(&[0u8; 32]).unwrap();
+    /// assert_eq!(proof.path_len(), 1);
+    /// assert_eq!(proof.encoded_size(), 2 + 33);
+    /// ```
+    pub fn encoded_size(&self) -> usize {
+        self.steps
+            .iter()
+            .map(|step| 2 + step.siblings.len() * (1 + H::hash_size()))
+            .sum()
+    }
+
+    /// Recomputes the `Root` hash committing to a `Leaf` with `address` and
+    /// `payload` at the end of the path. Each `Branch` hash is wrapped once
+    /// more on the way up, as its `Fork`, or ultimately the `Root`, hashes it.
+    pub fn compute_root<P, const N: usize>(&self, address: &Address<N>, payload: &P) -> RootHash<H>
+    where
+        P: Clone + Debug + Into<Vec<u8>>,
+    {
+        let leaf: Leaf<P, H, N> = Leaf::with_hasher(*address, payload.clone());
+        self.steps
+            .iter()
+            .rev()
+            .fold(leaf.get_hash(), |hash, step| H::hash(step.branch_hash(hash).as_ref()))
+    }
+}
+
+/// Merges `proofs`, all of the same `Trie`, into a `MultiProof` holding each
+/// of their sibling hashes once, e.g. for a block to ship the proofs of all
+/// its transactions without repeating the hashes near the `Root`, see
+/// `MultiProof::expand` to get them back. Returns `ProofError::Conflict` if
+/// two proofs hold different hashes at the same position, i.e. weren't built
+/// from the same `Trie`, and `ProofError::InvalidPath` if one has no steps.
+pub fn dedup_proofs<H: Hasher>(proofs: &[Proof<H>]) -> Result<MultiProof<H>, ProofError> {
+    let mut paths = BTreeSet::new();
+    let mut hashes = BTreeMap::new();
+    for proof in proofs {
+        if proof.steps.is_empty() {
+            return Err(ProofError::InvalidPath);
+        }
+        let mut prefix = vec![];
+        let mut path = vec![];
+        for step in &proof.steps {
+            let sides: Vec<bool> = step.siblings.iter().rev().map(|sibling| matches!(sibling, Sibling::Left(_))).collect();
+            for (depth, sibling) in step.siblings.iter().rev().enumerate() {
+                let (Sibling::Left(hash) | Sibling::Right(hash)) = sibling;
+                match hashes.entry((prefix.clone(), sibling_position(&sides, depth))) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(*hash);
+                    }
+                    Entry::Occupied(entry) if entry.get() == hash => {}
+                    Entry::Occupied(_) => return Err(ProofError::Conflict),
+                }
+            }
+            prefix.push(step.nibble);
+            path.push((step.nibble, sides));
+        }
+        paths.insert(path);
+    }
+    Ok(MultiProof { paths: paths.into_iter().collect(), hashes })
+}
+
+/// Returns the position of the sibling of the node reached by taking
+/// `sides[..=depth]` down a Merkle tree, i.e. the same sides except the last.
+fn sibling_position(sides: &[bool], depth: usize) -> Vec<bool> {
+    let mut position = sides[..depth].to_vec();
+    position.push(!sides[depth]);
+    position
+}
+
+impl<H: Hasher> MultiProof<H> {
+    /// Returns every `Proof` merged into this `MultiProof`, once each, in
+    /// the order of their paths.
+    pub fn expand(&self) -> Vec<Proof<H>> {
+        let mut proofs = Vec::with_capacity(self.paths.len());
+        for path in &self.paths {
+            let mut prefix = vec![];
+            let mut steps = Vec::with_capacity(path.len());
+            for (nibble, sides) in path {
+                let siblings = (0..sides.len())
+                    .rev()
+                    .map(|depth| {
+                        let hash = self.hashes[&(prefix.clone(), sibling_position(sides, depth))];
+                        if sides[depth] {
+                            Sibling::Left(hash)
+                        } else {
+                            Sibling::Right(hash)
+                        }
+                    })
+                    .collect();
+                prefix.push(*nibble);
+                steps.push(ProofStep { nibble: *nibble, siblings });
+            }
+            proofs.push(Proof { steps });
+        }
+        proofs
+    }
+
+    /// Returns the bytes of this `MultiProof`, see `from_bytes`, laid out as:
+    ///
+    /// - the `Hasher::algorithm_id` of `H`, in one byte,
+    /// - the number of paths, as a big endian `u32`,
+    /// - every path in ascending order, each as its number of steps, as a
+    ///   big endian `u16`, followed per step by its `Nibble`, the number of
+    ///   sides it takes down the Merkle tree of the `Branch`, at most 8, and
+    ///   a bitmap of those sides, the top one in the most significant bit, a
+    ///   set bit being the right side and the unused bits clear,
+    /// - every sibling hash, in `Hasher::hash_size` bytes each, in ascending
+    ///   order of their position, i.e. by the `Nibble`s leading to their
+    ///   `Branch`, then by the sides taken down to them, left first, a
+    ///   position coming before those below it.
+    ///
+    /// The positions of the hashes aren't written, as they follow from the
+    /// paths: the sibling of every node on a path that is not on the path.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    /// use mmpt::proof::{dedup_proofs, MultiProof};
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// let multi = dedup_proofs(&[trie.prove(&[0u8; 32]).unwrap()]).unwrap();
+    /// let bytes = multi.to_bytes();
+    /// assert_eq!(bytes[..10], [1, 0, 0, 0, 1, 0, 1, 0, 1, 0]);
+    /// assert_eq!(bytes.len(), 10 + 32);
+    /// assert_eq!(MultiProof::from_bytes(&bytes).unwrap(), multi);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![H::algorithm_id()];
+        out.extend((self.paths.len() as u32).to_be_bytes());
+        for path in &self.paths {
+            out.extend((path.len() as u16).to_be_bytes());
+            for (nibble, sides) in path {
+                let bitmap = sides.iter().enumerate().fold(0u8, |bitmap, (depth, right)| bitmap | (u8::from(*right) << (7 - depth)));
+                out.extend([*nibble, sides.len() as u8, bitmap]);
+            }
+        }
+        for hash in self.hashes.values() {
+            out.extend_from_slice(hash.as_ref());
+        }
+        out
+    }
+
+    /// Decodes a `MultiProof` written by `to_bytes`. Returns a `DecodeError`
+    /// if the bytes were written with another `Hasher`, are truncated or
+    /// followed by more bytes, or if a path is empty, malformed or out of
+    /// order.
+    pub fn from_bytes(bytes: &[u8]) -> Result<MultiProof<H>, DecodeError> {
+        let mut bytes = bytes;
+        let id = take(&mut bytes, 1)?[0];
+        if id != H::algorithm_id() {
+            return Err(DecodeError::AlgorithmMismatch(id));
+        }
+        let count = u32::from_be_bytes(take(&mut bytes, 4)?.try_into().unwrap());
+        let mut paths: Vec<Vec<(Nibble, Vec<bool>)>> = vec![];
+        for _ in 0..count {
+            let steps = u16::from_be_bytes(take(&mut bytes, 2)?.try_into().unwrap());
+            if steps == 0 {
+                return Err(DecodeError::InvalidPath);
+            }
+            let mut path = Vec::with_capacity(steps as usize);
+            for _ in 0..steps {
+                let step = take(&mut bytes, 3)?;
+                let (nibble, depth, bitmap) = (step[0], step[1] as usize, step[2]);
+                if depth > 8 || (depth < 8 && bitmap & (0xff >> depth) != 0) {
+                    return Err(DecodeError::InvalidPath);
+                }
+                path.push((nibble, (0..depth).map(|depth| bitmap & (0x80 >> depth) != 0).collect()));
+            }
+            if paths.last().is_some_and(|previous| *previous >= path) {
+                return Err(DecodeError::InvalidPath);
+            }
+            paths.push(path);
+        }
+
+        let mut positions = BTreeSet::new();
+        for path in &paths {
+            let mut prefix = vec![];
+            for (nibble, sides) in path {
+                positions.extend((0..sides.len()).map(|depth| (prefix.clone(), sibling_position(sides, depth))));
+                prefix.push(*nibble);
+            }
+        }
+        let mut hashes = BTreeMap::new();
+        for position in positions {
+            hashes.insert(position, hash_from::<H>(take(&mut bytes, H::hash_size())?)?);
+        }
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(MultiProof { paths, hashes })
+    }
+
+    /// Returns the number of proofs merged into this `MultiProof`.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns true if no proofs were merged into this `MultiProof`.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Returns the number of sibling hashes held, each once.
+    pub fn hash_count(&self) -> usize {
+        self.hashes.len()
+    }
+}
+
+impl<H: Hasher, const N: usize> ExclusionProof<H, N> {
+    /// Returns true if this `ExclusionProof` shows there is no `Leaf` at
+    /// `address` in the `Trie` committed to by `root`. The `steps` must follow
+    /// the path of `address`, the divergence must be consistent with it, i.e.
+    /// its `Nibble` is left out of the occupied ones, or is held by a `Leaf`
+    /// sharing the path but with another address, and the `Root` hash
+    /// recomputed from it must be `root`.
+    pub fn verify(&self, root: &RootHash<H>, address: &Address<N>) -> bool {
+        let layer = self.steps.len();
+        if layer >= address.len() {
+            return false;
+        }
+        let on_path = self
+            .steps
+            .iter()
+            .enumerate()
+            .all(|(layer, step)| step.nibble == nibble_at(address, layer));
+        if !on_path {
+            return false;
+        }
+
+        let nibble = nibble_at(address, layer);
+        let branch = match &self.divergence {
+            Divergence::Empty { occupied } => {
+                let ascending = occupied.windows(2).all(|pair| pair[0].0 < pair[1].0);
+                if !ascending || occupied.iter().any(|(occupied, _)| *occupied == nibble) {
+                    return false;
+                }
+                let hashes = occupied
+                    .iter()
+                    .map(|(nibble, hash)| (*nibble, slot_hash::<H>(*nibble, hash)))
+                    .collect();
+                H::merkle_root(&merkle_leaves::<H>(hashes))
+            }
+            Divergence::Leaf { address: other, payload, siblings } => {
+                if other == address || other[..=layer] != address[..=layer] {
+                    return false;
+                }
+                let leaf: Leaf<Vec<u8>, H, N> = Leaf::with_hasher(*other, payload.clone());
+                let step = ProofStep { nibble, siblings: siblings.clone() };
+                step.branch_hash(leaf.get_hash())
+            }
+        };
+
+        let computed = self
+            .steps
+            .iter()
+            .rev()
+            .fold(H::hash(branch.as_ref()), |hash, step| H::hash(step.branch_hash(hash).as_ref()));
+        computed == *root
+    }
+}
+
+impl<H: Hasher, const N: usize> RangeProof<H, N> {
+    /// Returns true if this `RangeProof` shows its leaves in `[start, end)`,
+    /// see `leaves`, are all the leaves in that range in the `Trie` committed
+    /// to by `root`. Slots must be in ascending order, every slot held by its
+    /// hash alone must lie outside the range, every `Leaf` must lie on the
+    /// path of its slot, and the `Root` hash recomputed from them must be `root`.
+    pub fn verify_range(&self, root: &RootHash<H>, start: &Address<N>, end: &Address<N>) -> bool {
+        fold_range::<H, N>(&self.slots, &mut vec![], start, end).is_some_and(|branch| H::hash(branch.as_ref()) == *root)
+    }
+
+    /// Returns the address and serialized payload of every `Leaf` of this
+    /// `RangeProof` in `[start, end)`, in order, leaving out those bounding
+    /// the range. Only meaningful once `verify_range` accepted the proof.
+    pub fn leaves(&self, start: &Address<N>, end: &Address<N>) -> Vec<(Address<N>, &[u8])> {
+        let mut leaves = vec![];
+        range_leaves(&self.slots, start, end, &mut leaves);
+        leaves
+    }
+}
+
+/// Rebuilds the hash of the `Branch` covered by `slots`, see
+/// `RangeProof::verify_range`, `path` being the prefix shared by the
+/// addresses beneath it. Returns `None` if the slots are inconsistent with
+/// the range or with their place in the `Trie`.
+fn fold_range<H: Hasher, const N: usize>(
+    slots: &[(Nibble, RangeNode<H, N>)],
+    path: &mut Vec<u8>,
+    start: &Address<N>,
+    end: &Address<N>,
+) -> Option<RootHash<H>> {
+    if path.len() >= N || !slots.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+        return None;
+    }
+    let mut hashes = Vec::with_capacity(slots.len());
+    for (nibble, node) in slots {
+        path.push(*nibble);
+        let child = match node {
+            RangeNode::Hash(hash) if outside_range(path, start, end) => Some(*hash),
+            RangeNode::Hash(_) => None,
+            RangeNode::Leaf { address, payload } if address.starts_with(path) => {
+                let leaf: Leaf<Vec<u8>, H, N> = Leaf::with_hasher(*address, payload.clone());
+                Some(leaf.get_hash())
+            }
+            RangeNode::Leaf { .. } => None,
+            RangeNode::Fork(slots) => fold_range(slots, path, start, end).map(|branch| H::hash(branch.as_ref())),
+        };
+        path.pop();
+        hashes.push((*nibble, slot_hash::<H>(*nibble, &child?)));
+    }
+    Some(H::merkle_root(&merkle_leaves::<H>(hashes)))
+}
+
+/// Appends the leaves held in `slots` with an address in `[start, end)` to
+/// `leaves`, in order.
+fn range_leaves<'a, H: Hasher, const N: usize>(
+    slots: &'a [(Nibble, RangeNode<H, N>)],
+    start: &Address<N>,
+    end: &Address<N>,
+    leaves: &mut Vec<(Address<N>, &'a [u8])>,
+) {
+    for (_, node) in slots {
+        match node {
+            RangeNode::Leaf { address, payload } if start <= address && address < end => {
+                leaves.push((*address, payload.as_slice()));
+            }
+            RangeNode::Fork(slots) => range_leaves(slots, start, end, leaves),
+            _ => {}
+        }
+    }
+}
+
+/// Returns true if no address starting with `path` lies in `[start, end)`,
+/// i.e. `path` padded with `0x00` bytes is at least `end`, or padded with
+/// `0xff` bytes is below `start`.
+pub(crate) fn outside_range<const N: usize>(path: &[u8], start: &Address<N>, end: &Address<N>) -> bool {
+    let len = path.len();
+    path < &start[..len] || path > &end[..len] || (path == &end[..len] && end[len..].iter().all(|byte| *byte == 0))
+}
+
+/// Returns every level of the binary Merkle tree `Hasher::merkle_root`
+/// folds `hashes` into, from `hashes` themselves up to the level holding the
+/// root alone. No hashes make no levels.
+pub(crate) fn merkle_levels<H: Hasher>(hashes: Vec<RootHash<H>>) -> Vec<Vec<RootHash<H>>> {
+    let mut levels = vec![];
+    if hashes.is_empty() {
+        return levels;
+    }
+    levels.push(hashes);
+    while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+        let next = level
+            .chunks(2)
+            .map(|pair| H::concat_and_hash(&pair[0], pair.get(1)))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Returns the `Sibling` hashes needed to fold the hash at `index` of the
+/// lowest of `levels`, see `merkle_levels`, up to the root, lowest level
+/// first. Levels at which the hash is the odd one out, and so carried up
+/// unchanged, add none. Nothing is hashed.
+pub(crate) fn merkle_path<H: Hasher>(levels: &[Vec<RootHash<H>>], mut index: usize) -> Vec<Sibling<H>> {
+    let mut siblings = vec![];
+    for level in levels.iter().take_while(|level| level.len() > 1) {
+        if index % 2 == 1 {
+            siblings.push(Sibling::Left(level[index - 1]));
+        } else if let Some(right) = level.get(index + 1) {
+            siblings.push(Sibling::Right(*right));
+        }
+        index /= 2;
+    }
+    siblings
+}
+
+/// Implements PartialEq for `Sibling`. Two `Sibling` hashes are equal if
+/// they sit on the same side and have the same hash.
+impl<H: Hasher> PartialEq for Sibling<H> {
+    fn eq(&self, other: &Sibling<H>) -> bool {
+        match (self, other) {
+            (Sibling::Left(hash), Sibling::Left(other)) => hash == other,
+            (Sibling::Right(hash), Sibling::Right(other)) => hash == other,
+            _ => false,
+        }
+    }
+}
+
+/// Implements PartialEq for `ProofStep`.
+impl<H: Hasher> PartialEq for ProofStep<H> {
+    fn eq(&self, other: &ProofStep<H>) -> bool {
+        self.nibble == other.nibble && self.siblings == other.siblings
+    }
+}
+
+/// Implements PartialEq for `Proof`. Two `Proof`s are equal if they take
+/// the same path with the same `Sibling` hashes.
+impl<H: Hasher> PartialEq for Proof<H> {
+    fn eq(&self, other: &Proof<H>) -> bool {
+        self.steps == other.steps
+    }
+}
+
+/// Implements PartialEq for `MultiProof`.
+impl<H: Hasher> PartialEq for MultiProof<H> {
+    fn eq(&self, other: &MultiProof<H>) -> bool {
+        self.paths == other.paths && self.hashes == other.hashes
+    }
+}
+
+/// Implements PartialEq for `Divergence`.
+impl<H: Hasher, const N: usize> PartialEq for Divergence<H, N> {
+    fn eq(&self, other: &Divergence<H, N>) -> bool {
+        match (self, other) {
+            (Divergence::Empty { occupied }, Divergence::Empty { occupied: other }) => occupied == other,
+            (
+                Divergence::Leaf { address, payload, siblings },
+                Divergence::Leaf { address: other_address, payload: other_payload, siblings: other_siblings },
+            ) => address == other_address && payload == other_payload && siblings == other_siblings,
+            _ => false,
+        }
+    }
+}
+
+/// Implements PartialEq for `ExclusionProof`.
+impl<H: Hasher, const N: usize> PartialEq for ExclusionProof<H, N> {
+    fn eq(&self, other: &ExclusionProof<H, N>) -> bool {
+        self.steps == other.steps && self.divergence == other.divergence
+    }
+}
+
+/// Implements PartialEq for `RangeNode`.
+impl<H: Hasher, const N: usize> PartialEq for RangeNode<H, N> {
+    fn eq(&self, other: &RangeNode<H, N>) -> bool {
+        match (self, other) {
+            (RangeNode::Hash(hash), RangeNode::Hash(other)) => hash == other,
+            (
+                RangeNode::Leaf { address, payload },
+                RangeNode::Leaf { address: other_address, payload: other_payload },
+            ) => address == other_address && payload == other_payload,
+            (RangeNode::Fork(slots), RangeNode::Fork(other)) => slots == other,
+            _ => false,
+        }
+    }
+}
+
+/// Implements PartialEq for `RangeProof`.
+impl<H: Hasher, const N: usize> PartialEq for RangeProof<H, N> {
+    fn eq(&self, other: &RangeProof<H, N>) -> bool {
+        self.slots == other.slots
+    }
+}
@@ -0,0 +1,116 @@
+use crate::node::DecodeError;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A decoded RLP item, borrowing its byte strings from the encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Item<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<Item<'a>>),
+}
+
+/// Appends the RLP encoding of the byte string `bytes` to `out`. A single
+/// byte below 0x80 is its own encoding, any other string is prefixed with
+/// its length.
+pub(crate) fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    if let [byte] = bytes {
+        if *byte < 0x80 {
+            out.push(*byte);
+            return;
+        }
+    }
+    encode_length(bytes.len(), 0x80, out);
+    out.extend_from_slice(bytes);
+}
+
+/// Appends the RLP encoding of a list to `out`, where `items` holds the
+/// concatenated encodings of its items.
+pub(crate) fn encode_list(items: &[u8], out: &mut Vec<u8>) {
+    encode_length(items.len(), 0xc0, out);
+    out.extend_from_slice(items);
+}
+
+/// Appends the prefix of a string, `offset` 0x80, or list, `offset` 0xc0,
+/// whose payload is `len` bytes long. Up to 55 bytes the length is added to
+/// the offset, beyond that the big endian length follows the offset plus 55
+/// plus the number of bytes it takes.
+fn encode_length(len: usize, offset: u8, out: &mut Vec<u8>) {
+    if len <= 55 {
+        out.push(offset + len as u8);
+        return;
+    }
+    let bytes = len.to_be_bytes();
+    let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+    out.push(offset + 55 + (bytes.len() - skip) as u8);
+    out.extend_from_slice(&bytes[skip..]);
+}
+
+/// Decodes `bytes` as a single RLP item holding lists nested at most
+/// `max_depth` deep. Returns a `DecodeError` if they are truncated, hold more
+/// than one item, nest lists deeper, or aren't in the canonical, i.e.
+/// shortest, encoding.
+pub(crate) fn decode(bytes: &[u8], max_depth: usize) -> Result<Item<'_>, DecodeError> {
+    let mut bytes = bytes;
+    let item = decode_item(&mut bytes, max_depth)?;
+    if !bytes.is_empty() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(item)
+}
+
+/// Decodes the RLP item at the front of `bytes`, advancing past it, with
+/// room for `depth` more levels of nested lists, so hostile input can't
+/// overflow the stack.
+fn decode_item<'a>(bytes: &mut &'a [u8], depth: usize) -> Result<Item<'a>, DecodeError> {
+    let prefix = *bytes.first().ok_or(DecodeError::UnexpectedEnd)?;
+    if prefix < 0x80 {
+        let byte = &bytes[..1];
+        *bytes = &bytes[1..];
+        return Ok(Item::Bytes(byte));
+    }
+    *bytes = &bytes[1..];
+    let (is_list, len) = match prefix {
+        0x80..=0xb7 => (false, (prefix - 0x80) as usize),
+        0xb8..=0xbf => (false, decode_length(bytes, (prefix - 0xb7) as usize)?),
+        0xc0..=0xf7 => (true, (prefix - 0xc0) as usize),
+        _ => (true, decode_length(bytes, (prefix - 0xf7) as usize)?),
+    };
+    if bytes.len() < len {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (mut payload, rest) = bytes.split_at(len);
+    *bytes = rest;
+    if !is_list {
+        if let [byte] = payload {
+            if *byte < 0x80 {
+                return Err(DecodeError::InvalidRlp);
+            }
+        }
+        return Ok(Item::Bytes(payload));
+    }
+    if depth == 0 {
+        return Err(DecodeError::InvalidRlp);
+    }
+    let mut items = vec![];
+    while !payload.is_empty() {
+        items.push(decode_item(&mut payload, depth - 1)?);
+    }
+    Ok(Item::List(items))
+}
+
+/// Decodes the `width` byte big endian length following a long string or
+/// list prefix, which must not fit in the prefix itself.
+fn decode_length(bytes: &mut &[u8], width: usize) -> Result<usize, DecodeError> {
+    if bytes.len() < width {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    if width > core::mem::size_of::<usize>() || bytes[0] == 0 {
+        return Err(DecodeError::InvalidRlp);
+    }
+    let len = bytes[..width].iter().fold(0usize, |len, byte| len << 8 | *byte as usize);
+    *bytes = &bytes[width..];
+    if len <= 55 {
+        return Err(DecodeError::InvalidRlp);
+    }
+    Ok(len)
+}
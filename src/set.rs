@@ -0,0 +1,85 @@
+use crate::node::{Address, Leaf};
+use crate::trie::Trie;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The payload stored in every `Leaf` of a `TrieSet`. It serializes to no
+/// bytes, so a `Leaf` hash only commits to the `Leaf` address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Empty;
+
+impl From<Empty> for Vec<u8> {
+    fn from(_: Empty) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// An authenticated set of addresses, i.e. a `Trie` whose leaves carry no data.
+/// Since every `Leaf` hash covers its address, distinct sets produce distinct
+/// commitments even though the payloads are all `Empty`.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::set::TrieSet;
+///
+/// let mut set = TrieSet::new();
+/// set.insert([0u8; 32]);
+/// assert!(set.contains(&[0u8; 32]));
+/// assert!(!set.contains(&[1u8; 32]));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrieSet {
+    trie: Trie<Empty>,
+}
+
+impl TrieSet {
+    /// Creates a new, empty `TrieSet`
+    pub fn new() -> TrieSet {
+        TrieSet { trie: Trie::new() }
+    }
+
+    /// Adds `address` to the set. Returns false if it was already a member.
+    pub fn insert(&mut self, address: Address) -> bool {
+        self.trie.add(Leaf::new(address, Empty)).is_ok()
+    }
+
+    /// Returns true if `address` is a member of the set.
+    pub fn contains(&self, address: &Address) -> bool {
+        self.trie.contains(address)
+    }
+
+    /// Returns an iterator over the members of the set in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = Address> + '_ {
+        self.trie.iter().map(|(address, _)| address)
+    }
+
+    /// Returns a new set containing every address that is a member of
+    /// `self`, `other`, or both.
+    pub fn union(&self, other: &TrieSet) -> TrieSet {
+        TrieSet {
+            trie: self.trie.union(&other.trie),
+        }
+    }
+
+    /// Returns a new set containing only the addresses that are members
+    /// of both `self` and `other`.
+    pub fn intersection(&self, other: &TrieSet) -> TrieSet {
+        TrieSet {
+            trie: self.trie.intersection(&other.trie),
+        }
+    }
+
+    /// Returns a new set containing the addresses that are members of
+    /// `self` but not of `other`.
+    pub fn difference(&self, other: &TrieSet) -> TrieSet {
+        TrieSet {
+            trie: self.trie.difference(&other.trie),
+        }
+    }
+
+    /// Returns the `Trie` underpinning the set, e.g. to read its hashes.
+    pub fn as_trie(&self) -> &Trie<Empty> {
+        &self.trie
+    }
+}
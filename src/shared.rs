@@ -0,0 +1,138 @@
+use crate::hash::{Hasher, Sha256Algorithm};
+use crate::node::{Address, Leaf, Node, RootHash};
+use crate::proof::Proof;
+use crate::trie::{InvalidInsertError, Trie};
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A `Trie` that can be read and written from many threads at once, e.g. to
+/// serve proof requests while new blocks are being committed. Clones of a
+/// `SharedTrie` are handles to the same `Trie`.
+///
+/// Every method takes the lock for the duration of the call only. The read
+/// methods (`get_by_address`, `contains`, `root_hash`, `prove` and
+/// `snapshot`) share a read lock, so any number of them run concurrently,
+/// while the write methods (`add` and `remove`) take the write lock, which
+/// waits for the readers holding the lock to finish and blocks new ones
+/// until the `Trie` and its hashes are updated. A reader therefore never sees
+/// a half applied mutation, but two consecutive reads may straddle a write,
+/// so a `Proof` should be verified against the `root_hash` of a `snapshot`
+/// taken once rather than against a separate call to `root_hash`.
+///
+/// # Panics
+///
+/// Every method panics if a thread panicked while holding the write lock,
+/// as the `Trie` may then have been left half mutated.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::shared::SharedTrie;
+/// use mmpt::node::Leaf;
+/// use std::thread;
+///
+/// let trie: SharedTrie<String> = SharedTrie::default();
+/// let writer = trie.clone();
+/// thread::spawn(move || {
+///     writer.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+/// })
+/// .join()
+/// .unwrap();
+///
+/// let mut snapshot = trie.snapshot();
+/// let proof = snapshot.prove(&[0u8; 32]).unwrap();
+/// assert!(proof.verify(&snapshot.root_hash(), &[0u8; 32], &"Some Data".to_string()));
+/// ```
+#[derive(Debug)]
+pub struct SharedTrie<P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    trie: Arc<RwLock<Trie<P, H, N>>>,
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> SharedTrie<P, H, N> {
+    /// Wraps `trie` so that it can be shared between threads.
+    pub fn new(trie: Trie<P, H, N>) -> SharedTrie<P, H, N> {
+        SharedTrie {
+            trie: Arc::new(RwLock::new(trie)),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Trie<P, H, N>> {
+        self.trie.read().expect("a writer panicked while holding the lock")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Trie<P, H, N>> {
+        self.trie.write().expect("a writer panicked while holding the lock")
+    }
+
+    /// Returns the node at `address` under a read lock, see
+    /// `Trie::get_by_address`.
+    pub fn get_by_address(&self, address: &Address<N>) -> Node<P, H, N> {
+        self.read().get_by_address(address)
+    }
+
+    /// Returns true if there is a `Leaf` at `address`, under a read lock.
+    pub fn contains(&self, address: &Address<N>) -> bool {
+        self.read().contains(address)
+    }
+
+    /// Returns the hash of the `Root` under a read lock, or under the write
+    /// lock if an `add` left it to be rehashed, so that it is cached for the
+    /// readers after it, see `Trie::root_hash`.
+    pub fn root_hash(&self) -> RootHash<H> {
+        {
+            let trie = self.read();
+            if !trie.root.get_next_ref().is_dirty() {
+                return trie.root.get_hash();
+            }
+        }
+        self.write().root_hash()
+    }
+
+    /// Builds a `Proof` for the `Leaf` at `address` under a read lock, see
+    /// `Trie::prove`.
+    pub fn prove(&self, address: &Address<N>) -> Option<Proof<H>> {
+        self.read().prove(address)
+    }
+
+    /// Returns a copy of the `Trie` as it is now, taken under a read lock in
+    /// constant time, see `Trie::snapshot`. Later writes don't affect it, so
+    /// several reads made through it are consistent with one another.
+    pub fn snapshot(&self) -> Trie<P, H, N> {
+        self.read().snapshot()
+    }
+
+    /// Adds `leaf` under the write lock, see `Trie::add`.
+    pub fn add(&self, leaf: Leaf<P, H, N>) -> Result<(), InvalidInsertError> {
+        self.write().add(leaf)
+    }
+
+    /// Removes the `Leaf` at `address` under the write lock, see
+    /// `Trie::remove`.
+    pub fn remove(&self, address: &Address<N>) -> Option<Leaf<P, H, N>> {
+        self.write().remove(address)
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Clone for SharedTrie<P, H, N> {
+    fn clone(&self) -> Self {
+        SharedTrie {
+            trie: Arc::clone(&self.trie),
+        }
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Default for SharedTrie<P, H, N> {
+    fn default() -> Self {
+        SharedTrie::new(Trie::with_hasher())
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> From<Trie<P, H, N>> for SharedTrie<P, H, N> {
+    fn from(trie: Trie<P, H, N>) -> SharedTrie<P, H, N> {
+        SharedTrie::new(trie)
+    }
+}
@@ -0,0 +1,240 @@
+use crate::hash::{Hasher, Sha256Algorithm};
+use crate::node::{Address, RootHash};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+/// The depth of a `SparseMerkleTree`, i.e. the number of bits in an `Address`.
+pub const DEPTH: usize = 256;
+
+/// A Sparse Merkle Tree: a binary Merkle tree of fixed depth with a leaf for
+/// every possible `Address`, in which the bits of an address, most
+/// significant first, select the path from the root down to its leaf.
+///
+/// Every leaf holds a value, the default one unless `update` set another,
+/// so proving that an address holds no value is a regular `SmtProof` of
+/// the default value. The hash of a leaf is the hash of its serialized
+/// payload, the default value being the empty payload, and the hash of an
+/// inner node that of its children concatenated, see `Hasher::concat_and_hash`.
+/// A subtree holding only default values therefore has a hash depending on
+/// its height only, which is computed once, so only the nodes above the
+/// leaves that were set are stored, and proofs leave out the default hashes.
+///
+/// Unlike a `Trie`, whose depth depends on how many leaves share a prefix,
+/// every proof covers all 256 levels, and an update rehashes all of them.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::smt::SparseMerkleTree;
+///
+/// let mut tree: SparseMerkleTree<String> = SparseMerkleTree::new();
+/// tree.update([1u8; 32], "Some Data".to_string());
+///
+/// let root = tree.root();
+/// assert!(tree.prove(&[1u8; 32]).verify(&root, &[1u8; 32], Some(&"Some Data".to_string())));
+/// assert!(tree.prove(&[2u8; 32]).verify::<String>(&root, &[2u8; 32], None));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SparseMerkleTree<P, H = Sha256Algorithm>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    leaves: BTreeMap<Address, P>,
+    /// The hashes of the nodes differing from the default hash at their
+    /// height, keyed by height and by the address of their leftmost leaf.
+    nodes: BTreeMap<(usize, Address), RootHash<H>>,
+    /// The hash of a subtree of default values at each height, leaves first.
+    defaults: Vec<RootHash<H>>,
+}
+
+/// A Merkle proof of the value at an `Address` in a `SparseMerkleTree`, as
+/// returned by `SparseMerkleTree::prove`. Only the siblings that differ from
+/// the default hash at their height are held, bit `i` of `non_default`, most
+/// significant bit of the first byte first, telling whether the sibling at
+/// height `i` is among them.
+#[derive(Clone, Debug)]
+pub struct SmtProof<H = Sha256Algorithm>
+where
+    H: Hasher,
+{
+    pub non_default: [u8; DEPTH / 8],
+    pub siblings: Vec<RootHash<H>>,
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>> SparseMerkleTree<P, Sha256Algorithm> {
+    /// Creates a new `SparseMerkleTree` holding the default value at every
+    /// address, hashed with `Sha256Algorithm`.
+    pub fn new() -> SparseMerkleTree<P, Sha256Algorithm> {
+        SparseMerkleTree::with_hasher()
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher> SparseMerkleTree<P, H> {
+    /// Creates a new `SparseMerkleTree` holding the default value at every
+    /// address, hashed with `H`.
+    pub fn with_hasher() -> SparseMerkleTree<P, H> {
+        SparseMerkleTree {
+            leaves: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            defaults: default_hashes::<H>(),
+        }
+    }
+
+    /// Sets the value at `key` to `value`, rehashing every node on its path.
+    /// A `value` serializing to no bytes is the default value, and resets
+    /// the leaf.
+    pub fn update(&mut self, key: Address, value: P) {
+        let bytes: Vec<u8> = value.clone().into();
+        let mut hash = if bytes.is_empty() {
+            self.leaves.remove(&key);
+            self.defaults[0]
+        } else {
+            self.leaves.insert(key, value);
+            H::hash(&bytes)
+        };
+        for height in 0..DEPTH {
+            self.set_node(height, subtree_of(&key, height), hash);
+            let sibling = self.node(height, &sibling_of(&key, height));
+            hash = if goes_right(&key, height) {
+                H::concat_and_hash(&sibling, Some(&hash))
+            } else {
+                H::concat_and_hash(&hash, Some(&sibling))
+            };
+        }
+        self.set_node(DEPTH, [0u8; 32], hash);
+    }
+
+    /// Returns the value at `key`, `None` if it holds the default value.
+    pub fn get(&self, key: &Address) -> Option<&P> {
+        self.leaves.get(key)
+    }
+
+    /// Returns the number of addresses holding a value other than the default.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns true if every address holds the default value.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Returns the hash of the root, committing to the value at every address.
+    pub fn root(&self) -> RootHash<H> {
+        self.node(DEPTH, &[0u8; 32])
+    }
+
+    /// Builds the `SmtProof` of the value at `key`, which is the default
+    /// value, and so proves `key` holds none, if `get` returns `None`.
+    pub fn prove(&self, key: &Address) -> SmtProof<H> {
+        let mut proof = SmtProof {
+            non_default: [0u8; DEPTH / 8],
+            siblings: Vec::new(),
+        };
+        for height in 0..DEPTH {
+            let sibling = sibling_of(key, height);
+            if let Some(hash) = self.nodes.get(&(height, sibling)) {
+                proof.non_default[height / 8] |= 0x80 >> (height % 8);
+                proof.siblings.push(*hash);
+            }
+        }
+        proof
+    }
+
+    /// Returns the hash of the node at `height` whose leftmost leaf is at
+    /// `subtree`, the default hash at that height if it isn't stored.
+    fn node(&self, height: usize, subtree: &Address) -> RootHash<H> {
+        self.nodes
+            .get(&(height, *subtree))
+            .copied()
+            .unwrap_or(self.defaults[height])
+    }
+
+    /// Stores the `hash` of the node at `height` whose leftmost leaf is at
+    /// `subtree`, or drops it if it is the default hash at that height.
+    fn set_node(&mut self, height: usize, subtree: Address, hash: RootHash<H>) {
+        if hash == self.defaults[height] {
+            self.nodes.remove(&(height, subtree));
+        } else {
+            self.nodes.insert((height, subtree), hash);
+        }
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>> Default for SparseMerkleTree<P, Sha256Algorithm> {
+    fn default() -> Self {
+        SparseMerkleTree::new()
+    }
+}
+
+impl<H: Hasher> SmtProof<H> {
+    /// Returns true if this `SmtProof` shows that `key` holds `value` in the
+    /// `SparseMerkleTree` committed to by `root`, where a `value` of `None`
+    /// stands for the default value, i.e. shows `key` holds no value.
+    pub fn verify<P>(&self, root: &RootHash<H>, key: &Address, value: Option<&P>) -> bool
+    where
+        P: Clone + Into<Vec<u8>>,
+    {
+        let bytes: Vec<u8> = value.map(|value| value.clone().into()).unwrap_or_default();
+        let mut default = H::hash(&[]);
+        let mut hash = if bytes.is_empty() { default } else { H::hash(&bytes) };
+        let mut siblings = self.siblings.iter();
+        for height in 0..DEPTH {
+            let sibling = if self.non_default[height / 8] & (0x80 >> (height % 8)) != 0 {
+                match siblings.next() {
+                    Some(sibling) => *sibling,
+                    None => return false,
+                }
+            } else {
+                default
+            };
+            hash = if goes_right(key, height) {
+                H::concat_and_hash(&sibling, Some(&hash))
+            } else {
+                H::concat_and_hash(&hash, Some(&sibling))
+            };
+            default = H::concat_and_hash(&default, Some(&default));
+        }
+        siblings.next().is_none() && hash == *root
+    }
+}
+
+/// Returns the hash of a subtree of default values at each height from the
+/// leaves, `0`, up to the root, `DEPTH`.
+fn default_hashes<H: Hasher>() -> Vec<RootHash<H>> {
+    let mut defaults = Vec::with_capacity(DEPTH + 1);
+    defaults.push(H::hash(&[]));
+    for height in 0..DEPTH {
+        let below = defaults[height];
+        defaults.push(H::concat_and_hash(&below, Some(&below)));
+    }
+    defaults
+}
+
+/// Returns true if the node at `height` on the path of `key` is the right
+/// child of its parent, i.e. the bit of `key` selecting it is set.
+fn goes_right(key: &Address, height: usize) -> bool {
+    let bit = DEPTH - 1 - height;
+    key[bit / 8] & (0x80 >> (bit % 8)) != 0
+}
+
+/// Returns the address of the leftmost leaf beneath the node at `height` on
+/// the path of `key`, i.e. `key` with its lowest `height` bits cleared.
+fn subtree_of(key: &Address, height: usize) -> Address {
+    let mut subtree = *key;
+    for bit in DEPTH - height..DEPTH {
+        subtree[bit / 8] &= !(0x80 >> (bit % 8));
+    }
+    subtree
+}
+
+/// Returns the address of the leftmost leaf beneath the sibling of the node
+/// at `height` on the path of `key`.
+fn sibling_of(key: &Address, height: usize) -> Address {
+    let mut sibling = subtree_of(key, height);
+    let bit = DEPTH - 1 - height;
+    sibling[bit / 8] ^= 0x80 >> (bit % 8);
+    sibling
+}
@@ -0,0 +1,182 @@
+use crate::hash::{Hasher, Sha256Algorithm};
+use crate::node::RootHash;
+#[cfg(feature = "std")]
+use crate::node::{Address, DecodeError};
+#[cfg(feature = "std")]
+use crate::trie::Trie;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// A persistence backend holding the encoded nodes of a `Trie` keyed by their
+/// hashes, see `Trie::commit` and `Trie::load`. Since every node is keyed by
+/// its hash, a `NodeStore` may be shared between any number of tries, which
+/// then store the nodes they have in common only once.
+pub trait NodeStore<H = Sha256Algorithm>
+where
+    H: Hasher,
+{
+    /// Returns the encoded node stored under `hash`, if any.
+    fn get(&self, hash: &RootHash<H>) -> Option<Vec<u8>>;
+
+    /// Stores the encoded node `bytes` under its `hash`.
+    fn put(&mut self, hash: RootHash<H>, bytes: Vec<u8>);
+}
+
+/// An in-memory `NodeStore` backed by a `HashMap`, available with the `std`
+/// feature.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::store::HashMapStore;
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let mut store = HashMapStore::new();
+/// let mut trie: Trie<Vec<u8>> = Trie::default();
+/// trie.add(Leaf::new([0u8; 32], b"Some Data".to_vec())).unwrap();
+/// let root = trie.commit(&mut store);
+///
+/// let loaded: Trie<Vec<u8>> = Trie::load(root, &store).unwrap();
+/// assert_eq!(loaded, trie);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct HashMapStore {
+    nodes: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+#[cfg(feature = "std")]
+impl HashMapStore {
+    /// Creates a new, empty `HashMapStore`
+    pub fn new() -> HashMapStore {
+        HashMapStore::default()
+    }
+
+    /// Creates a new, empty `HashMapStore` with room for `nodes` records
+    /// before it reallocates. Committing a `Trie` of `n` leaves stores `n`
+    /// leaf records, plus one record per `Branch` and per `Fork`, which is
+    /// at most `3 * n` records in all.
+    pub fn with_capacity(nodes: usize) -> HashMapStore {
+        HashMapStore {
+            nodes: HashMap::with_capacity(nodes),
+        }
+    }
+
+    /// Returns the number of nodes stored.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if no nodes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> NodeStore<H> for HashMapStore {
+    fn get(&self, hash: &RootHash<H>) -> Option<Vec<u8>> {
+        self.nodes.get(hash.as_ref()).cloned()
+    }
+
+    fn put(&mut self, hash: RootHash<H>, bytes: Vec<u8>) {
+        self.nodes.insert(hash.as_ref().to_vec(), bytes);
+    }
+}
+
+/// A `NodeStore` reading through to another one, e.g. one backed by disk,
+/// keeping every record read in memory, so reading it again doesn't hit
+/// the underlying store. Records written go to both. Available with the
+/// `std` feature.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::store::{CachedStore, HashMapStore};
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let mut store = HashMapStore::new();
+/// let mut trie: Trie<Vec<u8>> = Trie::default();
+/// trie.add(Leaf::new([0u8; 32], b"Some Data".to_vec())).unwrap();
+/// let root = trie.commit(&mut store);
+///
+/// let cached: CachedStore<_> = CachedStore::new(store);
+/// cached.prefetch(&root, &[[0u8; 32]]).unwrap();
+/// assert_eq!(Trie::<Vec<u8>>::get_stored(root, &cached, &[0u8; 32]), Some(b"Some Data".to_vec()));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct CachedStore<S, H = Sha256Algorithm>
+where
+    H: Hasher,
+{
+    store: S,
+    cache: RefCell<HashMapStore>,
+    hasher: PhantomData<H>,
+}
+
+#[cfg(feature = "std")]
+impl<S: NodeStore<H>, H: Hasher> CachedStore<S, H> {
+    /// Wraps `store` in a `CachedStore` with nothing cached yet.
+    pub fn new(store: S) -> CachedStore<S, H> {
+        CachedStore {
+            store,
+            cache: RefCell::new(HashMapStore::new()),
+            hasher: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the underlying store, dropping the records held in memory.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Returns the number of records held in memory.
+    pub fn cached(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    /// Loads every record on the paths to `addresses` in the `Trie`
+    /// committed under the `Root` hash `root` into memory, e.g. for a block
+    /// to warm up its access list before executing, so that looking them up
+    /// afterwards, see `Trie::try_get`, reads nothing from the underlying
+    /// store. Records shared by several paths are read once. Returns the
+    /// `DecodeError` of a record that is missing or tampered.
+    pub fn prefetch<const N: usize>(&self, root: &RootHash<H>, addresses: &[Address<N>]) -> Result<(), DecodeError> {
+        for address in addresses {
+            // Leaves hash their serialized payload, so reading them as bytes
+            // walks the same records whatever the payload type.
+            Trie::<Vec<u8>, H, N>::try_get(*root, self, address)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher, S: NodeStore<H>> NodeStore<H> for CachedStore<S, H> {
+    fn get(&self, hash: &RootHash<H>) -> Option<Vec<u8>> {
+        if let Some(bytes) = NodeStore::<H>::get(&*self.cache.borrow(), hash) {
+            return Some(bytes);
+        }
+        let bytes = self.store.get(hash)?;
+        NodeStore::<H>::put(&mut *self.cache.borrow_mut(), *hash, bytes.clone());
+        Some(bytes)
+    }
+
+    fn put(&mut self, hash: RootHash<H>, bytes: Vec<u8>) {
+        NodeStore::<H>::put(self.cache.get_mut(), hash, bytes.clone());
+        self.store.put(hash, bytes);
+    }
+}
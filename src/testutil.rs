@@ -0,0 +1,57 @@
+use crate::node::{Address, Leaf};
+use crate::trie::Trie;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+
+/// Returns `n` distinct, uniformly random addresses.
+pub fn random_addresses<R: Rng + ?Sized>(n: usize, rng: &mut R) -> Vec<Address> {
+    clustered_addresses(n, 0, rng)
+}
+
+/// Returns `n` distinct random addresses that all share the same random
+/// prefix of `shared_prefix_len` bytes, forcing a chain of `Fork` nodes.
+///
+/// Panics if `n` distinct addresses can't fit after the shared prefix.
+pub fn clustered_addresses<R: Rng + ?Sized>(
+    n: usize,
+    shared_prefix_len: usize,
+    rng: &mut R,
+) -> Vec<Address> {
+    assert!(shared_prefix_len < 32, "the shared prefix must leave a byte free");
+    let free_bytes = (32 - shared_prefix_len) as u32;
+    assert!(
+        free_bytes >= 8 || (n as u128) <= 256u128.pow(free_bytes),
+        "not enough free bytes for {} distinct addresses",
+        n
+    );
+
+    let mut prefix = [0u8; 32];
+    rng.fill(&mut prefix[..shared_prefix_len]);
+
+    let mut seen = HashSet::with_capacity(n);
+    let mut addresses = Vec::with_capacity(n);
+    while addresses.len() < n {
+        let mut address = prefix;
+        rng.fill(&mut address[shared_prefix_len..]);
+        if seen.insert(address) {
+            addresses.push(address);
+        }
+    }
+    addresses
+}
+
+/// Builds a `Trie` of `n` leaves at random addresses. The addresses are
+/// seeded from `n`, so the same `n` always yields the same `Trie`.
+pub fn build_trie(n: usize) -> Trie<String> {
+    let mut rng = StdRng::seed_from_u64(n as u64);
+    let mut trie = Trie::default();
+    random_addresses(n, &mut rng)
+        .into_iter()
+        .enumerate()
+        .for_each(|(i, address)| {
+            trie.add(Leaf::new(address, format!("Leaf {}", i)))
+                .expect("the random addresses are distinct")
+        });
+    trie
+}
@@ -1,57 +1,226 @@
-use crate::node::{Nibble, Node, Root, Leaf, BranchIntoIter};
-use crate::layer::Layer;
+use crate::node::{abbreviate, path_of, to_hex, Address, Branch, DecodeError, Nibble, Node, NodeRef, Root, RootHash, Leaf, BranchIntoIter};
+use crate::hash::{Hasher, Sha256Algorithm};
+use crate::proof::{ExclusionProof, Proof, RangeProof};
+use crate::store::NodeStore;
+use crate::entry::{Entry, PayloadMut};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::Display;
-use std::fmt::Debug;
-use std::cmp::{PartialEq, Eq};
+use core::fmt::Display;
+use core::fmt::Debug;
+use core::cmp::{Ordering, PartialEq, Eq};
+use alloc::collections::{btree_map, BTreeMap};
 
-#[derive(Debug)]
-pub struct InvalidInsertError;
+/// Errors returned when a `Leaf` can't be inserted into the `Trie`, or its
+/// payload can't be updated.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InvalidInsertError {
+    /// There already is a `Leaf` at the address, see `update` to replace its
+    /// payload instead.
+    DuplicateAddress,
+    /// The address can't be placed where it was asked to be, i.e. the layer
+    /// lies beyond the address or its path runs into another `Leaf`.
+    InvalidAddress,
+    /// There is no `Leaf` at the address to update.
+    NotFound,
+    /// The leaves given to `from_sorted` aren't in ascending order of their
+    /// addresses.
+    Unsorted,
+}
 
 impl Display for InvalidInsertError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Unable to insert the node into the Trie")
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            InvalidInsertError::DuplicateAddress => write!(f, "A leaf already exists at the address in the Trie"),
+            InvalidInsertError::InvalidAddress => write!(f, "The address can't be placed at the requested layer of the Trie"),
+            InvalidInsertError::NotFound => write!(f, "No leaf exists at the address in the Trie"),
+            InvalidInsertError::Unsorted => write!(f, "The leaves aren't sorted by address"),
+        }
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for InvalidInsertError {
     fn description(&self) -> &str {
-        "Unable to insert the node into the Trie"
+        match self {
+            InvalidInsertError::DuplicateAddress => "A leaf already exists at the address in the Trie",
+            InvalidInsertError::InvalidAddress => "The address can't be placed at the requested layer of the Trie",
+            InvalidInsertError::NotFound => "No leaf exists at the address in the Trie",
+            InvalidInsertError::Unsorted => "The leaves aren't sorted by address",
+        }
+    }
+}
+
+/// Errors returned by `Trie` operations that require an existing `Leaf`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrieError {
+    /// There is no `Leaf` at the requested address.
+    NotFound,
+}
+
+impl Display for TrieError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            TrieError::NotFound => write!(f, "No leaf exists at the address in the Trie"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TrieError {
+    fn description(&self) -> &str {
+        match self {
+            TrieError::NotFound => "No leaf exists at the address in the Trie",
+        }
     }
 }
 
+/// A Merkle Patricia Trie of `Leaf` nodes carrying payloads of type `P`,
+/// whose nodes are hashed with `H`, `Sha256Algorithm` unless specified, and
+/// whose leaves are addressed by `N` bytes, 32 unless specified, e.g.
+/// `Trie<P, Sha256Algorithm, 20>` for 20 byte addresses. `N` must lie
+/// within `1..=32`, as each byte of an address takes a `Layer` of its own.
+///
+/// Branches are shared between clones of a `Trie` through `Arc`s and copied
+/// on write, see `snapshot`. There is no `Rc` or interior mutability, so
+/// `Trie<P, H>` is `Send` and `Sync` whenever `P` and `H` are, and can be
+/// shared across threads behind the usual `Arc`/`RwLock` wrappers.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "P: serde::Serialize, H::Hash: serde::Serialize",
+    deserialize = "P: serde::Deserialize<'de>, H::Hash: serde::Deserialize<'de>"
+)))]
+#[derive(Clone, Debug)]
+pub struct Trie<P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    pub root: Box<Root<P, H, N>>,
+}
+
+/// The variant of the `Node` found at a step of a lookup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeVariant {
+    Data,
+    Fork,
+    None,
+}
+
+/// A single decision made while looking up an address: the `Node` variant
+/// found at `nibble` in the `Branch` at `layer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupStep {
+    pub layer: usize,
+    pub nibble: Nibble,
+    pub node: NodeVariant,
+}
+
+/// How a lookup ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LookupOutcome<const N: usize = 32> {
+    /// A `Leaf` with exactly the requested address was reached.
+    Found,
+    /// An unoccupied `Nibble` was reached.
+    NotFound,
+    /// A `Leaf` sharing the path so far, but with a different address
+    /// (the one contained), was reached.
+    DivergedAtLeaf(Address<N>),
+}
+
+/// The addresses at which two tries differ, as returned by `Trie::diff`,
+/// each in ascending order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrieDiff<const N: usize = 32> {
+    /// Addresses with a `Leaf` in the `Trie` diffed, but not the other one.
+    pub only_in_self: Vec<Address<N>>,
+    /// Addresses with a `Leaf` in the other `Trie`, but not the one diffed.
+    pub only_in_other: Vec<Address<N>>,
+    /// Addresses with a `Leaf` in both tries, carrying different payloads.
+    pub changed: Vec<Address<N>>,
+}
+
+impl<const N: usize> TrieDiff<N> {
+    /// Returns true if the tries diffed hold the same leaves.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The sequence of decisions made while looking up an address, as returned
+/// by `Trie::explain`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupTrace<const N: usize = 32> {
+    pub steps: Vec<LookupStep>,
+    pub outcome: LookupOutcome<N>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TrieIntoIter<P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    branches: Vec<BranchIntoIter<P, H, N>>,
+}
+
+/// A borrowing iterator over the leaves of a `Trie`, yielding each address
+/// along with a reference to its payload in ascending address order.
+#[derive(Clone, Debug)]
+pub struct Iter<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    branches: Vec<btree_map::Iter<'a, Nibble, Node<P, H, N>>>,
+}
+
+/// A borrowing iterator over the leaves of a `Trie` which rebuilds each
+/// address from the path taken to its `Leaf`, see `Trie::iter_addressed`.
 #[derive(Clone, Debug)]
-pub struct Trie<P> 
+pub struct Addressed<'a, P, H = Sha256Algorithm, const N: usize = 32>
 where
-    P: Clone + Debug + Into<Vec<u8>>
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
-    pub root: Box<Root<P>>,
+    branches: Vec<btree_map::Iter<'a, Nibble, Node<P, H, N>>>,
+    path: Vec<Nibble>,
 }
 
+/// A borrowing iterator over the `Fork` nodes of a `Trie`, see `Trie::iter_forks`.
 #[derive(Clone, Debug)]
-pub struct TrieIntoIter<P> 
+pub struct Forks<'a, P, H = Sha256Algorithm, const N: usize = 32>
 where
-    P: Clone + Debug + Into<Vec<u8>>
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
 {
-    curr_branch: BranchIntoIter<P>,
-    layer: Layer,
-    branches: Vec<BranchIntoIter<P>>,
+    branches: Vec<btree_map::Iter<'a, Nibble, Node<P, H, N>>>,
+    path: Vec<Nibble>,
+}
 
+/// A borrowing iterator over the leaves of a `Trie`, yielding a reference to
+/// each `Leaf` in ascending address order, see `IntoIterator for &Trie`.
+#[derive(Clone, Debug)]
+pub struct TrieIterator<'a, P, H = Sha256Algorithm, const N: usize = 32>
+where
+    P: Clone + Debug + Into<Vec<u8>>,
+    H: Hasher,
+{
+    branches: Vec<btree_map::Iter<'a, Nibble, Node<P, H, N>>>,
 }
 
-// TODO: Implement IntoIterator and Iterator for "borrowed" & mutably "borrowed" Tries.
-// pub struct TrieIterator<P> 
-// where
-//     P: Clone + Debug + Into<Vec<u8>>
-// {
-//     branch: Branch<P>,
-//     layer: Layer,
-//     layer_indices: [u8; 32],
-// }
+// TODO: Implement IntoIterator for `&mut Trie`, i.e. an iterator yielding a
+// mutable reference to each payload. Every `Branch` above a payload it hands
+// out has to be rehashed once the caller is done with it, as `PayloadMut`
+// does for a single `Leaf`.
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Trie<P> {
+impl<P: Clone + Debug + Into<Vec<u8>>, const N: usize> Trie<P, Sha256Algorithm, N> {
     /// Creates a new blank trie with a Root (which is initialized with
-    /// a Branch node)
+    /// a Branch node), hashed with `Sha256Algorithm`.
     /// 
     /// # Example
     /// ```
@@ -60,14 +229,97 @@ impl<P: Clone + Debug + Into<Vec<u8>>> Trie<P> {
     /// let trie: Trie<String> = Trie::default();
     /// assert_eq!(trie.root.get_next().get_layer(), 0u8);
     /// ```
-    pub fn new() -> Trie<P> {
-        let root: Box<Root<P>> = Box::new(Root::default());
+    pub fn new() -> Trie<P, Sha256Algorithm, N> {
+        Trie::with_hasher()
+    }
+}
+
+// There is deliberately no `Trie::with_capacity`. Each `Branch` keeps its
+// occupied slots in a `BTreeMap`, which allocates a node at a time and has no
+// capacity to reserve, and which branches a `Leaf` ends up in depends on the
+// addresses, so a leaf count alone can't pre-size anything. Bulk loads should
+// use `add_many` instead, and `HashMapStore::with_capacity` pre-sizes the
+// store a `Trie` is committed to.
+//
+// Nor is there a `Trie::clone_into` reusing the allocations of the `Trie`
+// overwritten. `clone` already shares every `Branch` behind an `Arc`, so it
+// allocates nothing but the `Root` box, and the `BTreeMap`s of the overwritten
+// branches can't be recycled node by node anyway, leaving a `clone_into`
+// exactly as costly as `*dst = src.clone()`.
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Trie<P, H, N> {
+    /// Creates a new blank trie whose nodes are hashed with `H` rather
+    /// than `Sha256Algorithm`.
+    ///
+    /// # Example
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::hash::Sha256Algorithm;
+    ///
+    /// let trie: Trie<String, Sha256Algorithm> = Trie::with_hasher();
+    /// assert_eq!(trie, Trie::new());
+    /// ```
+    pub fn with_hasher() -> Trie<P, H, N> {
+        let root: Box<Root<P, H, N>> = Box::default();
         Trie { root }
     }
 
+    /// Returns the hash of the `Root`, i.e. the commitment to the current
+    /// contents of the `Trie`. `add` only marks the branches on the path of
+    /// the new `Leaf` dirty, so the first call after it rehashes those, and
+    /// only those, bottom up and caches their hashes. Later calls, until the
+    /// next mutation, hash nothing. `Root::get_hash` gives the same hash
+    /// from a shared reference, without caching it.
+    ///
+    /// # Example
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let empty = trie.root_hash();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// assert_ne!(trie.root_hash(), empty);
+    /// assert_eq!(trie.root_hash(), trie.root.get_hash());
+    /// ```
+    pub fn root_hash(&mut self) -> RootHash<H> {
+        if self.root.get_next_ref().is_dirty() {
+            self.root.rehash();
+        }
+        self.root.get_hash()
+    }
+
+    /// Returns a copy of the `Trie` in constant time, e.g. to checkpoint
+    /// the state between blocks. The snapshot shares every `Branch` with
+    /// the `Trie`, and mutating either of them afterwards copies only the
+    /// branches on the path of the mutated `Leaf` before changing them,
+    /// leaving the other unaffected. The same holds for `clone`.
+    ///
+    /// The trade-off is that the first mutation of a path after a snapshot
+    /// clones each `Branch` on it, including the payloads of the leaves
+    /// held directly in those branches, even when only a single payload
+    /// changes.
+    ///
+    /// # Example
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// let snapshot = trie.snapshot();
+    ///
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    /// assert_eq!(snapshot.len(), 1);
+    /// assert_eq!(trie.len(), 2);
+    /// ```
+    pub fn snapshot(&self) -> Trie<P, H, N> {
+        self.clone()
+    }
+
     /// Traverses the trie, every time there's a Fork node discovered
-    /// `.get_next()` is called on the Fork node to get the next branch
-    /// and check the next nibble for an entry.
+    /// the next branch is borrowed from the Fork node
+    /// to check the next nibble for an entry.
     /// However if a data node is discovered, it is returned since a Data node
     /// is the "end of the road" so to speak. If a None node is discovered then
     /// the last Fork node discovered is returned, if there was no Fork node discovered
@@ -95,29 +347,41 @@ impl<P: Clone + Debug + Into<Vec<u8>>> Trie<P> {
     /// assert!(node_type_correct);
     /// 
     /// ```
-    pub fn traverse(&self, nibbles: &[u8]) -> (usize, Option<Nibble>, Node<P>) {
-        let mut branch = self.root.get_next();
+    pub fn traverse(&self, nibbles: &[u8]) -> (usize, Option<Nibble>, Node<P, H, N>) {
+        let (index, nibble, node) = self.traverse_ref(nibbles);
+        (index, nibble, node.clone())
+    }
+
+    /// Traverses the trie just like `traverse`, but returns a reference to
+    /// the `Node` found rather than a clone of it, so nothing is cloned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::node::Leaf;
+    /// use mmpt::trie::Trie;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([1u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let (index, nibble, node) = trie.traverse_ref(&[1u8, 1u8]);
+    /// assert_eq!((index, nibble), (0, Some(1u8)));
+    /// assert_eq!(node.as_data().map(|leaf| leaf.get_address()), Some([1u8; 32]));
+    /// ```
+    pub fn traverse_ref(&self, nibbles: &[u8]) -> (usize, Option<Nibble>, &Node<P, H, N>) {
+        let mut branch = self.root.get_next_ref();
         let mut iter = nibbles.iter().enumerate();
-        let mut data: Node<P> = Node::None;
+        let mut data: &Node<P, H, N> = &Node::None;
         loop {
             match iter.next() {
                 Some((index, nibble)) => 
-                    match branch.get(nibble) {
-                        Node::Fork { fork, hash } => {
-                            branch = fork.get_next();
-                            data = Node::Fork { fork, hash };
+                    match branch.get_ref(nibble) {
+                        node @ Node::Fork { fork, .. } => {
+                            branch = fork.get_next_ref();
+                            data = node;
                         }
                         Node::None => return (index, Some(*nibble), data),
-                        Node::Data { data, hash } => {
-                            return (
-                                index,
-                                Some(*nibble),
-                                Node::Data {
-                                    data: data.clone(),
-                                    hash: hash.clone(),
-                                },
-                            )
-                        }
+                        node @ Node::Data { .. } => return (index, Some(*nibble), node),
                     },
                 None => {
                     if let Some(nibble) = nibbles.last() {
@@ -130,9 +394,63 @@ impl<P: Clone + Debug + Into<Vec<u8>>> Trie<P> {
         }
     }
 
+    /// Looks up `address` and returns every decision made on the way, i.e. the
+    /// `Layer`, `Nibble` and `Node` variant at each `Branch` visited, along with
+    /// how the lookup ended. Unlike `traverse`, which returns the last `Fork`
+    /// on a partial match, the trace makes it clear why a lookup succeeded or
+    /// failed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::{LookupOutcome, NodeVariant, Trie};
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let trace = trie.explain(&[0u8; 32]);
+    /// assert_eq!(trace.steps.len(), 1);
+    /// assert_eq!(trace.steps[0].node, NodeVariant::Data);
+    /// assert_eq!(trace.outcome, LookupOutcome::Found);
+    ///
+    /// let mut address = [0u8; 32];
+    /// address[31] = 1;
+    /// assert_eq!(trie.explain(&address).outcome, LookupOutcome::DivergedAtLeaf([0u8; 32]));
+    /// ```
+    pub fn explain(&self, address: &Address<N>) -> LookupTrace<N> {
+        let mut branch = self.root.get_next_ref();
+        let mut steps = vec![];
+        for (layer, nibble) in path_of(address).into_iter().enumerate() {
+            let node = branch.get_ref(&nibble);
+            let (variant, outcome) = match node {
+                Node::Fork { fork, .. } => {
+                    steps.push(LookupStep { layer, nibble, node: NodeVariant::Fork });
+                    branch = fork.get_next_ref();
+                    continue;
+                }
+                Node::None => (NodeVariant::None, LookupOutcome::NotFound),
+                Node::Data { data, .. } if &data.get_address() == address => {
+                    (NodeVariant::Data, LookupOutcome::Found)
+                }
+                Node::Data { data, .. } => (
+                    NodeVariant::Data,
+                    LookupOutcome::DivergedAtLeaf(data.get_address()),
+                ),
+            };
+            steps.push(LookupStep { layer, nibble, node: variant });
+            return LookupTrace { steps, outcome };
+        }
+        LookupTrace { steps, outcome: LookupOutcome::NotFound }
+    }
+
     /// Adds a node to the `Trie`, recursively traversing through the `Trie`, starting
     /// with the `Branch` underpinning the `Root` of the `Trie` and, if there is a
     /// conflicting `Leaf` node with a shared `Nibble`, then a new `Fork` is inserted.
+    /// Adding a `Leaf` at an address that is already in the `Trie` leaves the
+    /// `Trie` untouched and returns an `InvalidInsertError::DuplicateAddress`.
+    /// Nothing is hashed beyond the new `Leaf`, the branches on its path are
+    /// left dirty until `root_hash` rehashes them.
     /// 
     /// # Example
     /// 
@@ -144,108 +462,1659 @@ impl<P: Clone + Debug + Into<Vec<u8>>> Trie<P> {
     /// let address = [0u8; 32];
     /// let payload = "Some Data".to_string();
     /// let new_leaf: Leaf<String> = Leaf::new(address, payload);
-    /// trie.add(new_leaf);
+    /// trie.add(new_leaf).unwrap();
     /// ```
-    pub fn add(&mut self, leaf: Leaf<P>) {
+    pub fn add(&mut self, leaf: Leaf<P, H, N>) -> Result<(), InvalidInsertError> {
+        if self.find_leaf(&leaf.get_address()).is_some() {
+            return Err(InvalidInsertError::DuplicateAddress);
+        }
         self.root.get_next_mut().insert(leaf);
+        Ok(())
+    }
+
+    /// Adds every `Leaf` in `leaves` to the `Trie`, deferring the rehashing
+    /// until all of them are in place. Adding N leaves with `add`, reading
+    /// `root_hash` after each, rehashes every `Branch` on the path of each
+    /// `Leaf`, i.e. up to N times for the `Root` `Branch`, while `add_many`
+    /// rehashes each `Branch` it touches exactly once, and the `Root` once.
+    /// The resulting `Trie` is the same.
+    ///
+    /// Returns an `InvalidInsertError::DuplicateAddress`, and adds nothing,
+    /// if any address is already in the `Trie` or appears more than once in
+    /// `leaves`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let leaves: Vec<Leaf<String>> = (0..8u8)
+    ///     .map(|byte| Leaf::new([byte; 32], "Some Data".to_string()))
+    ///     .collect();
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add_many(leaves.clone()).unwrap();
+    ///
+    /// let mut expected: Trie<String> = Trie::default();
+    /// for leaf in leaves {
+    ///     expected.add(leaf).unwrap();
+    /// }
+    /// assert_eq!(trie.root.get_hash(), expected.root.get_hash());
+    /// ```
+    pub fn add_many(&mut self, leaves: impl IntoIterator<Item = Leaf<P, H, N>>) -> Result<(), InvalidInsertError> {
+        let mut leaves: Vec<Leaf<P, H, N>> = leaves.into_iter().collect();
+        leaves.sort_unstable_by_key(|leaf| leaf.get_address());
+        let repeated = leaves
+            .windows(2)
+            .any(|pair| pair[0].get_address() == pair[1].get_address());
+        if repeated || leaves.iter().any(|leaf| self.contains(&leaf.get_address())) {
+            return Err(InvalidInsertError::DuplicateAddress);
+        }
+        self.insert_many(leaves);
+        Ok(())
+    }
+
+    /// Builds a `Trie` from `leaves` sorted by address, e.g. a state snapshot
+    /// read back in order, without sorting them or looking any of them up
+    /// first, see `add_many`. The `Trie` is the same as the one adding them
+    /// one at a time builds.
+    ///
+    /// Returns an `InvalidInsertError::DuplicateAddress` if an address appears
+    /// more than once, and an `InvalidInsertError::Unsorted` if the leaves
+    /// aren't in ascending order otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::{InvalidInsertError, Trie};
+    /// use mmpt::node::Leaf;
+    ///
+    /// let leaves: Vec<Leaf<String>> = (0..8u8)
+    ///     .map(|byte| Leaf::new([byte; 32], "Some Data".to_string()))
+    ///     .collect();
+    ///
+    /// let mut trie = Trie::from_sorted(leaves.clone()).unwrap();
+    /// let mut expected: Trie<String> = leaves.iter().cloned().collect();
+    /// assert_eq!(trie.root_hash(), expected.root_hash());
+    ///
+    /// let reversed = leaves.into_iter().rev().collect();
+    /// assert_eq!(Trie::from_sorted(reversed).unwrap_err(), InvalidInsertError::Unsorted);
+    /// ```
+    pub fn from_sorted(leaves: Vec<Leaf<P, H, N>>) -> Result<Trie<P, H, N>, InvalidInsertError> {
+        check_sorted(&leaves)?;
+        let mut trie = Trie::with_hasher();
+        trie.insert_many(leaves);
+        Ok(trie)
     }
 
-    pub fn get(&self, nibble: &u8) -> Node<P> {
+    /// Adds `leaves`, whose addresses must be distinct and not yet in the
+    /// `Trie`, see `add_many`.
+    fn insert_many(&mut self, leaves: Vec<Leaf<P, H, N>>) {
+        if leaves.is_empty() {
+            return;
+        }
+        self.root.get_next_mut().insert_many(leaves);
+        self.root.rehash();
+        debug_assert!(self.canonical_error().is_none(), "{:?}", self.canonical_error());
+    }
+
+    pub fn get(&self, nibble: &u8) -> Node<P, H, N> {
         self.root.get(nibble)
     }
+
+    /// Removes the `Leaf` at `address` from the `Trie` and returns it, or
+    /// returns `None` if there is no `Leaf` at that address. When the removal
+    /// leaves a `Fork` with a single `Leaf` beneath it, the `Fork` collapses
+    /// back into that `Leaf`, so the `Trie` ends up exactly as if the removed
+    /// `Leaf` had never been added. Every hash on the path is recomputed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let leaf: Leaf<String> = Leaf::new([0u8; 32], "Some Data".to_string());
+    /// trie.add(leaf.clone()).unwrap();
+    ///
+    /// assert_eq!(trie.remove(&[0u8; 32]), Some(leaf));
+    /// assert_eq!(trie.remove(&[0u8; 32]), None);
+    /// ```
+    pub fn remove(&mut self, address: &Address<N>) -> Option<Leaf<P, H, N>> {
+        // Looked up first so that a miss neither copies branches shared with
+        // a snapshot nor rehashes anything.
+        self.find_leaf(address)?;
+        let removed = self.root.get_next_mut().remove(address);
+        self.root.rehash();
+        removed.map(|leaf| leaf.at_layer(0))
+    }
+
+    /// Removes the `Leaf` at `address` like `remove`, returning it along with
+    /// an inclusion `Proof` of it against the root hash from just before the
+    /// removal, e.g. for an audit log showing the value existed when it was
+    /// deleted. The `Proof` is built before anything is mutated. Returns
+    /// `None`, removing nothing, if there is no `Leaf` at `address`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// let before = trie.root_hash();
+    /// let (leaf, proof) = trie.remove_with_proof(&[0u8; 32]).unwrap();
+    /// assert!(proof.verify(&before, &[0u8; 32], &leaf.get_payload()));
+    /// assert!(!trie.contains(&[0u8; 32]));
+    /// ```
+    pub fn remove_with_proof(&mut self, address: &Address<N>) -> Option<(Leaf<P, H, N>, Proof<H>)> {
+        let proof = self.prove(address)?;
+        let leaf = self.remove(address)?;
+        Some((leaf, proof))
+    }
+
+    /// Removes every `Leaf` for which `f` returns false, e.g. to prune the
+    /// accounts left with a zero balance. Forks left holding a single `Leaf`
+    /// collapse as they do in `remove`, so the `Trie` ends up exactly as if
+    /// the removed leaves had never been added. Each `Branch` that lost a
+    /// `Leaf` is rehashed once, at the end.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<Vec<u8>> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], vec![0])).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], vec![5])).unwrap();
+    ///
+    /// trie.retain(|_, balance| balance != &vec![0]);
+    /// assert!(!trie.contains(&[0u8; 32]));
+    /// assert!(trie.contains(&[1u8; 32]));
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(&Address<N>, &P) -> bool) {
+        if self.root.get_next_mut().retain(&mut f) {
+            self.root.rehash();
+        }
+    }
+
+    /// Removes the leaves at `addresses`, e.g. to evict the accounts emptied
+    /// by a block, returning how many were actually there. Given a sorted
+    /// list, every `Branch` on the way is visited once, and rehashed once, at
+    /// the end, rather than once per address as with repeated `remove` calls.
+    /// Forks collapse as they do in `remove`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// assert_eq!(trie.remove_many(&[[0u8; 32], [2u8; 32]]), 1);
+    /// assert!(!trie.contains(&[0u8; 32]));
+    /// assert!(trie.contains(&[1u8; 32]));
+    /// ```
+    pub fn remove_many(&mut self, addresses: &[Address<N>]) -> usize {
+        let removed = self.root.get_next_mut().remove_many(addresses);
+        if removed > 0 {
+            self.root.rehash();
+        }
+        removed
+    }
+
+    /// Adds a `Leaf` directly into the `Branch` at `layer` on its path, for
+    /// reconstructing a `Trie` from a dump in which each `Leaf`'s layer is
+    /// already known. Any `Fork` nodes missing on the path are created, so
+    /// once every `Leaf` of the dump is added the `Trie` matches one built
+    /// with `add`.
+    ///
+    /// Returns an `InvalidInsertError::InvalidAddress` if `layer` lies beyond the address, or
+    /// the hint contradicts the `Trie`, i.e. the path runs into another `Leaf`
+    /// or the slot at `layer` is already taken.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut address = [0u8; 32];
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add_at_layer(Leaf::new(address, "Some Data".to_string()), 1).unwrap();
+    /// address[1] = 1;
+    /// trie.add_at_layer(Leaf::new(address, "Some More Data".to_string()), 1).unwrap();
+    ///
+    /// let mut expected: Trie<String> = Trie::default();
+    /// expected.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// expected.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+    /// assert_eq!(trie.root.get_next().get_hash(), expected.root.get_next().get_hash());
+    /// ```
+    pub fn add_at_layer(&mut self, leaf: Leaf<P, H, N>, layer: usize) -> Result<(), InvalidInsertError> {
+        if layer >= leaf.get_address().len() {
+            return Err(InvalidInsertError::InvalidAddress);
+        }
+        self.insert_at_layer(leaf, layer)
+    }
+
+    /// Adds a `Leaf` directly into the `Branch` at `layer`, see `add_at_layer`.
+    /// `layer` must lie within the address.
+    pub(crate) fn insert_at_layer(&mut self, leaf: Leaf<P, H, N>, layer: usize) -> Result<(), InvalidInsertError> {
+        let inserted = self.root.get_next_mut().insert_at_layer(leaf, layer);
+        self.root.rehash();
+        inserted.map_err(|_| InvalidInsertError::InvalidAddress)
+    }
+
+    /// Returns the `Node::Data` holding the `Leaf` at `address`, following the
+    /// full address through every `Fork` rather than just the `Root` `Branch`
+    /// like `get`. The stored address of the `Leaf` that is reached must match,
+    /// so a `Leaf` that merely shares a prefix with `address` isn't returned.
+    /// Returns `Node::None` if there is no `Leaf` at `address`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::{Leaf, Node};
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let mut address = [0u8; 32];
+    /// trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+    /// address[3] = 1;
+    /// let leaf: Leaf<String> = Leaf::new(address, "Some More Data".to_string());
+    /// trie.add(leaf.clone()).unwrap();
+    ///
+    /// match trie.get_by_address(&address) {
+    ///     Node::Data { data, .. } => assert_eq!(data, leaf),
+    ///     _ => panic!("Leaf not found"),
+    /// }
+    /// assert!(trie.get_by_address(&[1u8; 32]).is_none());
+    /// ```
+    pub fn get_by_address(&self, address: &Address<N>) -> Node<P, H, N> {
+        self.root
+            .get_next_ref()
+            .find_data(address)
+            .cloned()
+            .unwrap_or(Node::None)
+    }
+
+    /// Returns true if the `Trie` holds a `Leaf` at exactly `address`. The
+    /// address is followed through every `Fork`, so an unoccupied `Nibble`, or
+    /// a `Leaf` that merely shares a prefix with `address`, both return false.
+    /// Nothing is cloned along the way.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let mut address = [0u8; 32];
+    /// assert!(trie.contains(&address));
+    /// address[31] = 1;
+    /// assert!(!trie.contains(&address));
+    /// ```
+    pub fn contains(&self, address: &Address<N>) -> bool {
+        self.find_leaf(address).is_some()
+    }
+
+    /// Builds a Merkle inclusion `Proof` for the `Leaf` at `address`, taking
+    /// at every `Branch` on the path the `Nibble` followed and the `Sibling`
+    /// hashes needed to rebuild that `Branch`'s hash. Together with the
+    /// payload, the `Proof` is enough to recompute the `Root` hash without the
+    /// `Trie`. Returns `None` if there is no `Leaf` at `address`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let mut address = [0u8; 32];
+    /// trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+    /// address[1] = 1;
+    /// trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+    ///
+    /// let proof = trie.prove(&address).unwrap();
+    /// assert_eq!(proof.steps.len(), 2);
+    /// assert!(trie.prove(&[1u8; 32]).is_none());
+    /// ```
+    pub fn prove(&self, address: &Address<N>) -> Option<Proof<H>> {
+        let mut steps = self.root.get_next_ref().prove(address)?;
+        steps.reverse();
+        Some(Proof { steps })
+    }
+
+    /// Builds a Merkle `ExclusionProof` that there is no `Leaf` at `address`,
+    /// by following its path to the `Branch` where it leaves the `Trie`, i.e.
+    /// reaches an unoccupied `Nibble` or a `Leaf` with a different address.
+    /// Returns `None` if there is a `Leaf` at `address`, see `prove`.
+    pub fn prove_absence(&self, address: &Address<N>) -> Option<ExclusionProof<H, N>> {
+        let (mut steps, divergence) = self.root.get_next_ref().prove_absence(address)?;
+        steps.reverse();
+        Some(ExclusionProof { steps, divergence })
+    }
+
+    /// Builds a `RangeProof` of every `Leaf` with an address in `[start, end)`,
+    /// opening each `Branch` whose addresses reach into the range, including
+    /// the leaves just outside it sharing a slot with it, and hashing the rest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<Vec<u8>> = Trie::default();
+    /// let mut address = [0u8; 32];
+    /// for i in 0..8u8 {
+    ///     address[1] = i;
+    ///     trie.add(Leaf::new(address, vec![i])).unwrap();
+    /// }
+    /// trie.add(Leaf::new([9u8; 32], vec![9])).unwrap();
+    ///
+    /// let mut start = [0u8; 32];
+    /// start[1] = 2;
+    /// let end = [1u8; 32];
+    /// let proof = trie.prove_range(&start, &end);
+    /// assert!(proof.verify_range(&trie.root_hash(), &start, &end));
+    /// let payloads: Vec<&[u8]> = proof.leaves(&start, &end).into_iter().map(|(_, payload)| payload).collect();
+    /// assert_eq!(payloads, vec![&[2u8][..], &[3], &[4], &[5], &[6], &[7]]);
+    /// ```
+    pub fn prove_range(&self, start: &Address<N>, end: &Address<N>) -> RangeProof<H, N> {
+        RangeProof {
+            slots: self.root.get_next_ref().prove_range(&mut vec![], start, end),
+        }
+    }
+
+    /// Replaces the payload of the `Leaf` at `address`, following the address
+    /// through every `Fork` on the way to the terminal `Leaf`. The `Leaf` hash
+    /// and every hash on the path back up to the `Root` `Branch` are recomputed.
+    /// If there is no `Leaf` at `address` nothing is inserted and an
+    /// `InvalidInsertError::NotFound` is returned, see `replace` to get the old
+    /// payload back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// assert!(trie.update(&[0u8; 32], "Some New Data".to_string()).is_ok());
+    /// assert!(trie.update(&[1u8; 32], "Some New Data".to_string()).is_err());
+    /// ```
+    pub fn update(&mut self, address: &Address<N>, payload: P) -> Result<(), InvalidInsertError> {
+        self.replace(address, payload)
+            .map(|_| ())
+            .map_err(|_| InvalidInsertError::NotFound)
+    }
+
+    /// Returns the `Leaf` stored at exactly `address`, following it through
+    /// every `Fork` on the way.
+    pub(crate) fn find_leaf(&self, address: &Address<N>) -> Option<&Leaf<P, H, N>> {
+        self.root.get_next_ref().find_leaf(address)
+    }
+
+    /// Replaces the payload of the existing `Leaf` at `address` and returns
+    /// the old payload. Unlike `add`, this never creates a new `Leaf`, if there
+    /// is no `Leaf` at `address` a `TrieError::NotFound` is returned instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::{Trie, TrieError};
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let old = trie.replace(&[0u8; 32], "Some New Data".to_string());
+    /// assert_eq!(old, Ok("Some Data".to_string()));
+    ///
+    /// let missing = trie.replace(&[1u8; 32], "Some Data".to_string());
+    /// assert_eq!(missing, Err(TrieError::NotFound));
+    /// ```
+    pub fn replace(&mut self, address: &Address<N>, payload: P) -> Result<P, TrieError> {
+        // Looked up first for the same reason as in `remove`.
+        self.find_leaf(address).ok_or(TrieError::NotFound)?;
+        let old = self.root.get_next_mut().replace_payload(address, payload);
+        self.root.rehash();
+        old.ok_or(TrieError::NotFound)
+    }
+
+    /// Returns the address a logical `key` is stored at, i.e. the hash of the
+    /// `key` with `H`, truncated, or padded with zeros, to `N` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::hash::{Hasher, Sha256Algorithm};
+    ///
+    /// let address = Trie::<String>::key_address(b"account");
+    /// assert_eq!(address, Sha256Algorithm::hash(b"account"));
+    /// ```
+    pub fn key_address(key: impl AsRef<[u8]>) -> Address<N> {
+        let hash = H::hash(key.as_ref());
+        let mut address = [0u8; N];
+        let len = hash.as_ref().len().min(N);
+        address[..len].copy_from_slice(&hash.as_ref()[..len]);
+        address
+    }
+
+    /// Adds a `Leaf` carrying `payload` at the address of `key`, see
+    /// `key_address`. Fails just as `add` does if the address is taken.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.insert_key("alice", "Some Data".to_string()).unwrap();
+    ///
+    /// assert!(trie.contains_key("alice"));
+    /// assert_eq!(trie.get_key("alice"), Some(&"Some Data".to_string()));
+    /// assert!(trie.remove_key("alice").is_some());
+    /// assert!(!trie.contains_key("alice"));
+    /// ```
+    pub fn insert_key(&mut self, key: impl AsRef<[u8]>, payload: P) -> Result<(), InvalidInsertError> {
+        self.add(Leaf::with_hasher(Self::key_address(key), payload))
+    }
+
+    /// Returns a reference to the payload stored at the address of `key`.
+    pub fn get_key(&self, key: impl AsRef<[u8]>) -> Option<&P> {
+        self.find_leaf(&Self::key_address(key)).map(Leaf::payload_ref)
+    }
+
+    /// Removes the `Leaf` stored at the address of `key` and returns it, see
+    /// `remove`.
+    pub fn remove_key(&mut self, key: impl AsRef<[u8]>) -> Option<Leaf<P, H, N>> {
+        self.remove(&Self::key_address(key))
+    }
+
+    /// Returns true if there is a `Leaf` at the address of `key`.
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.contains(&Self::key_address(key))
+    }
+
+    /// Returns the `Entry` at `address`, for adding a `Leaf` there if there
+    /// is none, and modifying its payload otherwise. Every hash on the path
+    /// is recomputed whenever a payload is added or modified through it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.entry([0u8; 32]).or_insert("Some Data".to_string());
+    /// trie.entry([0u8; 32])
+    ///     .and_modify(|payload| payload.push_str(" Modified"))
+    ///     .or_insert("Other Data".to_string());
+    ///
+    /// assert_eq!(trie.entry([0u8; 32]).or_insert_with(String::new).as_str(), "Some Data Modified");
+    /// ```
+    pub fn entry(&mut self, address: Address<N>) -> Entry<'_, P, H, N> {
+        Entry::new(self, address)
+    }
+
+    /// Returns the payload of the `Leaf` at `address`, first adding a `Leaf`
+    /// carrying the result of `f` if there is none, in a single lookup, see
+    /// `Entry::or_insert_with`.
+    ///
+    /// A `PayloadMut` guard is returned rather than a `&mut P`: the hash of
+    /// the `Leaf`, and every hash on its path, commit to the payload, and a
+    /// plain reference would let it change behind them. The guard derefs to
+    /// the payload, and when it is dropped writes it back and rehashes the
+    /// path, whether or not the payload was modified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    ///
+    /// let mut trie: Trie<Vec<u8>> = Trie::default();
+    /// trie.get_or_insert_with([0u8; 32], Vec::new).push(1);
+    /// trie.get_or_insert_with([0u8; 32], Vec::new).push(2);
+    ///
+    /// assert_eq!(trie.get_by_address(&[0u8; 32]).as_data().map(|leaf| leaf.get_payload()), Some(vec![1, 2]));
+    /// ```
+    pub fn get_or_insert_with<F: FnOnce() -> P>(&mut self, address: Address<N>, f: F) -> PayloadMut<'_, P, H, N> {
+        self.entry(address).or_insert_with(f)
+    }
+
+    /// Returns a new `Trie` holding every `Leaf` of `self` and `other`. Where
+    /// both hold a `Leaf` at the same address the payload of `self` is kept.
+    /// Subtrees whose hashes are equal in both are carried over in bulk.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie_1: Trie<String> = Trie::default();
+    /// let mut trie_2: Trie<String> = Trie::default();
+    /// trie_1.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie_2.add(Leaf::new([0u8; 32], "Other Data".to_string())).unwrap();
+    /// trie_2.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// let union = trie_1.union(&trie_2);
+    /// let payloads: Vec<&String> = union.iter().map(|(_, payload)| payload).collect();
+    /// assert_eq!(payloads, vec!["Some Data", "Some More Data"]);
+    /// ```
+    pub fn union(&self, other: &Trie<P, H, N>) -> Trie<P, H, N> {
+        Trie {
+            root: Box::new(self.root.union(&other.root)),
+        }
+    }
+
+    /// Adds every `Leaf` of `other` to `self`. Where both hold a `Leaf` at the
+    /// same address, its payload becomes the result of `on_conflict` given
+    /// the payload of `self` and then that of `other`. The new leaves are
+    /// added with a single rehash per touched `Branch`, see `add_many`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie_1: Trie<String> = Trie::default();
+    /// trie_1.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// let mut trie_2: Trie<String> = Trie::default();
+    /// trie_2.add(Leaf::new([0u8; 32], "Other Data".to_string())).unwrap();
+    /// trie_2.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// trie_1.merge(trie_2, |ours, theirs| format!("{} + {}", ours, theirs));
+    /// let payloads: Vec<&String> = trie_1.iter().map(|(_, payload)| payload).collect();
+    /// assert_eq!(payloads, vec!["Some Data + Other Data", "Some More Data"]);
+    /// ```
+    pub fn merge(&mut self, other: Trie<P, H, N>, on_conflict: impl Fn(&P, &P) -> P) {
+        let mut added = vec![];
+        let mut resolved = vec![];
+        for (address, theirs) in other.iter_addressed() {
+            match self.find_leaf(&address) {
+                Some(ours) => resolved.push((address, on_conflict(ours.payload_ref(), theirs))),
+                None => added.push(Leaf::with_hasher(address, theirs.clone())),
+            }
+        }
+        self.insert_many(added);
+        for (address, payload) in resolved {
+            self.replace(&address, payload)
+                .expect("a conflict is only resolved at an address holding a leaf");
+        }
+    }
+
+    /// Returns a new `Trie` holding the leaves of `self` whose address also
+    /// has a `Leaf` in `other`, keeping the payloads of `self`. Subtrees whose
+    /// hashes are equal in both are taken in bulk.
+    pub fn intersection(&self, other: &Trie<P, H, N>) -> Trie<P, H, N> {
+        Trie {
+            root: Box::new(self.root.intersection(&other.root)),
+        }
+    }
+
+    /// Returns the addresses at which `self` and `other` differ: those only
+    /// holding a `Leaf` in one of them, and those holding a `Leaf` with a
+    /// different payload in each. Subtrees whose hashes are equal in both are
+    /// skipped in bulk, so tries sharing most of their state diff quickly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie_1: Trie<String> = Trie::default();
+    /// trie_1.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie_1.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    /// let mut trie_2 = trie_1.clone();
+    /// trie_2.update(&[1u8; 32], "Other Data".to_string()).unwrap();
+    /// trie_2.add(Leaf::new([2u8; 32], "Even More Data".to_string())).unwrap();
+    ///
+    /// let diff = trie_1.diff(&trie_2);
+    /// assert!(diff.only_in_self.is_empty());
+    /// assert_eq!(diff.only_in_other, vec![[2u8; 32]]);
+    /// assert_eq!(diff.changed, vec![[1u8; 32]]);
+    /// ```
+    pub fn diff(&self, other: &Trie<P, H, N>) -> TrieDiff<N> {
+        let mut diff = TrieDiff::default();
+        self.root.get_next_ref().collect_diff(other.root.get_next_ref(), &mut diff);
+        diff
+    }
+
+    /// Returns a new `Trie` holding the leaves of `self` whose address has no
+    /// `Leaf` in `other`. Subtrees whose hashes are equal in both are skipped
+    /// in bulk.
+    pub fn difference(&self, other: &Trie<P, H, N>) -> Trie<P, H, N> {
+        Trie {
+            root: Box::new(self.root.difference(&other.root)),
+        }
+    }
+
+    /// Returns an iterator over every `Leaf` in the `Trie` yielding its address
+    /// and a reference to its payload, in ascending address order. The `Trie`
+    /// is borrowed, and no payloads are cloned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let payloads: Vec<&String> = trie.iter().map(|(_, payload)| payload).collect();
+    /// assert_eq!(payloads, vec!["Some Data", "Some More Data"]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, P, H, N> {
+        Iter {
+            branches: vec![self.root.get_next_ref().nodes()],
+        }
+    }
+
+    /// Returns an iterator over every `Leaf` in the `Trie` whose address
+    /// starts with `prefix`, yielding its address and a reference to its
+    /// payload in ascending address order. The `Trie` is only descended along
+    /// `prefix`, and then every `Leaf` beneath the `Branch` reached is
+    /// yielded. If the path runs into a `Leaf` before `prefix` is used up,
+    /// that `Leaf` is the only one yielded, as long as it matches `prefix`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let mut address = [3u8; 32];
+    /// trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+    /// address[1] = 4;
+    /// trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([4u8; 32], "Other Data".to_string())).unwrap();
+    ///
+    /// let payloads: Vec<&String> = trie.iter_prefix(&[3]).map(|(_, payload)| payload).collect();
+    /// assert_eq!(payloads, vec!["Some Data", "Some More Data"]);
+    /// assert_eq!(trie.iter_prefix(&[3, 4, 3]).count(), 1);
+    /// assert_eq!(trie.iter_prefix(&[5]).count(), 0);
+    /// ```
+    pub fn iter_prefix<'a>(&'a self, prefix: &[u8]) -> impl Iterator<Item = (Address<N>, &'a P)> + 'a {
+        let mut below = Iter { branches: vec![] };
+        let mut leaf = None;
+        match self.root.get_next_ref().find_prefix(prefix) {
+            Some(NodeRef::Branch(branch)) => below.branches.push(branch.nodes()),
+            Some(NodeRef::Leaf(data)) => leaf = Some((data.get_address(), data.payload_ref())),
+            _ => {}
+        }
+        leaf.into_iter().chain(below)
+    }
+
+    /// Returns an iterator over every `Leaf` in the `Trie` like `iter`, but
+    /// rebuilding each address from the structure of the `Trie` rather than
+    /// reading the one stored in the `Leaf`: the `Nibble`s of the `Fork`s
+    /// descended through, followed by the `Leaf`'s own `nibble` and remainder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let mut address = [3u8; 32];
+    /// trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+    /// address[1] = 4;
+    /// trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+    ///
+    /// let addressed: Vec<_> = trie.iter_addressed().collect();
+    /// let stored: Vec<_> = trie.iter().collect();
+    /// assert_eq!(addressed, stored);
+    /// ```
+    pub fn iter_addressed(&self) -> Addressed<'_, P, H, N> {
+        Addressed {
+            branches: vec![self.root.get_next_ref().nodes()],
+            path: vec![],
+        }
+    }
+
+    /// Returns an iterator over every `Fork` in the `Trie`, parents before
+    /// children and in `Nibble` order, yielding for each:
+    ///
+    /// - the path of `Nibble` values leading to the `Branch` holding the `Fork`,
+    /// - the shared `Nibble` of the `Fork`,
+    /// - the number of occupied slots in the `Branch` beneath the `Fork`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let mut address = [3u8; 32];
+    /// trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+    /// address[1] = 4;
+    /// trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+    ///
+    /// let forks: Vec<_> = trie.iter_forks().collect();
+    /// assert_eq!(forks, vec![(vec![], 3, 2)]);
+    /// ```
+    pub fn iter_forks(&self) -> Forks<'_, P, H, N> {
+        Forks {
+            branches: vec![self.root.get_next_ref().nodes()],
+            path: vec![],
+        }
+    }
+
+    /// Returns a new `Trie` with the same addresses, and therefore the same
+    /// shape, whose payloads are the result of applying `f` to each payload
+    /// of this `Trie`, e.g. to project accounts onto their balances. Since the
+    /// hash of a `Leaf` covers its payload, every hash is recomputed, and the
+    /// root hash generally differs, but no leaf is re-inserted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let lengths: Trie<Vec<u8>> = trie.map(|payload| vec![payload.len() as u8]);
+    /// assert_eq!(lengths.max_depth(), trie.max_depth());
+    /// ```
+    pub fn map<Q, F>(&self, f: F) -> Trie<Q, H, N>
+    where
+        Q: Clone + Debug + Into<Vec<u8>>,
+        F: Fn(&P) -> Q,
+    {
+        Trie {
+            root: Box::new(self.root.map_payloads(&f)),
+        }
+    }
+
+    /// Returns a new `Trie` whose payloads are the result of applying `f` to
+    /// each payload of this `Trie`, see `map`, which this was renamed to.
+    #[deprecated(note = "use map")]
+    pub fn map_payloads<Q, F>(&self, f: F) -> Trie<Q, H, N>
+    where
+        Q: Clone + Debug + Into<Vec<u8>>,
+        F: Fn(&P) -> Q,
+    {
+        self.map(f)
+    }
+
+    /// Finds the `Branch`, `Fork` or `Leaf` anywhere in the `Trie` whose hash
+    /// is `hash`. There is no hash to node index, so this is a full depth first
+    /// traversal, i.e. `O(n)` in the number of nodes in the `Trie`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::{Leaf, NodeRef};
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let leaf: Leaf<String> = Leaf::new([0u8; 32], "Some Data".to_string());
+    /// trie.add(leaf.clone()).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    ///
+    /// match trie.node_by_hash(&leaf.get_hash()) {
+    ///     Some(NodeRef::Leaf(found)) => assert_eq!(found, &leaf),
+    ///     _ => panic!("Leaf not found by its hash"),
+    /// }
+    /// ```
+    pub fn node_by_hash(&self, hash: &RootHash<H>) -> Option<NodeRef<'_, P, H, N>> {
+        self.root.get_next_ref().find_by_hash(hash)
+    }
+
+    /// Returns the smallest address held in the `Trie`, or `None` if it is
+    /// empty. Descends the lowest occupied `Nibble` of each `Branch`, so this
+    /// takes `O(depth)` rather than iterating every `Leaf`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// assert_eq!(trie.min_address(), None);
+    /// trie.add(Leaf::new([2u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    /// assert_eq!(trie.min_address(), Some([1u8; 32]));
+    /// assert_eq!(trie.max_address(), Some([2u8; 32]));
+    /// ```
+    pub fn min_address(&self) -> Option<Address<N>> {
+        self.root.get_next_ref().min_address()
+    }
+
+    /// Returns the largest address held in the `Trie`, or `None` if it is
+    /// empty. Descends the highest occupied `Nibble` of each `Branch`, so this
+    /// takes `O(depth)` rather than iterating every `Leaf`.
+    pub fn max_address(&self) -> Option<Address<N>> {
+        self.root.get_next_ref().max_address()
+    }
+
+    /// Encodes the `Trie` in a compact, length prefixed binary format without
+    /// depending on `serde`: the `Hasher::algorithm_id` of `H`, followed by
+    /// every `Branch` from the `Root` down, each as its occupied `Nibble`s
+    /// with their node type and contents. Hashes aren't written, `from_bytes`
+    /// recomputes them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<Vec<u8>> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], b"Some Data".to_vec())).unwrap();
+    ///
+    /// let bytes = trie.to_bytes();
+    /// assert_eq!(Trie::from_bytes(&bytes), Ok(trie));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![H::algorithm_id()];
+        self.root.encode(&mut out);
+        out
+    }
+
+    /// Returns the number of bytes `to_bytes` will return, e.g. to size a
+    /// buffer up front when serializing a large `Trie` every block. It is
+    /// exact rather than an estimate, counted from the nodes and payload
+    /// lengths without writing anything, but each payload is still converted
+    /// into bytes once to learn its length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<Vec<u8>> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], b"Some Data".to_vec())).unwrap();
+    /// assert_eq!(trie.serialized_size_hint(), trie.to_bytes().len());
+    /// ```
+    pub fn serialized_size_hint(&self) -> usize {
+        1 + self.root.get_next_ref().encoded_len()
+    }
+
+    /// Decodes a `Trie` written by `to_bytes`, converting each payload back
+    /// with `P::try_from`. Returns a `DecodeError` if the bytes were written
+    /// with another `Hasher`, are truncated or malformed, or don't describe
+    /// a valid `Trie`, e.g. a `Leaf` sits off the path of its address.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Trie<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let mut bytes = bytes;
+        let id = *bytes.first().ok_or(DecodeError::UnexpectedEnd)?;
+        if id != H::algorithm_id() {
+            return Err(DecodeError::AlgorithmMismatch(id));
+        }
+        bytes = &bytes[1..];
+        let root = Root::decode(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(Trie { root: Box::new(root) })
+    }
+
+    /// Encodes the `Trie` as RLP, the encoding Ethereum uses for the nodes
+    /// of its tries, e.g. to inspect it with Ethereum tooling. The `Root`
+    /// `Branch` is written as a list of its 256 slots, each `Fork` as an
+    /// extension node and each `Leaf` as a leaf node, see `from_rlp`. The
+    /// hashes aren't written, and as the slots are bytes rather than hex
+    /// digits they wouldn't match Ethereum's anyway.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<Vec<u8>> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], b"Some Data".to_vec())).unwrap();
+    ///
+    /// let rlp = trie.to_rlp();
+    /// assert_eq!(Trie::from_rlp(&rlp), Ok(trie));
+    /// ```
+    #[cfg(feature = "rlp")]
+    pub fn to_rlp(&self) -> Vec<u8> {
+        let mut out = vec![];
+        self.root.encode_rlp(&mut out);
+        out
+    }
+
+    /// Decodes a `Trie` written by `to_rlp`, converting each payload back
+    /// with `P::try_from`. Returns a `DecodeError` if the bytes aren't
+    /// canonical RLP, are truncated, or don't describe a valid `Trie`, e.g.
+    /// a `Leaf` whose address is too short or too long.
+    #[cfg(feature = "rlp")]
+    pub fn from_rlp(bytes: &[u8]) -> Result<Trie<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Ok(Trie { root: Box::new(Root::decode_rlp(bytes)?) })
+    }
+
+    /// Writes every `Branch`, `Fork` and `Leaf` of the `Trie` to `store`, each
+    /// keyed by its hash, and returns the `Root` hash under which the `Trie`
+    /// can be loaded again with `load`. Nodes already in `store`, e.g. those
+    /// shared with a previously committed `Trie`, are simply overwritten.
+    pub fn commit<S: NodeStore<H>>(&self, store: &mut S) -> RootHash<H> {
+        self.root.commit(store);
+        self.root.get_hash()
+    }
+
+    /// Rebuilds the `Trie` committed to `store` under the `Root` hash `root`,
+    /// reading the nodes it references as they are reached from the `Root`.
+    /// Every node is checked against the hash it is stored under, so a
+    /// `DecodeError` is returned if a node is missing, malformed or tampered.
+    pub fn load<S: NodeStore<H>>(root: RootHash<H>, store: &S) -> Result<Trie<P, H, N>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Ok(Trie { root: Box::new(Root::load(&root, store)?) })
+    }
+
+    /// Returns the payload at `address` in the `Trie` committed to `store`
+    /// under the `Root` hash `root`, reading only the nodes on its path rather
+    /// than loading the whole `Trie`. Unlike a lookup in a loaded `Trie`, an
+    /// address without a `Leaf`, `Ok(None)`, is told apart from a node that
+    /// can't be read, e.g. one missing from `store` or not matching the hash
+    /// it is stored under, which returns a `DecodeError`, see `get_stored`.
+    pub fn try_get<S: NodeStore<H>>(root: RootHash<H>, store: &S, address: &Address<N>) -> Result<Option<P>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Ok(Root::<P, H, N>::load_leaf(&root, store, address)?.map(|leaf| leaf.get_payload()))
+    }
+
+    /// Returns the payload at `address` in the `Trie` committed to `store`
+    /// under `root`, like `try_get`, for callers treating an unreadable
+    /// `store` as fatal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a node on the path can't be read, see `try_get`.
+    pub fn get_stored<S: NodeStore<H>>(root: RootHash<H>, store: &S, address: &Address<N>) -> Option<P>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        Self::try_get(root, store, address).expect("the nodes on the path can be read from the store")
+    }
+
+    /// Builds a Merkle inclusion `Proof` for the `Leaf` at `address` in the
+    /// `Trie` committed to `store` under a past `Root` hash `root`, e.g. to
+    /// prove a balance at an earlier block, reading only the nodes on its
+    /// path, see `try_get`. Returns `Ok(None)` if there was no `Leaf` at
+    /// `address`, and a `DecodeError::MissingNode` if nodes of that `Trie`
+    /// were pruned from `store` since.
+    pub fn prove_at<S: NodeStore<H>>(root: RootHash<H>, store: &S, address: &Address<N>) -> Result<Option<Proof<H>>, DecodeError>
+    where
+        P: TryFrom<Vec<u8>>,
+    {
+        let steps = Root::<P, H, N>::load_proof(&root, store, address)?;
+        Ok(steps.map(|steps| Proof { steps }))
+    }
+
+    /// Returns a cheap, non-cryptographic 64-bit checksum (FNV-1a) over every
+    /// address and payload in the `Trie`, in address order. Useful as a quick
+    /// "did anything change" signal, e.g. for logging or monitoring loops.
+    ///
+    /// The checksum depends only on the entries, so two tries committing to
+    /// the same hashes always have equal checksums. The reverse isn't
+    /// guaranteed, equal checksums don't prove equal contents, compare the
+    /// hashes for that.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let empty = trie.checksum();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// assert_ne!(trie.checksum(), empty);
+    /// ```
+    pub fn checksum(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+        self.iter().fold(FNV_OFFSET_BASIS, |checksum, (address, payload)| {
+            let payload: Vec<u8> = payload.clone().into();
+            address
+                .iter()
+                .chain((payload.len() as u64).to_be_bytes().iter())
+                .chain(payload.iter())
+                .fold(checksum, |checksum, byte| {
+                    (checksum ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+                })
+        })
+    }
+
+    /// Returns true if `other` holds the same nodes as this `Trie`, i.e. the
+    /// same leaves, with the same addresses and serialized payloads, under the
+    /// same `Fork`s. Unlike `==`, which compares only the `Root` hashes, no
+    /// cached hash is read, so the result doesn't depend on them being up to
+    /// date, or on the absence of hash collisions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// let mut other = trie.clone();
+    /// assert!(trie.structural_eq(&other));
+    ///
+    /// other.update(&[0u8; 32], "Other Data".to_string()).unwrap();
+    /// assert!(!trie.structural_eq(&other));
+    /// ```
+    pub fn structural_eq(&self, other: &Trie<P, H, N>) -> bool {
+        self.root.get_next_ref().structural_eq(other.root.get_next_ref())
+    }
+
+    /// Returns a Graphviz DOT `digraph` of the `Trie`, with a node for the
+    /// `Root`, for every `Branch`, and for every occupied `Nibble`, i.e. each
+    /// `Leaf` and `Fork`, labeled with its `Nibble` and abbreviated hash, and
+    /// an edge from each node to the nodes beneath it. Render it with e.g.
+    /// `dot -Tsvg`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    ///
+    /// let dot = trie.to_dot();
+    /// assert!(dot.starts_with("digraph trie {"));
+    /// assert!(dot.contains("root -> b;"));
+    /// assert!(dot.contains("b -> b_00;"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph trie {\n");
+        out.push_str(&format!(
+            "  root [shape=box, label=\"root\\n{}\"];\n",
+            abbreviate(self.root.get_hash().as_ref())
+        ));
+        self.root.get_next_ref().write_dot("b", &mut out);
+        out.push_str("  root -> b;\n}\n");
+        out
+    }
+
+    /// Returns the number of leaves in the `Trie`, counted by walking every
+    /// `Branch` and `Fork` rather than through an iterator.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+    /// assert_eq!(trie.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.root.get_next_ref().leaf_count()
+    }
+
+    /// Returns true if the `Trie` holds no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.root.get_next_ref().occupied() == 0
+    }
+
+    /// Returns the number of `Branch` nodes on the longest path from the `Root`
+    /// to a `Leaf`, i.e. a `Leaf` in the `Root` `Branch` has a depth of 1. An
+    /// empty `Trie` has a depth of 0.
+    ///
+    /// Proof sizes grow with depth, so operators can use this (or
+    /// `is_depth_within`) to guard against adversarial key clustering.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// assert_eq!(trie.max_depth(), 0);
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// assert_eq!(trie.max_depth(), 1);
+    /// ```
+    pub fn max_depth(&self) -> usize {
+        self.root.get_next_ref().max_depth()
+    }
+
+    /// Returns true if no `Leaf` in the `Trie` sits deeper than `limit`.
+    /// Use `address_exceeding_depth` to find out which address broke the limit.
+    pub fn is_depth_within(&self, limit: usize) -> bool {
+        self.address_exceeding_depth(limit).is_none()
+    }
+
+    /// Returns the address of a `Leaf` sitting deeper than `limit` (see
+    /// `max_depth`), or `None` if every `Leaf` is within the limit.
+    pub fn address_exceeding_depth(&self, limit: usize) -> Option<Address<N>> {
+        self.root.get_next_ref().find_deeper_than(limit, 1)
+    }
+
+    /// Returns true if the `Trie` holds a `Fork` that is not canonical, i.e.
+    /// one wrapping an empty `Branch` or a `Branch` whose only child is a
+    /// `Node::Data`. Inserting leaves never produces either, so this signals a
+    /// bug in whatever reshaped the `Trie`, or a corrupted `Trie`.
+    ///
+    /// A `Fork` whose only child is another `Fork` is canonical, a chain of
+    /// them represents a prefix shared by every `Leaf` beneath it.
+    pub fn has_dangling_forks(&self) -> bool {
+        self.root.get_next_ref().has_dangling_forks()
+    }
+
+    /// Panics unless the `Trie` is exactly the one adding its leaves one at a
+    /// time with `add` builds, e.g. for a caller not trusting a bulk build
+    /// like `from_sorted` or `add_many`. The shape a set of leaves takes is
+    /// unique, so every `Leaf` must sit on its path, every `Branch` at the
+    /// `Layer` of its depth, every `Fork` hold more than a single `Leaf`, see
+    /// `has_dangling_forks`, and every cached hash be up to date, see
+    /// `verify_integrity`. Bulk builds check the shape, which hashes nothing,
+    /// in debug builds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let leaves: Vec<Leaf<String>> = (0..8u8)
+    ///     .map(|byte| Leaf::new([byte; 32], "Some Data".to_string()))
+    ///     .collect();
+    ///
+    /// Trie::from_sorted(leaves).unwrap().assert_canonical();
+    /// ```
+    pub fn assert_canonical(&self) {
+        if let Some(error) = self.canonical_error() {
+            panic!("The Trie isn't canonical: {}", error);
+        }
+        assert!(self.verify_integrity(), "The Trie isn't canonical: a cached hash is stale");
+    }
+
+    /// Returns why the shape of the `Trie` isn't the one `add` builds, if it
+    /// isn't, see `assert_canonical`.
+    fn canonical_error(&self) -> Option<String> {
+        self.root.get_next_ref().canonical_error(&mut vec![])
+    }
+
+    /// Returns true if every cached hash in the `Trie`, i.e. that of every
+    /// `Leaf`, `Fork` and `Branch` and of the `Root`, matches the hash
+    /// recomputed from its contents. Every mutation through the `Trie` keeps
+    /// them in sync, so this detects corruption, e.g. of a deserialized
+    /// `Trie`, or of a hash overwritten through `Root::get_next_mut`. The
+    /// hashes of branches left dirty by `add` aren't cached yet, so only
+    /// their nodes are checked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::{Leaf, Node};
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+    /// assert!(trie.verify_integrity());
+    ///
+    /// let data = Leaf::new([0u8; 32], "Some Other Data".to_string());
+    /// trie.root.get_next_mut()[0] = Node::Data { data, hash: [0u8; 32] };
+    /// assert!(!trie.verify_integrity());
+    /// trie.recompute_hashes();
+    /// assert!(trie.verify_integrity());
+    /// ```
+    pub fn verify_integrity(&self) -> bool {
+        let branch = self.root.get_next_ref();
+        branch.verify_hashes() && H::hash(branch.get_hash().as_ref()) == self.root.get_hash()
+    }
+
+    /// Recomputes every cached hash in the `Trie` bottom up, from the leaves
+    /// to the `Root`, repairing any `verify_integrity` would reject. With the
+    /// `rayon` feature the subtrees are recomputed in parallel instead.
+    #[cfg(not(feature = "rayon"))]
+    pub fn recompute_hashes(&mut self) {
+        self.root.get_next_mut().recompute_hashes();
+        self.root.rehash();
+    }
+
+    /// Returns the fraction of shared prefix bytes held in `Fork` nodes that a
+    /// minimal Patricia representation would not need to store separately.
+    ///
+    /// Every `Fork` stores one shared byte of address. A `Fork` whose `Branch`
+    /// has a single occupied `Nibble` (always another `Fork`) is a link in a chain
+    /// that a Patricia extension node would fold into one path segment, so:
+    ///
+    /// `ratio = single_child_forks / total_forks`
+    ///
+    /// The ratio is `0.0` for a trie without any `Fork` nodes. Values close to
+    /// `1.0` mean the key set clusters on long shared prefixes and would benefit
+    /// from prefix compaction.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let mut trie: Trie<String> = Trie::default();
+    /// let mut address = [0u8; 32];
+    /// trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+    /// address[2] = 1;
+    /// trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+    ///
+    /// // Forks at layers 0 and 1, only the second one wraps both leaves.
+    /// assert_eq!(trie.prefix_compression_ratio(), 0.5);
+    /// ```
+    pub fn prefix_compression_ratio(&self) -> f64 {
+        let (total, single) = self.root.get_next_ref().fork_counts();
+        if total == 0 {
+            return 0.0;
+        }
+        single as f64 / total as f64
+    }
+}
+
+/// Checks `leaves` are in strictly ascending order of their addresses, see
+/// `Trie::from_sorted`.
+fn check_sorted<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize>(leaves: &[Leaf<P, H, N>]) -> Result<(), InvalidInsertError> {
+    for pair in leaves.windows(2) {
+        match pair[0].get_address().cmp(&pair[1].get_address()) {
+            Ordering::Less => {}
+            Ordering::Equal => return Err(InvalidInsertError::DuplicateAddress),
+            Ordering::Greater => return Err(InvalidInsertError::Unsorted),
+        }
+    }
+    Ok(())
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Default for Trie<P> {
+/// Parallel bulk building, available with the `rayon` feature.
+#[cfg(feature = "rayon")]
+impl<P, H, const N: usize> Trie<P, H, N>
+where
+    P: Clone + Debug + Into<Vec<u8>> + Send + Sync,
+    H: Hasher + Send + Sync,
+    RootHash<H>: Send + Sync,
+{
+    /// Recomputes every cached hash in the `Trie` bottom up, from the leaves
+    /// to the `Root`, repairing any `verify_integrity` would reject. The
+    /// subtrees under the `Nibble`s of each `Branch` are recomputed in
+    /// parallel, see `Root::par_rehash`, to the same hashes a sequential
+    /// pass gives.
+    pub fn recompute_hashes(&mut self) {
+        self.root.get_next_mut().par_recompute_hashes();
+        self.root.par_rehash();
+    }
+
+    /// Same as `from_sorted`, but builds the subtree under each `Nibble` of
+    /// the `Root` `Branch` in parallel, hashing it along the way, before
+    /// hashing the `Root`. The `Trie` is identical.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mmpt::trie::Trie;
+    /// use mmpt::node::Leaf;
+    ///
+    /// let leaves: Vec<Leaf<String>> = (0..8u8)
+    ///     .map(|byte| Leaf::new([byte; 32], "Some Data".to_string()))
+    ///     .collect();
+    ///
+    /// let trie = Trie::from_sorted_par(leaves.clone()).unwrap();
+    /// assert_eq!(trie, Trie::from_sorted(leaves).unwrap());
+    /// ```
+    pub fn from_sorted_par(leaves: Vec<Leaf<P, H, N>>) -> Result<Trie<P, H, N>, InvalidInsertError> {
+        check_sorted(&leaves)?;
+        let mut trie = Trie::with_hasher();
+        if !leaves.is_empty() {
+            trie.root.get_next_mut().par_insert_many(leaves);
+            trie.root.rehash();
+            debug_assert!(trie.canonical_error().is_none(), "{:?}", trie.canonical_error());
+        }
+        Ok(trie)
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Default for Trie<P, H, N> {
     fn default() -> Self {
-        Self::new()
+        Self::with_hasher()
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> PartialEq for Trie<P> {
-    fn eq(&self, other: &Trie<P>) -> bool {
+/// Renders the shape of the `Trie`: a line for the `Root` hash, then a line
+/// per occupied `Nibble`, indented by its `Layer`, giving the `Nibble` and
+/// the hash of the node, along with the address for a `Leaf`. The `Branch`
+/// beneath each `Fork` follows on the lines right after it.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let mut trie: Trie<String> = Trie::default();
+/// let mut address = [3u8; 32];
+/// trie.add(Leaf::new(address, "Some Data".to_string())).unwrap();
+/// address[1] = 4;
+/// trie.add(Leaf::new(address, "Some More Data".to_string())).unwrap();
+///
+/// let rendered = trie.to_string();
+/// let lines: Vec<&str> = rendered.lines().collect();
+/// assert!(lines[0].starts_with("root "));
+/// assert!(lines[1].starts_with("  03 fork "));
+/// assert!(lines[2].starts_with("    03 leaf 0303"));
+/// assert!(lines[3].starts_with("    04 leaf 0304"));
+/// ```
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Display for Trie<P, H, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        writeln!(f, "root {}", to_hex(self.root.get_hash().as_ref()))?;
+        self.root.get_next_ref().fmt_tree(f, 1)
+    }
+}
+
+/// Implements PartialEq for `Trie`. Two tries are equal if their `Root`
+/// hashes are, which is fast but relies on the cached hashes, see
+/// `Trie::structural_eq` to compare the nodes themselves.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> PartialEq for Trie<P, H, N> {
+    fn eq(&self, other: &Trie<P, H, N>) -> bool {
         self.root.eq(&other.root)
     }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Eq for Trie<P, H, N> { }
+
+/// Returns a lower bound on the number of leaves left to yield by a stack of
+/// `Branch` iterators, i.e. the number of occupied slots left in each, as
+/// every occupied slot holds at least one `Leaf`. The number of leaves is
+/// not tracked, so there is no upper bound short of walking them.
+fn leaves_left<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize>(
+    branches: &[btree_map::Iter<'_, Nibble, Node<P, H, N>>],
+) -> usize {
+    branches
+        .iter()
+        .map(|nodes| nodes.clone().filter(|(_, node)| !node.is_none()).count())
+        .sum()
+}
+
+/// Descends into every `Fork` as it is reached, so leaves are yielded in
+/// `Nibble` order at every layer, i.e. in ascending address order.
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for Iter<'a, P, H, N> {
+    type Item = (Address<N>, &'a P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(nodes) = self.branches.last_mut() {
+            match nodes.next() {
+                Some((_, Node::Data { data, .. })) => {
+                    return Some((data.get_address(), data.payload_ref()))
+                }
+                Some((_, Node::Fork { fork, .. })) => {
+                    let nodes = fork.get_next_ref().nodes();
+                    self.branches.push(nodes);
+                }
+                Some((_, Node::None)) => {}
+                None => {
+                    self.branches.pop();
+                }
+            }
+        }
+        None
+    }
 
-    fn ne(&self, other: &Trie<P>) -> bool {
-        self.root.ne(&other.root)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (leaves_left(&self.branches), None)
     }
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> Eq for Trie<P> { }
+/// Descends into every `Fork` as it is reached, tracking the path of
+/// `Nibble` values taken alongside the stack of branches, so each address
+/// is the path followed by the `nibble` and remainder of its `Leaf`.
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for Addressed<'a, P, H, N> {
+    type Item = (Address<N>, &'a P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(nodes) = self.branches.last_mut() {
+            match nodes.next() {
+                Some((nibble, Node::Data { data, .. })) => {
+                    let mut address = [0u8; N];
+                    let layer = self.path.len();
+                    address[..layer].copy_from_slice(&self.path);
+                    address[layer] = *nibble;
+                    address[layer + 1..].copy_from_slice(data.get_remainder());
+                    return Some((address, data.payload_ref()));
+                }
+                Some((nibble, Node::Fork { fork, .. })) => {
+                    self.path.push(*nibble);
+                    self.branches.push(fork.get_next_ref().nodes());
+                }
+                Some((_, Node::None)) => {}
+                None => {
+                    self.branches.pop();
+                    self.path.pop();
+                }
+            }
+        }
+        None
+    }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> IntoIterator for Trie<P> {
-    type Item = Node<P>;
-    type IntoIter = TrieIntoIter<P>;
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (leaves_left(&self.branches), None)
+    }
+}
+
+/// Yields each `Fork` as it is reached, then descends into it, tracking
+/// the path of `Nibble` values taken alongside the stack of branches.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for Forks<'_, P, H, N> {
+    type Item = (Vec<Nibble>, Nibble, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(nodes) = self.branches.last_mut() {
+            match nodes.next() {
+                Some((nibble, Node::Fork { fork, .. })) => {
+                    let next = fork.get_next_ref();
+                    let item = (self.path.clone(), *nibble, next.occupied());
+                    self.path.push(*nibble);
+                    self.branches.push(next.nodes());
+                    return Some(item);
+                }
+                Some(_) => {}
+                None => {
+                    self.branches.pop();
+                    self.path.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for Trie<P, H, N> {
+    type Item = Node<P, H, N>;
+    type IntoIter = TrieIntoIter<P, H, N>;
     
     fn into_iter(self) -> Self::IntoIter {
-        let layer = self.root.get_next().get_layer();
         TrieIntoIter {
-            curr_branch: self.root.get_next().into_iter(),
-            layer: layer.into(),
-            branches: vec![self.root.get_next().into_iter()],
+            branches: vec![self.root.into_next().into_iter()],
         }
     }
 }
 
-/// Implements basic, and necessary Iterator methods for TrieIntoIter<P> struct.
-/// This allows us to recursively search for Data Nodes in the Trie.
-impl<P: Clone + Debug + Into<Vec<u8>>> Iterator for TrieIntoIter<P> {
-    type Item = Node<P>;
-    fn next(&mut self) -> Option<Node<P>> {
-        while let Some(node) = self.curr_branch.next() {
-            match node.clone() {
-                Node::Data { .. } => { return Some(node) },
-                Node::Fork { fork, .. } => {
-                    self.branches.push(fork.get_next().into_iter());
-                    self.curr_branch = fork.get_next().into_iter();
-                    let mut layer: u8 = self.layer.clone().into();
-                    layer += 1;
-                    self.layer = layer.into();
-                }
-                Node::None => { self.next(); }
+/// Borrows a `Trie` to iterate over references to its leaves, so the `Trie`
+/// can be iterated over any number of times without being consumed.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let mut trie: Trie<String> = Trie::default();
+/// trie.add(Leaf::new([1u8; 32], "Some More Data".to_string())).unwrap();
+/// trie.add(Leaf::new([0u8; 32], "Some Data".to_string())).unwrap();
+///
+/// for leaf in &trie {
+///     println!("{:?}", leaf);
+/// }
+/// assert_eq!((&trie).into_iter().count(), 2);
+/// ```
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> IntoIterator for &'a Trie<P, H, N> {
+    type Item = &'a Leaf<P, H, N>;
+    type IntoIter = TrieIterator<'a, P, H, N>;
 
+    fn into_iter(self) -> Self::IntoIter {
+        TrieIterator {
+            branches: vec![self.root.get_next_ref().nodes()],
+        }
+    }
+}
+
+/// Descends into every `Fork` as it is reached and pops back up to the
+/// `Branch` above once a `Branch` is exhausted, like `Iter`.
+impl<'a, P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for TrieIterator<'a, P, H, N> {
+    type Item = &'a Leaf<P, H, N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(nodes) = self.branches.last_mut() {
+            match nodes.next() {
+                Some((_, Node::Data { data, .. })) => return Some(data),
+                Some((_, Node::Fork { fork, .. })) => {
+                    let nodes = fork.get_next_ref().nodes();
+                    self.branches.push(nodes);
+                }
+                Some((_, Node::None)) => {}
+                None => {
+                    self.branches.pop();
+                }
             }
         }
-        if self.layer.clone() as u8 == 0u8 {
-            return None
-        } else {
-            let mut layer = self.layer.clone() as u8;
-            layer -= 1;
-            self.layer = layer.into();
-            self.curr_branch = self.branches[self.layer.clone() as usize].clone();
-            self.branches.pop();
-            self.next()
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (leaves_left(&self.branches), None)
+    }
+}
+
+/// Implements basic, and necessary Iterator methods for TrieIntoIter<P> struct.
+/// This allows us to search for Data Nodes in the Trie, descending into every
+/// `Fork` as it is reached and popping back up to the `Branch` above once a
+/// `Branch` is exhausted, without recursing.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Iterator for TrieIntoIter<P, H, N> {
+    type Item = Node<P, H, N>;
+    fn next(&mut self) -> Option<Node<P, H, N>> {
+        while let Some(nodes) = self.branches.last_mut() {
+            match nodes.next() {
+                Some(node @ Node::Data { .. }) => return Some(node),
+                Some(Node::Fork { fork, .. }) => {
+                    self.branches.push(Branch::from(fork).into_iter());
+                }
+                Some(Node::None) => {}
+                None => {
+                    self.branches.pop();
+                }
+            }
         }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.branches.iter().map(BranchIntoIter::occupied_len).sum();
+        (left, None)
     }
-    // TODO: Implement other Iterator methods for trie.        
 }
 
-impl<P: Clone + Debug + Into<Vec<u8>>> DoubleEndedIterator for TrieIntoIter<P> {
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> DoubleEndedIterator for TrieIntoIter<P, H, N> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        while let Some(node) = self.curr_branch.next_back() {
-            match node.clone() {
-                Node::Data { .. } => { return Some(node) }
-                Node::Fork { fork, .. } => {
-                    self.branches.push(fork.get_next().into_iter());
-                    self.curr_branch = fork.get_next().into_iter();
-                    let mut layer: u8 = self.layer.clone().into();
-                    layer += 1;
-                    self.layer = layer.into();
+        while let Some(nodes) = self.branches.last_mut() {
+            match nodes.next_back() {
+                Some(node @ Node::Data { .. }) => return Some(node),
+                Some(Node::Fork { fork, .. }) => {
+                    self.branches.push(Branch::from(fork).into_iter());
+                }
+                Some(Node::None) => {}
+                None => {
+                    self.branches.pop();
                 }
-                Node::None => { self.next_back(); }
             }
         }
-        if self.layer.clone() as u8 == 0u8 {
-            return None
-        } else {
-            let mut layer = self.layer.clone() as u8;
-            layer -= 1;
-            self.layer = layer.into();
-            self.curr_branch = self.branches[self.layer.clone() as usize].clone();
-            self.branches.pop();
-            self.next_back()
+        None
+    }
+}
+
+/// Adds every `Leaf` yielded by `iter` with a single rehash per touched
+/// `Branch`, see `Trie::add_many`. As `extend` can't fail, a `Leaf` whose
+/// address is already in the `Trie`, or was yielded before, is skipped, just
+/// as `add` would reject it.
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> Extend<Leaf<P, H, N>> for Trie<P, H, N> {
+    fn extend<I: IntoIterator<Item = Leaf<P, H, N>>>(&mut self, iter: I) {
+        let mut leaves: BTreeMap<Address<N>, Leaf<P, H, N>> = BTreeMap::new();
+        for leaf in iter {
+            if !self.contains(&leaf.get_address()) {
+                leaves.entry(leaf.get_address()).or_insert(leaf);
+            }
         }
+        self.insert_many(leaves.into_values().collect());
+    }
+}
+
+/// Builds a `Trie` from the leaves yielded by `iter` by extending an empty
+/// one, so every `Branch` is hashed once. As with `Extend`, a `Leaf` whose
+/// address was yielded before is skipped.
+///
+/// # Example
+///
+/// ```
+/// use mmpt::trie::Trie;
+/// use mmpt::node::Leaf;
+///
+/// let leaves = vec![
+///     Leaf::new([0u8; 32], "Some Data".to_string()),
+///     Leaf::new([1u8; 32], "Some More Data".to_string()),
+/// ];
+/// let trie: Trie<String> = leaves.into_iter().collect();
+/// assert_eq!(trie.len(), 2);
+/// ```
+impl<P: Clone + Debug + Into<Vec<u8>>, H: Hasher, const N: usize> FromIterator<Leaf<P, H, N>> for Trie<P, H, N> {
+    fn from_iter<I: IntoIterator<Item = Leaf<P, H, N>>>(iter: I) -> Self {
+        let mut trie = Trie::with_hasher();
+        trie.extend(iter);
+        trie
     }
 }
 
-//TODO: Implement "Extend", "FromIterator", "ExactSizeIterator" for Trie
+// None of the iterators over a `Trie` implement `ExactSizeIterator`: a
+// `Trie` doesn't track how many leaves lie beneath each `Fork`, so their
+// `size_hint` is only a lower bound, see `leaves_left`.